@@ -0,0 +1,76 @@
+// Pinning of the parallel jobs to disjoint sets of CPUs with --pin-cpus, so their runs don't
+// migrate between CPUs and get in each other's way. Every process a job spawns inherits the
+// affinity of its thread.
+use std::io::{Error, Result as IOResult};
+
+// the CPUs we may run on, in ascending order
+pub fn available_cpus() -> IOResult<Vec<usize>> {
+    // safe because an all-zero cpu_set_t is a valid (empty) set and sched_getaffinity writes at
+    // most size_of::<cpu_set_t>() bytes to it
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok((0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| libc::CPU_ISSET(cpu, &set))
+            .collect())
+    }
+}
+
+// cpus split into jobs disjoint sets of consecutive CPUs, as equal in size as possible, None if
+// there are fewer CPUs than jobs
+pub fn partition(cpus: &[usize], jobs: usize) -> Option<Vec<Vec<usize>>> {
+    if jobs == 0 || cpus.len() < jobs {
+        return None;
+    }
+    let (size, larger) = (cpus.len() / jobs, cpus.len() % jobs);
+    let mut rest = cpus;
+    Some(
+        (0..jobs)
+            .map(|job| {
+                let (set, after) = rest.split_at(size + usize::from(job < larger));
+                rest = after;
+                set.to_vec()
+            })
+            .collect(),
+    )
+}
+
+// restrict the calling thread, and so everything it spawns from now on, to cpus
+pub fn pin_current_thread(cpus: &[usize]) -> IOResult<()> {
+    // safe because the set is initialized before it is passed on, and CPU_SET is only called
+    // with CPUs that came out of a set of the same size
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus.iter() {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// like 0-3,8,10-11
+pub fn format_cpus(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus.iter() {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
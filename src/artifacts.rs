@@ -0,0 +1,134 @@
+// Preservation of the proof directory contents of failed runs, so they can be inspected after
+// the benchmark is done.
+use crate::compression::{self, Compression};
+use std::fs;
+use std::io::Result as IOResult;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone)]
+pub struct FailureArtifacts {
+    pub directory: PathBuf,
+    // total size in bytes the artifact directory may take up, oldest artifacts get evicted first
+    pub max_size: Option<u64>,
+    // pack every artifact into a compressed tarball once it is copied
+    pub compression: Option<Compression>,
+}
+
+// parse a size like 1048576, 512K, 100M or 2G
+pub fn parse_size(arg: &str) -> Result<u64, String> {
+    let arg = arg.trim();
+    let (number, multiplier) = match arg.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&arg[..arg.len() - 1], 1 << 10),
+        Some('M') => (&arg[..arg.len() - 1], 1 << 20),
+        Some('G') => (&arg[..arg.len() - 1], 1 << 30),
+        _ => (arg, 1),
+    };
+    let number = number
+        .parse::<u64>()
+        .map_err(|err| format!("invalid size '{}': {}", arg, err))?;
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("invalid size '{}': too large", arg))
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> IOResult<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_recursively(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn disk_usage(path: &Path) -> IOResult<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+// copy the proof directory of a failed run into the artifact directory and return where it went
+pub fn preserve(
+    artifacts: &FailureArtifacts,
+    proof_dir: &Path,
+    proof_name: &str,
+    run_nr: u32,
+) -> IOResult<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+    let target = artifacts
+        .directory
+        .join(format!("{}-run{}-{}", proof_name, run_nr, timestamp));
+    copy_recursively(proof_dir, &target)?;
+    match artifacts.compression {
+        Some(compression) => compression::compress_directory(&target, compression),
+        None => Ok(target),
+    }
+}
+
+// delete the oldest preserved artifacts until the artifact directory fits into max_size again,
+// returning the evicted artifacts together with their sizes. The newest artifact is never evicted,
+// even if it alone is bigger than max_size.
+pub fn enforce_size_cap(artifacts: &FailureArtifacts) -> IOResult<Vec<(PathBuf, u64)>> {
+    let max_size = match artifacts.max_size {
+        Some(max_size) => max_size,
+        None => return Ok(Vec::new()),
+    };
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&artifacts.directory)? {
+        let entry = entry?;
+        let modified = entry.metadata()?.modified()?;
+        entries.push((modified, entry.path(), disk_usage(&entry.path())?));
+    }
+    entries.sort();
+    let mut total: u64 = entries.iter().map(|(_, _, size)| size).sum();
+    let mut evicted = Vec::new();
+    let nr_of_entries = entries.len();
+    for (_, path, size) in entries.into_iter().take(nr_of_entries.saturating_sub(1)) {
+        if total <= max_size {
+            break;
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
+        total -= size;
+        evicted.push((path, size));
+    }
+    Ok(evicted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("1048576"), Ok(1 << 20));
+        assert_eq!(parse_size("512K"), Ok(512 << 10));
+        assert_eq!(parse_size("100m"), Ok(100 << 20));
+        assert_eq!(parse_size(" 2G "), Ok(2 << 30));
+        assert!(parse_size("").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("-1K").is_err());
+        assert!(parse_size("1.5G").is_err());
+        assert_eq!(
+            parse_size("99999999999G"),
+            Err("invalid size '99999999999G': too large".to_string())
+        );
+    }
+}
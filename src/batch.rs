@@ -0,0 +1,226 @@
+// Running the proofs on AWS Batch with --aws-batch, every make invocation of a run as a job of its
+// own, submitted and waited for by this program (as the hidden batch-job subcommand) with the aws
+// CLI. The job definition has to mount the proofs at the same path as here (e.g. from EFS), since
+// the goto binaries of one job are the input of the next.
+use crate::progress::json_string;
+use crate::remote;
+use crate::GenericResult;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{Result as IOResult, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+use structopt::StructOpt;
+
+// the file the batch-job subcommand appends how long every job ran to, without its time in the
+// queue
+pub const BATCH_TIMES_VARIABLE: &str = "BENCH_BATCH_TIMES";
+
+// how often the status of a job is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+// where AWS Batch sends the output of its jobs
+const LOG_GROUP: &str = "/aws/batch/job";
+// the longest job name AWS Batch takes
+const MAX_JOB_NAME_LENGTH: usize = 128;
+
+// the queue to submit the jobs to and the job definition they run with
+#[derive(Clone)]
+pub struct BatchTarget {
+    pub queue: String,
+    pub job_definition: String,
+}
+
+impl FromStr for BatchTarget {
+    type Err = String;
+
+    // QUEUE:JOB_DEFINITION, where the job definition can have a :REVISION
+    fn from_str(arg: &str) -> Result<BatchTarget, String> {
+        match arg.split_once(':') {
+            Some((queue, job_definition)) if !queue.is_empty() && !job_definition.is_empty() => {
+                Ok(BatchTarget {
+                    queue: queue.to_string(),
+                    job_definition: job_definition.to_string(),
+                })
+            }
+            _ => Err(format!(
+                "invalid AWS Batch target '{}', expected QUEUE:JOB_DEFINITION",
+                arg
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BatchTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.queue, self.job_definition)
+    }
+}
+
+#[derive(StructOpt)]
+pub struct BatchJobArguments {
+    queue: String,
+    job_definition: String,
+    /// what the job is for, like the make target it builds
+    name: String,
+    /// the shell command the job runs
+    command: String,
+}
+
+// command submitted as a job named after the make target target by the batch-job subcommand
+pub fn command(batch: &BatchTarget, target: &str, command: &Command) -> IOResult<Command> {
+    let mut batch_job = Command::new(std::env::current_exe()?);
+    batch_job
+        .arg("batch-job")
+        .args([&batch.queue, &batch.job_definition, target])
+        .arg(remote::shell_command(command));
+    Ok(batch_job)
+}
+
+// the output of the aws CLI with arguments, which prints what its --query selects as text
+fn aws(arguments: &[&str]) -> GenericResult<String> {
+    let output = Command::new("aws")
+        .args(arguments)
+        .args(["--output", "text"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| format!("can't run the aws CLI: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "aws {} failed with {}",
+            arguments[..2].join(" "),
+            output.status
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// as far as describe-jobs tells, text shows missing values as None
+struct JobStatus {
+    status: String,
+    // in milliseconds since the epoch
+    started_at: Option<u64>,
+    stopped_at: Option<u64>,
+    exit_code: Option<i32>,
+    log_stream: Option<String>,
+}
+
+fn describe(job_id: &str) -> GenericResult<JobStatus> {
+    let fields = aws(&[
+        "batch",
+        "describe-jobs",
+        "--jobs",
+        job_id,
+        "--query",
+        "jobs[0].[status,startedAt,stoppedAt,container.exitCode,container.logStreamName]",
+    ])?;
+    let fields: Vec<&str> = fields.split('\t').collect();
+    let field = |index: usize| fields.get(index).cloned().filter(|field| *field != "None");
+    Ok(JobStatus {
+        status: field(0).unwrap_or_default().to_string(),
+        started_at: field(1).and_then(|field| field.parse().ok()),
+        stopped_at: field(2).and_then(|field| field.parse().ok()),
+        exit_code: field(3).and_then(|field| field.parse().ok()),
+        log_stream: field(4).map(String::from),
+    })
+}
+
+// submit the command as a job, wait for it, print its output, append how long it ran to the file
+// in BENCH_BATCH_TIMES and exit like it did
+pub fn batch_job(args: &BatchJobArguments) -> GenericResult<()> {
+    let job_name: String = format!("benchmark-{}", args.name)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(MAX_JOB_NAME_LENGTH)
+        .collect();
+    let overrides = format!(
+        "{{\"command\":[\"sh\",\"-c\",{}]}}",
+        json_string(&args.command)
+    );
+    let job_id = aws(&[
+        "batch",
+        "submit-job",
+        "--job-name",
+        &job_name,
+        "--job-queue",
+        &args.queue,
+        "--job-definition",
+        &args.job_definition,
+        "--container-overrides",
+        &overrides,
+        "--query",
+        "jobId",
+    ])?;
+    // in the log of the run, so a job left behind by a killed run can be found and terminated
+    println!("BATCH JOB {} submitted to {}", job_id, args.queue);
+    let job = loop {
+        let job = describe(&job_id)?;
+        if job.status == "SUCCEEDED" || job.status == "FAILED" {
+            break job;
+        }
+        sleep(POLL_INTERVAL);
+    };
+    if let Some(log_stream) = &job.log_stream {
+        // a row per event, so every message ends up on a line of its own
+        match aws(&[
+            "logs",
+            "get-log-events",
+            "--log-group-name",
+            LOG_GROUP,
+            "--log-stream-name",
+            log_stream,
+            "--start-from-head",
+            "--query",
+            "events[].[message]",
+        ]) {
+            Ok(output) if !output.is_empty() => println!("{}", output),
+            Ok(_) => {}
+            Err(err) => eprintln!("ERROR fetching the output of batch job {}: {}", job_id, err),
+        }
+    }
+    println!("BATCH JOB {} {}", job_id, job.status);
+    if let (Some(started_at), Some(stopped_at), Some(times_path)) = (
+        job.started_at,
+        job.stopped_at,
+        std::env::var_os(BATCH_TIMES_VARIABLE),
+    ) {
+        let runtime = stopped_at.saturating_sub(started_at) as f64 / 1000.0;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(times_path)?
+            .write_all(format!("{} {}\n", args.name, runtime).as_bytes())?;
+    }
+    std::process::exit(match (job.status.as_str(), job.exit_code) {
+        (_, Some(exit_code)) => exit_code,
+        ("SUCCEEDED", None) => 0,
+        (_, None) => 1,
+    })
+}
+
+// how long the jobs in a file written by batch-job ran, by their name, with lines like goto 1.25
+pub fn parse_file(path: &Path) -> IOResult<Vec<(String, Duration)>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let (name, seconds) = line.rsplit_once(' ')?;
+            let time = Duration::try_from_secs_f64(seconds.parse().ok()?).ok()?;
+            Some((name.to_string(), time))
+        })
+        .collect())
+}
+
+// where batch-job writes the times of the jobs of the run with run_id
+pub fn times_path(run_id: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("benchmark-batch-{}.txt", run_id))
+}
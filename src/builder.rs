@@ -0,0 +1,102 @@
+// The build tool driving a proof, detected per proof directory by the file that marks it (a
+// Makefile, build.ninja or CMake presets), since newer proof setups move off plain make.
+use std::path::Path;
+
+// in the command of a builder, replaced by the target to build; appended if it's missing
+const TARGET_PLACEHOLDER: &str = "{target}";
+
+// the builders every session knows, after those given with --builder
+const DEFAULT_BUILDERS: [(&str, &str); 3] = [
+    ("Makefile", "make"),
+    ("build.ninja", "ninja"),
+    (
+        "CMakePresets.json",
+        "cmake --build --preset default --target {target}",
+    ),
+];
+
+#[derive(Clone)]
+pub struct Builder {
+    // the file in a proof directory that selects this builder
+    pub marker: String,
+    // program and arguments
+    command: Vec<String>,
+}
+
+// MARKER=COMMAND
+pub fn parse_builder_arg(arg: &str) -> Result<Builder, String> {
+    match arg.split_once('=') {
+        Some((marker, command)) if !marker.is_empty() && !command.trim().is_empty() => {
+            Ok(Builder::new(marker, command))
+        }
+        _ => Err(format!(
+            "invalid builder '{}', expected MARKER=COMMAND (e.g. build.ninja=ninja)",
+            arg
+        )),
+    }
+}
+
+// the configured builders in the order they were given, then the defaults for the markers they
+// don't replace
+pub fn builders(configured: &[Builder]) -> Vec<Builder> {
+    let mut builders = configured.to_vec();
+    for (marker, command) in DEFAULT_BUILDERS.iter() {
+        if !builders.iter().any(|builder| builder.marker == *marker) {
+            builders.push(Builder::new(marker, command));
+        }
+    }
+    builders
+}
+
+// the first builder whose marker is in dir, None if dir isn't a proof directory
+pub fn detect<'a>(builders: &'a [Builder], dir: &Path) -> Option<&'a Builder> {
+    builders
+        .iter()
+        .find(|builder| dir.join(&builder.marker).exists())
+}
+
+impl Builder {
+    fn new(marker: &str, command: &str) -> Builder {
+        Builder {
+            marker: marker.to_string(),
+            command: command.split_whitespace().map(str::to_string).collect(),
+        }
+    }
+
+    pub fn program(&self) -> &str {
+        &self.command[0]
+    }
+
+    // make (or gmake) takes VARIABLE=value overrides on its command line
+    pub fn is_make(&self) -> bool {
+        let program = Path::new(self.program())
+            .file_name()
+            .map_or("".into(), |name| name.to_string_lossy());
+        program == "make" || program == "gmake"
+    }
+
+    // the arguments to build target with, -j in front of an appended target (make, ninja) or last
+    // if the command places the target itself (cmake --build --target)
+    pub fn arguments(&self, target: &str, jobs: Option<u32>) -> Vec<String> {
+        let jobs = jobs.map(|jobs| format!("-j{}", jobs));
+        let mut arguments: Vec<String> = self.command[1..]
+            .iter()
+            .map(|argument| argument.replace(TARGET_PLACEHOLDER, target))
+            .collect();
+        arguments.extend(jobs);
+        if !self
+            .command
+            .iter()
+            .any(|word| word.contains(TARGET_PLACEHOLDER))
+        {
+            arguments.push(target.to_string());
+        }
+        arguments
+    }
+}
+
+impl std::fmt::Display for Builder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} ({})", self.command.join(" "), self.marker)
+    }
+}
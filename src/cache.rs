@@ -0,0 +1,86 @@
+// The state of the page cache at the start of every measured run with --page-cache, so either all
+// runs of a proof read their files from disk or none do, instead of just the first one.
+use std::fmt;
+use std::fs;
+use std::io::Result as IOResult;
+use std::path::Path;
+use std::str::FromStr;
+
+const DROP_CACHES: &str = "/proc/sys/vm/drop_caches";
+
+#[derive(Clone, Copy)]
+pub enum PageCache {
+    // dropped, which takes root
+    Cold,
+    // holding the files of the proof
+    Warm,
+}
+
+impl FromStr for PageCache {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<PageCache, String> {
+        match arg {
+            "cold" => Ok(PageCache::Cold),
+            "warm" => Ok(PageCache::Warm),
+            _ => Err(format!(
+                "unknown page cache state '{}', expected cold or warm",
+                arg
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PageCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PageCache::Cold => write!(f, "cold"),
+            PageCache::Warm => write!(f, "warm"),
+        }
+    }
+}
+
+// fail early if we can't get the page cache into this state
+pub fn check(page_cache: PageCache) -> IOResult<()> {
+    match page_cache {
+        PageCache::Cold => fs::OpenOptions::new()
+            .write(true)
+            .open(DROP_CACHES)
+            .map(|_| ()),
+        PageCache::Warm => Ok(()),
+    }
+}
+
+// read every file below path, so they are all in the page cache, skipping those we can't read
+fn read_all(path: &Path) {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                read_all(&entry.path());
+            }
+        }
+    } else if metadata.is_file() {
+        if let Ok(mut file) = fs::File::open(path) {
+            let _ = std::io::copy(&mut file, &mut std::io::sink());
+        }
+    }
+}
+
+// get the page cache into the state for a run of a proof in source_root
+pub fn prepare(page_cache: PageCache, source_root: &Path) -> IOResult<()> {
+    match page_cache {
+        PageCache::Cold => {
+            // dirty pages can't be dropped, so write them out first
+            unsafe { libc::sync() };
+            fs::write(DROP_CACHES, "3")
+        }
+        PageCache::Warm => {
+            read_all(source_root);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// A small deterministic CPU and memory workload whose runtime serves as a rough performance
+// score for the machine, for normalizing runtimes across different hardware.
+use crate::stats::xorshift;
+use crate::{run_make, RunOptions};
+use std::hint::black_box;
+use std::io::Result as IOResult;
+use std::time::{Duration, Instant};
+
+// metadata key the calibration runtime gets recorded under
+pub const CALIBRATION_KEY: &str = "calibration";
+// metadata key the make overhead gets recorded under
+pub const MAKE_OVERHEAD_KEY: &str = "make_overhead";
+
+const MEMORY_WORDS: usize = 16 * 1024 * 1024;
+const CPU_ROUNDS: u64 = 50_000_000;
+const REPETITIONS: u32 = 3;
+const MAKE_OVERHEAD_REPETITIONS: u32 = 10;
+
+fn workload() -> u64 {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    // CPU bound part: integer arithmetic with a long dependency chain, which is roughly what
+    // a SAT solver spends its time on between memory accesses
+    let mut accumulator: u64 = 0;
+    for _ in 0..CPU_ROUNDS {
+        accumulator = accumulator
+            .wrapping_mul(31)
+            .wrapping_add(xorshift(&mut state) >> 3);
+    }
+    // memory bound part: random reads and writes in a buffer much bigger than the caches
+    let mut memory: Vec<u64> = (0..MEMORY_WORDS as u64).collect();
+    for _ in 0..MEMORY_WORDS {
+        let index = (xorshift(&mut state) as usize) % MEMORY_WORDS;
+        memory[index] = memory[index].wrapping_add(accumulator);
+        accumulator ^= memory[(accumulator as usize) % MEMORY_WORDS];
+    }
+    accumulator
+}
+
+// runtime of the calibration workload in seconds (the best of a few repetitions, to get rid of
+// as much noise as possible)
+pub fn calibrate() -> f64 {
+    (0..REPETITIONS)
+        .map(|_| {
+            let start_time = Instant::now();
+            black_box(workload());
+            start_time.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+// fixed cost of invoking make (process startup, parsing the Makefile) measured on an empty target,
+// the best of a few repetitions so we never subtract more than the actual overhead
+pub fn make_overhead(options: &RunOptions) -> IOResult<Duration> {
+    let directory =
+        std::env::temp_dir().join(format!("benchmark_make_overhead-{}", std::process::id()));
+    std::fs::create_dir_all(&directory)?;
+    let overhead = std::fs::write(directory.join("Makefile"), ".PHONY: overhead\noverhead:\n")
+        .and_then(|_| {
+            let mut best = Duration::MAX;
+            for _ in 0..MAKE_OVERHEAD_REPETITIONS {
+                let start_time = Instant::now();
+                run_make("overhead", &directory, options, None, None, None)?;
+                best = best.min(start_time.elapsed());
+            }
+            Ok(best)
+        });
+    std::fs::remove_dir_all(&directory)?;
+    overhead
+}
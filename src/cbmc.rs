@@ -0,0 +1,159 @@
+// Statistics CBMC prints about its verification runs, parsed from the captured output of a run.
+use std::io::Result as IOResult;
+use std::path::Path;
+use std::time::Duration;
+
+// summed over all CBMC invocations in the output, None if CBMC didn't print it at all
+#[derive(Clone, Default)]
+pub struct CbmcStats {
+    // verification conditions generated, and left after simplification
+    pub vccs: Option<u64>,
+    pub remaining_vccs: Option<u64>,
+    // size of the program expression after symbolic execution
+    pub program_steps: Option<u64>,
+    pub symex_time: Option<Duration>,
+    pub decision_procedure_time: Option<Duration>,
+    // hash of the property results and verification verdicts, which should be the same for every
+    // run of a proof
+    pub property_hash: Option<u64>,
+}
+
+fn add<T: std::ops::Add<Output = T>>(total: &mut Option<T>, value: Option<T>) {
+    if let Some(value) = value {
+        *total = Some(match total.take() {
+            Some(sum) => sum + value,
+            None => value,
+        });
+    }
+}
+
+// a time like 1.234s
+fn parse_seconds(time: &str) -> Option<Duration> {
+    let seconds: f64 = time.trim().strip_suffix('s')?.parse().ok()?;
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+// a property result like [main.assertion.1] line 12 assertion x > 0: SUCCESS, or a verdict like
+// VERIFICATION SUCCESSFUL
+fn is_result(line: &str) -> bool {
+    if line.starts_with("VERIFICATION ") {
+        return true;
+    }
+    match line.rsplit_once(": ") {
+        Some((property, status)) => {
+            property.starts_with('[')
+                && !status.is_empty()
+                && status.chars().all(|c| c.is_ascii_uppercase() || c == '-')
+        }
+        None => false,
+    }
+}
+
+// 64 bit FNV-1a, which unlike the std hashers is stable across Rust versions and so can be
+// compared across sessions
+fn fnv1a(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub fn parse(output: &str) -> CbmcStats {
+    let mut stats = CbmcStats::default();
+    let mut results = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if is_result(line) {
+            results.push(line);
+        } else if let Some(vccs) = line.strip_prefix("Generated ") {
+            // Generated 12 VCC(s), 3 remaining after simplification
+            let mut counts = vccs.split(", ");
+            add(
+                &mut stats.vccs,
+                counts
+                    .next()
+                    .and_then(|count| count.strip_suffix(" VCC(s)")?.parse().ok()),
+            );
+            add(
+                &mut stats.remaining_vccs,
+                counts.next().and_then(|count| {
+                    count
+                        .strip_suffix(" remaining after simplification")?
+                        .parse()
+                        .ok()
+                }),
+            );
+        } else if let Some(size) = line.strip_prefix("size of program expression: ") {
+            add(
+                &mut stats.program_steps,
+                size.strip_suffix(" steps")
+                    .and_then(|steps| steps.parse().ok()),
+            );
+        } else if let Some(time) = line.strip_prefix("Runtime Symex: ") {
+            add(&mut stats.symex_time, parse_seconds(time));
+        } else if let Some(time) = line.strip_prefix("Runtime decision procedure: ") {
+            add(&mut stats.decision_procedure_time, parse_seconds(time));
+        }
+    }
+    if !results.is_empty() {
+        // with make -j the invocations of CBMC can finish in any order
+        results.sort_unstable();
+        stats.property_hash =
+            Some(fnv1a(results.iter().flat_map(|result| {
+                result.bytes().chain(std::iter::once(b'\n'))
+            })));
+    }
+    stats
+}
+
+// the statistics in a (not yet compressed) log file of a run
+pub fn parse_log(log_path: &Path) -> IOResult<CbmcStats> {
+    let output = std::fs::read(log_path)?;
+    Ok(parse(&String::from_utf8_lossy(&output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_statistics() {
+        let output = "\
+Generated 12 VCC(s), 3 remaining after simplification
+size of program expression: 100 steps
+Runtime Symex: 0.5s
+Runtime decision procedure: 1.25s
+[main.assertion.1] line 12 assertion x > 0: SUCCESS
+VERIFICATION SUCCESSFUL
+Generated 8 VCC(s), 2 remaining after simplification
+size of program expression: 50 steps
+Runtime Symex: 0.25s
+Runtime decision procedure: bogus
+[other.assertion.1] line 3 assertion y: FAILURE
+VERIFICATION FAILED
+";
+        let stats = parse(output);
+        assert_eq!(stats.vccs, Some(20));
+        assert_eq!(stats.remaining_vccs, Some(5));
+        assert_eq!(stats.program_steps, Some(150));
+        assert_eq!(stats.symex_time, Some(Duration::from_millis(750)));
+        assert_eq!(
+            stats.decision_procedure_time,
+            Some(Duration::from_millis(1250))
+        );
+        assert!(stats.property_hash.is_some());
+
+        // the results of parallel invocations can come in any order
+        let mut reordered: Vec<&str> = output.lines().collect();
+        reordered.rotate_left(6);
+        assert_eq!(
+            parse(&reordered.join("\n")).property_hash,
+            stats.property_hash
+        );
+        let changed = output.replace(": FAILURE", ": SUCCESS");
+        assert_ne!(parse(&changed).property_hash, stats.property_hash);
+
+        let empty = parse("nothing to see here\n");
+        assert_eq!(empty.vccs, None);
+        assert_eq!(empty.symex_time, None);
+        assert_eq!(empty.property_hash, None);
+    }
+}
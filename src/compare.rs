@@ -0,0 +1,445 @@
+// Comparison of two result files without re-running anything.
+use crate::names::NameMap;
+use crate::progress::json_string;
+use crate::results::{read_results, ProofResults, ResultFile, RunMetadata};
+use crate::stats::{self, mann_whitney_u, Statistic};
+use crate::system;
+use crate::GenericResult;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+// how compare prints the comparison
+#[derive(Clone, Copy)]
+pub enum Format {
+    // tables for humans
+    Table,
+    // a single JSON object with everything in the tables, for bots
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Format, String> {
+        match arg {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("unknown format '{}', expected table or json", arg)),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+pub struct CompareArguments {
+    #[structopt(parse(from_os_str))]
+    old_csv: PathBuf,
+    #[structopt(parse(from_os_str))]
+    new_csv: PathBuf,
+    /// only report runtime changes whose Mann-Whitney U test p-value is below this level
+    #[structopt(long, default_value = "0.05")]
+    significance: f64,
+    /// metadata key holding a per-machine calibration runtime in seconds, new runtimes are scaled
+    /// by old calibration / new calibration to roughly account for different hardware
+    #[structopt(long)]
+    normalize_by: Option<String>,
+    /// how to summarize the runtimes of each proof: mean, median, min or best-of-N
+    #[structopt(long, default_value = "mean")]
+    statistic: Statistic,
+    /// file with lines of raw-name,display-name[,group] giving proofs readable names
+    #[structopt(long, parse(from_os_str))]
+    name_map: Option<PathBuf>,
+    /// table, or json to print the whole comparison (the metadata and the old and new statistics,
+    /// difference, p-value and status of every proof) as a single JSON object
+    #[structopt(long, default_value = "table")]
+    format: Format,
+}
+
+fn calibration(results: &ResultFile, key: &str, label: &str) -> GenericResult<f64> {
+    let value = results
+        .metadata
+        .get(key)
+        .ok_or_else(|| format!("{} results have no '{}' metadata", label, key))?;
+    let calibration: f64 = value.parse().map_err(|err| {
+        format!(
+            "{} results have invalid calibration {}={}: {}",
+            label, key, value, err
+        )
+    })?;
+    if calibration <= 0.0 {
+        return Err(format!("{} results have non-positive calibration {}", label, key).into());
+    }
+    Ok(calibration)
+}
+
+fn format_runtime(runtime: Option<f64>) -> String {
+    match runtime {
+        Some(seconds) => format!("{:.3}", seconds),
+        None => "-".to_string(),
+    }
+}
+
+// status of a proof in the new results relative to the old ones, runtime changes are only
+// reported if their p_value is below the significance level
+pub fn proof_status(
+    old: Option<&ProofResults>,
+    new: Option<&ProofResults>,
+    p_value: Option<f64>,
+    significance: f64,
+    statistic: Statistic,
+) -> &'static str {
+    match (old, new) {
+        (Some(_), None) => "REMOVED",
+        (None, Some(_)) => "ADDED",
+        (Some(old), Some(new)) if old.failed_runs() == 0 && new.failed_runs() > 0 => "NEW FAILURE",
+        (Some(old), Some(new)) if old.failed_runs() > 0 && new.failed_runs() == 0 => "FIXED",
+        (Some(old), Some(new)) if matches!(p_value, Some(p) if p < significance) => {
+            if new.runtime(statistic) > old.runtime(statistic) {
+                "REGRESSION"
+            } else {
+                "IMPROVEMENT"
+            }
+        }
+        _ => "",
+    }
+}
+
+pub fn compare_result_files(args: &CompareArguments) -> GenericResult<()> {
+    let significance = args.significance;
+    let mut old_results = read_results(&args.old_csv)?;
+    let mut new_results = read_results(&args.new_csv)?;
+    if let Some(name_map) = &args.name_map {
+        let names = NameMap::load(name_map)?;
+        names.rename(&mut old_results.proofs);
+        names.rename(&mut new_results.proofs);
+    }
+    let mut proofs: BTreeMap<&str, (Option<&ProofResults>, Option<&ProofResults>)> =
+        BTreeMap::new();
+    let table = matches!(args.format, Format::Table);
+    if table {
+        for (label, results) in [("OLD", &old_results), ("NEW", &new_results)].iter() {
+            if !results.metadata.is_empty() {
+                println!("{}:", label);
+                results.metadata.print("  ");
+            }
+        }
+    }
+    let mut warnings = Vec::new();
+    match (
+        old_results.metadata.get("host"),
+        new_results.metadata.get("host"),
+    ) {
+        (Some(old_host), Some(new_host)) if old_host != new_host => warnings.push(format!(
+            "comparing results from different machines ({} vs {}){}",
+            old_host,
+            new_host,
+            if args.normalize_by.is_some() {
+                ", runtimes are only roughly comparable even with normalization"
+            } else {
+                ", consider --normalize-by"
+            }
+        )),
+        _ => {}
+    }
+    for key in system::SYSTEM_KEYS.iter() {
+        if let (Some(old_value), Some(new_value)) =
+            (old_results.metadata.get(key), new_results.metadata.get(key))
+        {
+            if old_value != new_value {
+                warnings.push(format!(
+                    "the results differ in {} ({} vs {})",
+                    key, old_value, new_value
+                ));
+            }
+        }
+    }
+    if table {
+        for warning in warnings.iter() {
+            println!("WARNING: {}", warning);
+        }
+    }
+    let mut normalization = None;
+    if let Some(key) = &args.normalize_by {
+        let factor =
+            calibration(&old_results, key, "OLD")? / calibration(&new_results, key, "NEW")?;
+        if table {
+            println!(
+                "NORMALIZED new runtimes by a factor of {:.4} ({})",
+                factor, key
+            );
+        }
+        new_results.proofs = new_results
+            .proofs
+            .iter()
+            .map(|proof| proof.scaled(factor))
+            .collect();
+        normalization = Some(factor);
+    }
+    for old in old_results.proofs.iter() {
+        proofs.entry(&old.name).or_default().0 = Some(old);
+    }
+    for new in new_results.proofs.iter() {
+        proofs.entry(&new.name).or_default().1 = Some(new);
+    }
+    if !table {
+        let comparison = JsonComparison {
+            args,
+            old: &old_results,
+            new: &new_results,
+            warnings: &warnings,
+            normalization,
+            proofs: &proofs,
+        };
+        println!("{}", comparison.to_json());
+        return Ok(());
+    }
+
+    let name_width = proofs
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!("COMPARING {} runtimes", args.statistic);
+    println!(
+        "{:<name_width$} {:>10} {:>10} {:>10} {:>9} {:>8}  STATUS",
+        "PROOF",
+        "OLD(s)",
+        "NEW(s)",
+        "DIFF(s)",
+        "DIFF(%)",
+        "P",
+        name_width = name_width
+    );
+    for (name, (old, new)) in proofs.iter() {
+        let old_runtime = old.and_then(|old| old.runtime(args.statistic));
+        let new_runtime = new.and_then(|new| new.runtime(args.statistic));
+        let (diff, relative_diff) = match (old_runtime, new_runtime) {
+            (Some(old_runtime), Some(new_runtime)) => (
+                format!("{:+.3}", new_runtime - old_runtime),
+                if old_runtime > 0.0 {
+                    format!("{:+.1}", 100.0 * (new_runtime - old_runtime) / old_runtime)
+                } else {
+                    "-".to_string()
+                },
+            ),
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        let p_value = match (old, new) {
+            (Some(old), Some(new)) => mann_whitney_u(&old.runtimes_secs(), &new.runtimes_secs()),
+            _ => None,
+        };
+        let has_suspect_runs = old
+            .iter()
+            .chain(new.iter())
+            .any(|proof| !proof.suspect_runs.is_empty());
+        println!(
+            "{:<name_width$} {:>10} {:>10} {:>10} {:>9} {:>8}  {}{}",
+            name,
+            format_runtime(old_runtime),
+            format_runtime(new_runtime),
+            diff,
+            relative_diff,
+            p_value.map_or("-".to_string(), |p| format!("{:.3}", p)),
+            proof_status(*old, *new, p_value, significance, args.statistic),
+            if has_suspect_runs {
+                " (SUSPECT RUNS)"
+            } else {
+                ""
+            },
+            name_width = name_width
+        );
+    }
+    compare_phase(
+        &proofs,
+        &format!("{} build", args.statistic),
+        name_width,
+        |proof| proof.build_time(args.statistic),
+    );
+    compare_phase(
+        &proofs,
+        &format!("{} solver", args.statistic),
+        name_width,
+        |proof| proof.solver_time(args.statistic),
+    );
+    compare_phase(
+        &proofs,
+        &format!("{} goto-cc", args.statistic),
+        name_width,
+        |proof| proof.goto_time(args.statistic),
+    );
+    compare_phase(
+        &proofs,
+        &format!("{} cbmc", args.statistic),
+        name_width,
+        |proof| proof.cbmc_time(args.statistic),
+    );
+    Ok(())
+}
+
+// table of the summarized time of a phase of the runs (like mean build) in the old and new
+// results, if either of them has it
+fn compare_phase(
+    proofs: &BTreeMap<&str, (Option<&ProofResults>, Option<&ProofResults>)>,
+    phase: &str,
+    name_width: usize,
+    time: impl Fn(&ProofResults) -> Option<f64>,
+) {
+    let has_times = proofs.values().any(|(old, new)| {
+        old.iter()
+            .chain(new.iter())
+            .any(|proof| time(proof).is_some())
+    });
+    if !has_times {
+        return;
+    }
+    println!("COMPARING {} times", phase);
+    println!(
+        "{:<name_width$} {:>10} {:>10} {:>10} {:>9}",
+        "PROOF",
+        "OLD(s)",
+        "NEW(s)",
+        "DIFF(s)",
+        "DIFF(%)",
+        name_width = name_width
+    );
+    for (name, (old, new)) in proofs.iter() {
+        let old_time = old.and_then(&time);
+        let new_time = new.and_then(&time);
+        let (diff, relative_diff) = match (old_time, new_time) {
+            (Some(old_time), Some(new_time)) if old_time > 0.0 => (
+                format!("{:+.3}", new_time - old_time),
+                format!("{:+.1}", 100.0 * (new_time - old_time) / old_time),
+            ),
+            _ => ("-".to_string(), "-".to_string()),
+        };
+        println!(
+            "{:<name_width$} {:>10} {:>10} {:>10} {:>9}",
+            name,
+            format_runtime(old_time),
+            format_runtime(new_time),
+            diff,
+            relative_diff,
+            name_width = name_width
+        );
+    }
+}
+
+fn json_number(value: Option<f64>) -> String {
+    match value {
+        Some(value) if value.is_finite() => value.to_string(),
+        _ => "null".to_string(),
+    }
+}
+
+fn json_metadata(file: &Path, metadata: &RunMetadata) -> String {
+    let meta: Vec<String> = metadata
+        .meta
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+        .collect();
+    format!(
+        "{{\"file\":{},\"note\":{},\"meta\":{{{}}}}}",
+        json_string(&file.display().to_string()),
+        metadata
+            .note
+            .as_ref()
+            .map_or("null".to_string(), |note| json_string(note)),
+        meta.join(",")
+    )
+}
+
+// the statistics of one side of the comparison of a proof, times in seconds
+fn json_proof_stats(proof: &ProofResults, statistic: Statistic) -> String {
+    let runtimes = proof.runtimes_secs();
+    let summary =
+        |summarize: fn(&[f64]) -> f64| (!runtimes.is_empty()).then(|| summarize(&runtimes));
+    format!(
+        "{{\"runtime\":{},\"mean\":{},\"median\":{},\"min\":{},\"standard_deviation\":{},\"runs\":{},\"successful_runs\":{},\"failed_runs\":{},\"suspect_runs\":{},\"build_time\":{},\"solver_time\":{},\"goto_time\":{},\"cbmc_time\":{}}}",
+        json_number(proof.runtime(statistic)),
+        json_number(summary(stats::mean)),
+        json_number(summary(stats::median)),
+        json_number(runtimes.iter().copied().reduce(f64::min)),
+        json_number(
+            (runtimes.len() > 1).then(|| stats::sample_standard_deviation(&runtimes))
+        ),
+        proof.runs.len(),
+        runtimes.len(),
+        proof.failed_runs(),
+        proof.suspect_runs.len(),
+        json_number(proof.build_time(statistic)),
+        json_number(proof.solver_time(statistic)),
+        json_number(proof.goto_time(statistic)),
+        json_number(proof.cbmc_time(statistic))
+    )
+}
+
+// everything compare prints as tables, for compare --format json
+struct JsonComparison<'a> {
+    args: &'a CompareArguments,
+    old: &'a ResultFile,
+    new: &'a ResultFile,
+    warnings: &'a [String],
+    // the factor the new runtimes were scaled by with --normalize-by
+    normalization: Option<f64>,
+    proofs: &'a BTreeMap<&'a str, (Option<&'a ProofResults>, Option<&'a ProofResults>)>,
+}
+
+impl JsonComparison<'_> {
+    fn to_json(&self) -> String {
+        let statistic = self.args.statistic;
+        let proofs: Vec<String> = self
+            .proofs
+            .iter()
+            .map(|(name, (old, new))| {
+                let old_runtime = old.and_then(|old| old.runtime(statistic));
+                let new_runtime = new.and_then(|new| new.runtime(statistic));
+                let diff = match (old_runtime, new_runtime) {
+                    (Some(old_runtime), Some(new_runtime)) => Some(new_runtime - old_runtime),
+                    _ => None,
+                };
+                let relative_diff = match (diff, old_runtime) {
+                    (Some(diff), Some(old_runtime)) if old_runtime > 0.0 => {
+                        Some(100.0 * diff / old_runtime)
+                    }
+                    _ => None,
+                };
+                let p_value = match (old, new) {
+                    (Some(old), Some(new)) => {
+                        mann_whitney_u(&old.runtimes_secs(), &new.runtimes_secs())
+                    }
+                    _ => None,
+                };
+                let status =
+                    proof_status(*old, *new, p_value, self.args.significance, statistic);
+                format!(
+                    "    {{\"name\":{},\"status\":\"{}\",\"significant\":{},\"old\":{},\"new\":{},\"diff\":{},\"diff_percent\":{},\"p_value\":{}}}",
+                    json_string(name),
+                    if status.is_empty() { "UNCHANGED" } else { status },
+                    matches!(p_value, Some(p) if p < self.args.significance),
+                    old.map_or("null".to_string(), |old| json_proof_stats(old, statistic)),
+                    new.map_or("null".to_string(), |new| json_proof_stats(new, statistic)),
+                    json_number(diff),
+                    json_number(relative_diff),
+                    json_number(p_value)
+                )
+            })
+            .collect();
+        let warnings: Vec<String> = self
+            .warnings
+            .iter()
+            .map(|warning| json_string(warning))
+            .collect();
+        format!(
+            "{{\n  \"statistic\":\"{}\",\n  \"significance\":{},\n  \"normalization\":{},\n  \"old\":{},\n  \"new\":{},\n  \"warnings\":[{}],\n  \"proofs\":[\n{}\n  ]\n}}",
+            statistic,
+            json_number(Some(self.args.significance)),
+            json_number(self.normalization),
+            json_metadata(&self.args.old_csv, &self.old.metadata),
+            json_metadata(&self.args.new_csv, &self.new.metadata),
+            warnings.join(","),
+            proofs.join(",\n")
+        )
+    }
+}
@@ -0,0 +1,148 @@
+// Compression of artifacts and result files with the gzip or zstd command line tools.
+use std::fmt;
+use std::io::{Error, ErrorKind, Result as IOResult};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+#[derive(Clone, Copy)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Compression, String> {
+        match arg {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(format!(
+                "unknown compression '{}', expected gzip or zstd",
+                arg
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.program())
+    }
+}
+
+impl Compression {
+    fn program(&self) -> &'static str {
+        match *self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+// the compression of a file going by its extension, None for uncompressed files
+pub fn detect(path: &Path) -> Option<Compression> {
+    match path.extension()?.to_str()? {
+        "gz" => Some(Compression::Gzip),
+        "zst" => Some(Compression::Zstd),
+        _ => None,
+    }
+}
+
+fn with_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+fn run(command: &mut Command) -> IOResult<()> {
+    let status = command.stdin(Stdio::null()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::other(format!(
+            "{:?} failed with {}",
+            command, status
+        )))
+    }
+}
+
+// replace the file at path by a compressed path.gz or path.zst and return its path
+pub fn compress_file(path: &Path, compression: Compression) -> IOResult<PathBuf> {
+    let mut command = Command::new(compression.program());
+    match compression {
+        Compression::Gzip => command.arg("-f"),
+        Compression::Zstd => command.args(["-q", "-f", "--rm"]),
+    };
+    run(command.arg(path))?;
+    Ok(with_extension(path, compression.extension()))
+}
+
+// replace directory by a compressed tarball directory.tar.gz or directory.tar.zst and return its
+// path
+pub fn compress_directory(directory: &Path, compression: Compression) -> IOResult<PathBuf> {
+    let tarball = with_extension(directory, &format!("tar.{}", compression.extension()));
+    let name = directory
+        .file_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "directory has no name"))?;
+    let mut command = Command::new("tar");
+    if let Some(parent) = directory
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        command.arg("-C").arg(parent);
+    }
+    run(command
+        .arg(format!("--use-compress-program={}", compression.program()))
+        .arg("-cf")
+        .arg(&tarball)
+        .arg(name))?;
+    std::fs::remove_dir_all(directory)?;
+    Ok(tarball)
+}
+
+// the compression of the contents of a file going by its magic number, which unlike the extension
+// survives renaming the file
+fn detect_contents(contents: &[u8]) -> Option<Compression> {
+    if contents.starts_with(&[0x1f, 0x8b]) {
+        Some(Compression::Gzip)
+    } else if contents.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Some(Compression::Zstd)
+    } else {
+        None
+    }
+}
+
+// the contents of a file, decompressed first if it is compressed with gzip or zstd
+pub fn read(path: &Path) -> IOResult<Vec<u8>> {
+    let contents = std::fs::read(path)?;
+    let compression = match detect_contents(&contents) {
+        Some(compression) => compression,
+        None => return Ok(contents),
+    };
+    let output = Command::new(compression.program())
+        .arg("-dc")
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "decompressing {} failed: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,74 @@
+// Progress output of the coordinator loop, written to stdout by a separate thread so a slow
+// terminal or pipe never holds up the bookkeeping of runs.
+use crossbeam_channel::{bounded, Sender};
+use std::io::{BufWriter, Write};
+use std::thread::JoinHandle;
+
+// how many lines can be waiting to be written before printing blocks
+const QUEUED_LINES: usize = 1024;
+
+struct Line {
+    text: String,
+    // write this out right away instead of together with the lines after it
+    urgent: bool,
+}
+
+pub struct Reporter {
+    sender: Option<Sender<Line>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Reporter {
+    pub fn start() -> Reporter {
+        let (sender, receiver) = bounded::<Line>(QUEUED_LINES);
+        let thread = std::thread::spawn(move || {
+            let stdout = std::io::stdout();
+            for line in receiver.iter() {
+                // we only hold the lock while writing out what is queued up, other threads get to
+                // print in between
+                let mut out = BufWriter::new(stdout.lock());
+                let mut next = Some(line);
+                while let Some(line) = next {
+                    // there is nothing sensible we can do when stdout is gone
+                    let _ = writeln!(out, "{}", line.text);
+                    if line.urgent {
+                        let _ = out.flush();
+                    }
+                    next = receiver.try_recv().ok();
+                }
+                let _ = out.flush();
+            }
+        });
+        Reporter {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    fn send(&self, text: String, urgent: bool) {
+        self.sender
+            .as_ref()
+            .expect("the sender is only taken when dropping the reporter")
+            .send(Line { text, urgent })
+            .expect("the reporter thread lives as long as the reporter");
+    }
+
+    pub fn print(&self, text: String) {
+        self.send(text, false);
+    }
+
+    // for failures, which must not get stuck in a buffer if we crash right after
+    pub fn print_urgent(&self, text: String) {
+        self.send(text, true);
+    }
+}
+
+// wait for everything to be written, also when unwinding from a panic
+impl Drop for Reporter {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
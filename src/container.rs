@@ -0,0 +1,97 @@
+// Running every make invocation of a run in a fresh container with --container, for the same
+// toolchain on every machine and with CPU and memory limits that keep a runaway proof from taking
+// down the host. The git checkout of the proof is mounted at the same path, so its Makefiles find
+// everything they include.
+use crate::sandbox;
+use std::io::Result as IOResult;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// numbers the containers of a session, a run can start several
+static CONTAINER_COUNT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone)]
+pub struct Container {
+    pub image: String,
+    // docker or podman, or anything that takes their run options
+    pub runtime: String,
+    // how many CPUs the container may keep busy
+    pub cpus: Option<f64>,
+    pub memory_bytes: Option<u64>,
+}
+
+impl Container {
+    fn is_podman(&self) -> bool {
+        Path::new(&self.runtime)
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with("podman"))
+    }
+}
+
+// a name for a new container, after the run it is for
+fn new_name(run_id: Option<&str>) -> String {
+    let number = CONTAINER_COUNT.fetch_add(1, Ordering::SeqCst);
+    match run_id {
+        Some(run_id) => format!("bench-{}-{}", run_id, number),
+        None => format!("bench-{}-{}", std::process::id(), number),
+    }
+}
+
+// command run in a container of the image instead, in the same directory with the same
+// environment variables and without network access if no_network is set, together with the
+// command that kills and removes the container. Killing the container runtime client leaves the
+// container running, so a killed run has to do both
+pub fn command(
+    container: &Container,
+    command: &Command,
+    no_network: bool,
+    run_id: Option<&str>,
+) -> IOResult<(Command, Command)> {
+    let directory = command
+        .get_current_dir()
+        .expect("make runs in the proof directory")
+        .canonicalize()?;
+    let source_root = sandbox::source_root(&directory)?
+        .to_string_lossy()
+        .to_string();
+    let name = new_name(run_id);
+    let mut run = Command::new(&container.runtime);
+    run.args(["run", "--rm", "--init", "--name", &name])
+        .args(["--volume", &format!("{}:{}", source_root, source_root)])
+        .arg("--workdir")
+        .arg(&directory);
+    // so the build outputs belong to us and can be cleaned up without root
+    let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+    run.args(["--user", &format!("{}:{}", uid, gid)]);
+    if container.is_podman() {
+        // rootless podman would map our user to a subordinate one otherwise
+        run.arg("--userns=keep-id");
+    }
+    if let Some(cpus) = container.cpus {
+        run.args(["--cpus", &cpus.to_string()]);
+    }
+    if let Some(memory_bytes) = container.memory_bytes {
+        // without swap on top, so the limit is the limit
+        run.args(["--memory", &memory_bytes.to_string()])
+            .args(["--memory-swap", &memory_bytes.to_string()]);
+    }
+    if no_network {
+        run.args(["--network", "none"]);
+    }
+    for (variable, value) in command.get_envs() {
+        if let Some(value) = value {
+            run.arg("--env").arg(format!(
+                "{}={}",
+                variable.to_string_lossy(),
+                value.to_string_lossy()
+            ));
+        }
+    }
+    run.arg(&container.image)
+        .arg(command.get_program())
+        .args(command.get_args());
+    let mut kill = Command::new(&container.runtime);
+    kill.args(["rm", "--force", &name]);
+    Ok((run, kill))
+}
@@ -0,0 +1,66 @@
+// Energy used by the CPU packages of the whole machine, from the RAPL counters Linux exposes
+// through powercap.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const POWERCAP_DIR: &str = "/sys/class/powercap";
+
+// the energy counter of every CPU package in microjoules, together with the value it wraps
+// around at
+pub struct EnergyCounters(Vec<(u64, u64)>);
+
+fn read_number(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// the package zones, which include their core and uncore subzones; other top level zones like
+// psys would count the same energy again
+fn package_zones() -> Option<Vec<PathBuf>> {
+    let mut zones: Vec<PathBuf> = fs::read_dir(POWERCAP_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|zone| {
+            fs::read_to_string(zone.join("name"))
+                .is_ok_and(|name| name.trim().starts_with("package"))
+        })
+        .collect();
+    zones.sort();
+    Some(zones).filter(|zones| !zones.is_empty())
+}
+
+// None without RAPL support or if the counters aren't readable, which on many systems they are
+// only for root
+pub fn sample() -> Option<EnergyCounters> {
+    package_zones()?
+        .iter()
+        .map(|zone| {
+            Some((
+                read_number(&zone.join("energy_uj"))?,
+                read_number(&zone.join("max_energy_range_uj"))?,
+            ))
+        })
+        .collect::<Option<_>>()
+        .map(EnergyCounters)
+}
+
+impl EnergyCounters {
+    // the joules used since earlier, assuming no counter wrapped around more than once
+    pub fn joules_since(&self, earlier: &EnergyCounters) -> Option<f64> {
+        if self.0.len() != earlier.0.len() {
+            return None;
+        }
+        let microjoules: u64 = self
+            .0
+            .iter()
+            .zip(earlier.0.iter())
+            .map(|(&(now, range), &(before, _))| {
+                if now >= before {
+                    now - before
+                } else {
+                    range - before + now
+                }
+            })
+            .sum();
+        Some(microjoules as f64 / 1e6)
+    }
+}
@@ -0,0 +1,157 @@
+// Facts about the machine that influence runtimes, sampled at the start, in the middle and at the
+// end of a session to notice when the early and the late proofs weren't measured under the same
+// conditions.
+use crate::monitor;
+use crate::results::cpu_governor;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::Duration;
+
+// samples of the competing processes we average over, since they come and go
+const LOAD_SAMPLES: u32 = 10;
+const LOAD_SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+
+// how much the facts may differ between samples before we warn about them
+const COMPETING_PROCESSES_DRIFT: f64 = 1.0;
+// as a fraction of the total memory
+const MEMORY_DRIFT: f64 = 0.1;
+// as a fraction of the most free disk space seen
+const DISK_DRIFT: f64 = 0.25;
+
+pub struct Environment {
+    // runnable processes that aren't ours, competing with our runs for the CPUs
+    pub competing_processes: f64,
+    // memory available to other processes, counting the memory our own processes take up as
+    // available
+    pub available_memory_kib: Option<u64>,
+    pub total_memory_kib: Option<u64>,
+    // free space on the file system the proofs are built on
+    pub free_disk_bytes: Option<u64>,
+    pub cpu_governor: Option<String>,
+}
+
+// a field of /proc/meminfo in KiB
+pub fn meminfo_field(meminfo: &str, name: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn free_disk_bytes(directory: &Path) -> Option<u64> {
+    let path = CString::new(directory.as_os_str().as_bytes()).ok()?;
+    // all zeroes is a valid statvfs, it only consists of integers
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // safe because path is NUL terminated and stat is valid for writes for the duration of the call
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+// sample the environment, with directory on the file system the proofs are built on
+pub fn sample(directory: &Path) -> Environment {
+    let mut competing_processes = 0;
+    let mut own_memory_kib = 0;
+    for sample in 0..LOAD_SAMPLES {
+        if sample > 0 {
+            std::thread::sleep(LOAD_SAMPLE_INTERVAL);
+        }
+        let (runnable, memory_kib) = monitor::outside_tree(std::process::id());
+        competing_processes += runnable;
+        own_memory_kib = memory_kib;
+    }
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    Environment {
+        competing_processes: competing_processes as f64 / LOAD_SAMPLES as f64,
+        available_memory_kib: meminfo_field(&meminfo, "MemAvailable")
+            .map(|available| available + own_memory_kib),
+        total_memory_kib: meminfo_field(&meminfo, "MemTotal"),
+        free_disk_bytes: free_disk_bytes(directory),
+        cpu_governor: cpu_governor(),
+    }
+}
+
+fn spread<T: Copy + PartialOrd>(values: impl Iterator<Item = T>) -> Option<(T, T)> {
+    values.fold(None, |range, value| match range {
+        None => Some((value, value)),
+        Some((min, max)) => Some((
+            if value < min { value } else { min },
+            if value > max { value } else { max },
+        )),
+    })
+}
+
+fn gib(bytes: u64) -> String {
+    format!("{:.1}GiB", bytes as f64 / (1u64 << 30) as f64)
+}
+
+// the facts that drifted significantly across the named samples, each with its value in every
+// sample
+pub fn drift(samples: &[(&str, Environment)]) -> Vec<String> {
+    let describe = |value: &dyn Fn(&Environment) -> String| {
+        samples
+            .iter()
+            .map(|(name, sample)| format!("{} {}", value(sample), name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut drifted = Vec::new();
+    if let Some((min, max)) = spread(samples.iter().map(|(_, sample)| sample.competing_processes)) {
+        if max - min > COMPETING_PROCESSES_DRIFT {
+            drifted.push(format!(
+                "competing processes: {}",
+                describe(&|sample| format!("{:.1}", sample.competing_processes))
+            ));
+        }
+    }
+    let total_memory_kib = samples
+        .iter()
+        .filter_map(|(_, sample)| sample.total_memory_kib)
+        .max();
+    if let (Some((min, max)), Some(total)) = (
+        spread(
+            samples
+                .iter()
+                .filter_map(|(_, sample)| sample.available_memory_kib),
+        ),
+        total_memory_kib,
+    ) {
+        if (max - min) as f64 > MEMORY_DRIFT * total as f64 {
+            drifted.push(format!(
+                "available memory: {}",
+                describe(&|sample| sample
+                    .available_memory_kib
+                    .map_or("?".to_string(), |kib| gib(kib * 1024)))
+            ));
+        }
+    }
+    if let Some((min, max)) = spread(
+        samples
+            .iter()
+            .filter_map(|(_, sample)| sample.free_disk_bytes),
+    ) {
+        if (max - min) as f64 > DISK_DRIFT * max as f64 {
+            drifted.push(format!(
+                "free disk space: {}",
+                describe(&|sample| sample.free_disk_bytes.map_or("?".to_string(), gib))
+            ));
+        }
+    }
+    let first_governor = samples.first().map(|(_, sample)| &sample.cpu_governor);
+    if samples
+        .iter()
+        .any(|(_, sample)| Some(&sample.cpu_governor) != first_governor)
+    {
+        drifted.push(format!(
+            "CPU governor: {}",
+            describe(&|sample| sample.cpu_governor.clone().unwrap_or("?".to_string()))
+        ));
+    }
+    drifted
+}
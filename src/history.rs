@@ -0,0 +1,300 @@
+// Storage of the results of past sessions and reporting of runtime trends across them.
+use crate::compression::{self, Compression};
+use crate::names::NameMap;
+use crate::results::{read_results, ProofResults, ResultFile};
+use crate::stats::{mean, Statistic};
+use crate::GenericResult;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Result as IOResult;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+pub struct TrendArguments {
+    /// directory the sessions were stored in with --history-dir
+    #[structopt(long, parse(from_os_str))]
+    history_dir: PathBuf,
+    /// how to summarize the runtimes of each proof: mean, median, min or best-of-N
+    #[structopt(long, default_value = "mean")]
+    statistic: Statistic,
+    /// only consider the last N sessions
+    #[structopt(long)]
+    last: Option<usize>,
+    /// file with lines of raw-name,display-name[,group] giving proofs readable names
+    #[structopt(long, parse(from_os_str))]
+    name_map: Option<PathBuf>,
+}
+
+// short hash of the git commit checked out in directory, if it is in a git repository
+pub fn git_revision(directory: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(directory)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let revision = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if revision.is_empty() {
+        None
+    } else {
+        Some(revision)
+    }
+}
+
+// copy the result file of a finished session into the history directory, named so that sessions
+// sort chronologically, and compress the copy if requested
+pub fn store(
+    history_dir: &Path,
+    csv_path: &Path,
+    timestamp: u64,
+    git_revision: Option<&str>,
+    compression: Option<Compression>,
+) -> IOResult<PathBuf> {
+    fs::create_dir_all(history_dir)?;
+    let target = history_dir.join(format!(
+        "{:012}-{}.csv",
+        timestamp,
+        git_revision.unwrap_or("unknown")
+    ));
+    fs::copy(csv_path, &target)?;
+    match compression {
+        Some(compression) => compression::compress_file(&target, compression),
+        None => Ok(target),
+    }
+}
+
+// the name of a stored session without the .csv extension, None for anything that isn't a result
+// file (which may be compressed)
+fn session_name(path: &Path) -> Option<String> {
+    let mut name = path.file_name()?.to_str()?;
+    if let Some(compression) = compression::detect(path) {
+        name = name
+            .strip_suffix(compression.extension())?
+            .strip_suffix('.')?;
+    }
+    name.strip_suffix(".csv").map(String::from)
+}
+
+// all stored sessions in chronological order
+pub fn load(history_dir: &Path) -> GenericResult<Vec<(String, ResultFile)>> {
+    let mut session_paths: Vec<(String, PathBuf)> = fs::read_dir(history_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| Some((session_name(&path)?, path)))
+        .collect();
+    session_paths.sort();
+    let mut sessions = Vec::new();
+    for (session_name, session_path) in session_paths {
+        sessions.push((session_name, read_results(&session_path)?));
+    }
+    Ok(sessions)
+}
+
+// weight of the newest session in the exponentially weighted moving average and variance of the
+// runtime of a proof across sessions
+const EWMA_WEIGHT: f64 = 0.3;
+// runtimes further than this many standard deviations from the moving average are anomalies
+const ANOMALY_SIGMAS: f64 = 3.0;
+// past sessions a proof needs runtimes in before we judge its latest runtime
+const MIN_ANOMALY_HISTORY: usize = 5;
+// lower bound for the standard deviation relative to the average, so that a proof with very
+// stable runtimes isn't flagged for a tiny difference
+const MIN_RELATIVE_SIGMA: f64 = 0.01;
+
+// a proof whose latest runtime is outside the control band of its history
+pub struct Anomaly {
+    pub name: String,
+    pub average: f64,
+    pub sigma: f64,
+    pub latest: f64,
+}
+
+impl Anomaly {
+    // signed distance from the average in standard deviations
+    pub fn deviation(&self) -> f64 {
+        (self.latest - self.average) / self.sigma
+    }
+}
+
+// exponentially weighted moving average and standard deviation of values, oldest first
+fn ewma(values: &[f64]) -> (f64, f64) {
+    let mut average = values[0];
+    let mut variance = 0.0;
+    for value in values[1..].iter() {
+        let difference = value - average;
+        let increment = EWMA_WEIGHT * difference;
+        average += increment;
+        variance = (1.0 - EWMA_WEIGHT) * (variance + difference * increment);
+    }
+    (average, variance.sqrt())
+}
+
+// the proofs in latest whose runtime is outside the EWMA ± 3 sigma band of their runtimes in the
+// past sessions (oldest first)
+pub fn anomalies(
+    past: &[(String, ResultFile)],
+    latest: &[ProofResults],
+    statistic: Statistic,
+) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    for proof in latest.iter() {
+        let latest_runtime = match proof.runtime(statistic) {
+            Some(runtime) => runtime,
+            None => continue,
+        };
+        let history: Vec<f64> = past
+            .iter()
+            .filter_map(|(_, session)| {
+                session
+                    .proofs
+                    .iter()
+                    .find(|past_proof| past_proof.name == proof.name)?
+                    .runtime(statistic)
+            })
+            .collect();
+        if history.len() < MIN_ANOMALY_HISTORY {
+            continue;
+        }
+        let (average, sigma) = ewma(&history);
+        let sigma = sigma.max(MIN_RELATIVE_SIGMA * average);
+        if sigma > 0.0 && (latest_runtime - average).abs() > ANOMALY_SIGMAS * sigma {
+            anomalies.push(Anomaly {
+                name: proof.name.clone(),
+                average,
+                sigma,
+                latest: latest_runtime,
+            });
+        }
+    }
+    anomalies
+}
+
+pub fn print_anomalies(anomalies: &[Anomaly]) {
+    if anomalies.is_empty() {
+        return;
+    }
+    println!(
+        "ANOMALIES (latest runtime outside the moving average ± {} sigma of the history)",
+        ANOMALY_SIGMAS
+    );
+    let name_width = anomalies
+        .iter()
+        .map(|anomaly| anomaly.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:<name_width$} {:>10} {:>10} {:>10} {:>10}",
+        "PROOF",
+        "EWMA(s)",
+        "SIGMA(s)",
+        "LATEST(s)",
+        "SIGMAS",
+        name_width = name_width
+    );
+    for anomaly in anomalies.iter() {
+        println!(
+            "{:<name_width$} {:>10.3} {:>10.3} {:>10.3} {:>+10.1}",
+            anomaly.name,
+            anomaly.average,
+            anomaly.sigma,
+            anomaly.latest,
+            anomaly.deviation(),
+            name_width = name_width
+        );
+    }
+}
+
+// slope of the least squares line through the points (i, values[i])
+fn slope(values: &[f64]) -> f64 {
+    let xs: Vec<f64> = (0..values.len()).map(|i| i as f64).collect();
+    let (x_mean, y_mean) = (mean(&xs), mean(values));
+    let covariance: f64 = xs
+        .iter()
+        .zip(values.iter())
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum();
+    let variance: f64 = xs.iter().map(|x| (x - x_mean) * (x - x_mean)).sum();
+    covariance / variance
+}
+
+pub fn print_trend(args: &TrendArguments) -> GenericResult<()> {
+    let mut sessions = load(&args.history_dir)?;
+    if let Some(last) = args.last {
+        let skip = sessions.len().saturating_sub(last);
+        sessions.drain(..skip);
+    }
+    if let Some(name_map) = &args.name_map {
+        let names = NameMap::load(name_map)?;
+        for (_, session) in sessions.iter_mut() {
+            names.rename(&mut session.proofs);
+        }
+    }
+    if sessions.is_empty() {
+        return Err(format!("no sessions found in {}", args.history_dir.display()).into());
+    }
+    println!(
+        "SESSIONS {} to {} ({} in total)",
+        sessions[0].0,
+        sessions[sessions.len() - 1].0,
+        sessions.len()
+    );
+    for (session_name, session) in sessions.iter() {
+        if let Some(note) = &session.metadata.note {
+            println!("  {}: {}", session_name, note);
+        }
+    }
+    // runtimes of each proof in chronological order, skipping sessions without successful runs
+    let mut proof_runtimes: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+    for (_, session) in sessions.iter() {
+        for proof in session.proofs.iter() {
+            if let Some(runtime) = proof.runtime(args.statistic) {
+                proof_runtimes.entry(&proof.name).or_default().push(runtime);
+            }
+        }
+    }
+    let name_width = proof_runtimes
+        .keys()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:<name_width$} {:>8} {:>10} {:>10} {:>9} {:>14}",
+        "PROOF",
+        "SESSIONS",
+        "FIRST(s)",
+        "LAST(s)",
+        "CHANGE%",
+        "SLOPE%/SESSION",
+        name_width = name_width
+    );
+    for (name, runtimes) in proof_runtimes.iter() {
+        let first = runtimes[0];
+        let last = runtimes[runtimes.len() - 1];
+        let trend = if runtimes.len() >= 2 {
+            format!("{:+.2}", 100.0 * slope(runtimes) / mean(runtimes))
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<name_width$} {:>8} {:>10.3} {:>10.3} {:>+9.1} {:>14}",
+            name,
+            runtimes.len(),
+            first,
+            last,
+            100.0 * (last - first) / first,
+            trend,
+            name_width = name_width
+        );
+    }
+    if let Some(((_, latest), past)) = sessions.split_last() {
+        print_anomalies(&anomalies(past, &latest.proofs, args.statistic));
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+// The parts of the benchmark that other programs can use directly. Everything else is only
+// reachable through the command line.
+extern crate crossbeam_channel;
+
+pub mod progress;
+
+pub use progress::{Progress, ProgressSnapshot, ProofProgress, ProofState};
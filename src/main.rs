@@ -4,83 +4,1087 @@
 extern crate crossbeam_channel;
 extern crate structopt;
 
-use crossbeam_channel::{Receiver, Sender};
-use std::collections::HashMap;
+mod affinity;
+mod artifacts;
+mod batch;
+mod builder;
+mod cache;
+mod calibration;
+mod cbmc;
+mod compare;
+mod compression;
+mod console;
+mod container;
+mod energy;
+mod environment;
+mod history;
+mod monitor;
+mod names;
+mod outputs;
+mod pattern;
+mod perf;
+mod power;
+mod priority;
+mod remote;
+mod report;
+mod requirements;
+mod results;
+mod sandbox;
+mod scheduler;
+mod state;
+mod stats;
+mod system;
+mod template;
+mod tools;
+mod triage;
+
+use artifacts::FailureArtifacts;
+use batch::BatchTarget;
+use benchmark_amazon_projects::progress::{self, Progress, ProgressWriter};
+use builder::Builder;
+use cache::PageCache;
+use compression::Compression;
+use console::Reporter;
+use container::Container;
+use crossbeam_channel::Sender;
+use monitor::{ProofTimeout, RunStats, Timeout};
+use names::NameMap;
+use power::PauseConditions;
+use priority::IoPriority;
+use remote::Worker;
+use report::ReportOptions;
+use requirements::Requirement;
+use results::{dump_csv, dump_metadata, dump_run, Exit, ProofResults, ResultFile, RunMetadata};
+use sandbox::Overlay;
+use scheduler::{Admission, Dependency, ProofGroup, Scheduler};
+use state::StateFile;
+use stats::Statistic;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs::{File, OpenOptions};
-use std::io::{Result as IOResult, Write};
+use std::io::Result as IOResult;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::process::ExitStatus;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
+use template::ReportTemplate;
 
-type GenericResult<T> = Result<T, Box<dyn Error>>;
+pub type GenericResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Clone, Copy, PartialEq)]
 enum JobMessagePayload {
     JobStarted,
-    RunStarted,
-    RunFinished,
-    RunFailed,
-    JobFinished,
+    // with --interleave, a round of the job after its first one starts
+    RoundStarted,
+    // the following messages for this job are about the variant with this index in
+    // RunOptions::targets
+    VariantSelected(usize),
+    WarmupStarted,
+    WarmupFinished,
+    // with the id of the run
+    RunStarted(String),
+    // the wall clock and the monotonic clock disagreed by this much during the current run
+    ClockJump(Duration),
+    RunFinished(RunStats),
+    // with how the run ended, unless it couldn't be started at all
+    RunFailed(RunStats, Option<Exit>),
+    // the current run is not recorded and will be repeated
+    RunDiscarded,
+    // the current run was killed because the session was interrupted or ran out of time and is not
+    // recorded
+    RunInterrupted,
+    // the current run failed (how, unless it couldn't be started) and is repeated with --retries,
+    // for the retry with this number
+    RunRetried(Option<Exit>, u32),
+    // the last run deviated by this many standard deviations from the others and is replaced by
+    // an extra iteration
+    RunOutlier(f64),
+    // the job thread died, the runs it made since the job (or its round) started are discarded
+    JobDied,
+    // with the error that ended the job early with --robust
+    JobFinished(Option<String>),
+}
+
+// mixed into the --seed of --shuffle, so small seeds don't start xorshift off with few bits set
+const SHUFFLE_SEED_MASK: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// discrepancies between wall clock and monotonic clock time larger than this mean that the system
+// was suspended or the clock was stepped (e.g. by NTP) during a run
+const CLOCK_JUMP_THRESHOLD: Duration = Duration::from_secs(1);
+
+// how many times in total a proof will be re-run because of clock jumps with --rerun-suspect
+const MAX_SUSPECT_RERUNS: u32 = 3;
+
+// if all CPUs were busy on average above this during a run, and not only because of the run
+// itself, the machine was probably oversubscribed and the runtime inflated by waiting for a CPU
+const OVERSUBSCRIBED_UTILIZATION: f64 = 0.95;
+// how many CPUs something else needs to keep busy to count as competing with the run
+const COMPETING_CPU_UTILIZATION: f64 = 0.5;
+
+// how many extra iterations a proof gets at most to replace outliers with --rerun-outliers
+const MAX_OUTLIER_RERUNS: u32 = 2;
+
+// defaults for --mode accurate
+const ACCURATE_ITERATIONS: u32 = 10;
+const ACCURATE_WARMUP: u32 = 1;
+const ACCURATE_COOLDOWN: Duration = Duration::from_secs(5);
+const ACCURATE_OUTLIER_THRESHOLD: f64 = 3.0;
+
+// how often a job is requeued when the job thread running it dies, before giving up on its proof
+const MAX_JOB_RESTARTS: u32 = 1;
+
+// the environment variable every process of a run finds its id in
+const RUN_ID_VARIABLE: &str = "BENCH_RUN_ID";
+
+// the Makefile variable that marks a proof as exclusive
+const EXCLUSIVE_VARIABLE: &str = "BENCHMARK_EXCLUSIVE";
+
+// how far the elapsed wall clock time deviates from the elapsed monotonic time, if by more than
+// CLOCK_JUMP_THRESHOLD
+fn clock_jump(start_time: Instant, start_wall_time: SystemTime) -> Option<Duration> {
+    let monotonic_elapsed = start_time.elapsed();
+    let wall_elapsed = match SystemTime::now().duration_since(start_wall_time) {
+        Ok(elapsed) => elapsed,
+        // the clock went backwards
+        Err(err) => return Some(err.duration() + monotonic_elapsed),
+    };
+    let discrepancy = wall_elapsed.abs_diff(monotonic_elapsed);
+    if discrepancy > CLOCK_JUMP_THRESHOLD {
+        Some(discrepancy)
+    } else {
+        None
+    }
 }
 
 struct JobMessage(PathBuf, Instant, JobMessagePayload);
 
+#[derive(Clone, Copy)]
+enum Iterations {
+    Fixed(u32),
+    // keep running until the 95% confidence interval of the mean runtime is within
+    // ±target_relative_width of the mean, but at least min and at most max times
+    Adaptive {
+        min: u32,
+        max: u32,
+        target_relative_width: f64,
+    },
+}
+
+impl Iterations {
+    fn max(&self) -> u32 {
+        match *self {
+            Iterations::Fixed(iterations) => iterations,
+            Iterations::Adaptive { max, .. } => max,
+        }
+    }
+
+    // whether we're done after runs runs with the given successful runtimes
+    fn done(&self, runs: u32, runtimes: &[f64]) -> bool {
+        match *self {
+            Iterations::Fixed(iterations) => runs >= iterations,
+            Iterations::Adaptive {
+                min,
+                max,
+                target_relative_width,
+            } => {
+                runs >= max
+                    || (runs >= min
+                        && matches!(stats::relative_confidence_half_width(runtimes),
+                            Some(width) if width <= target_relative_width))
+            }
+        }
+    }
+}
+
+// presets for what the benchmark run is optimized for
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Normal,
+    // get pass/fail results for the whole suite as fast as possible, the runtimes are only rough
+    Throughput,
+    // get runtimes that are as precise as possible, however long it takes
+    Accurate,
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Mode, String> {
+        match arg {
+            "normal" => Ok(Mode::Normal),
+            "throughput" => Ok(Mode::Throughput),
+            "accurate" => Ok(Mode::Accurate),
+            _ => Err(format!(
+                "unknown mode '{}', expected normal, throughput or accurate",
+                arg
+            )),
+        }
+    }
+}
+
+// which log files of the runs to keep with --log-dir, they are always captured so CBMC's
+// statistics and the end of the output of failed runs can be read from them
+#[derive(Clone, Copy, PartialEq)]
+enum KeepLogs {
+    All,
+    Failed,
+    None,
+}
+
+impl std::str::FromStr for KeepLogs {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<KeepLogs, String> {
+        match arg {
+            "all" => Ok(KeepLogs::All),
+            "failed" => Ok(KeepLogs::Failed),
+            "none" => Ok(KeepLogs::None),
+            _ => Err(format!(
+                "unknown log retention '{}', expected all, failed or none",
+                arg
+            )),
+        }
+    }
+}
+
+// options that apply to all runs of all proofs
+#[derive(Clone)]
+struct RunOptions {
+    // number of unmeasured runs before the actual iterations
+    warmup: u32,
+    failure_artifacts: Option<FailureArtifacts>,
+    // repeat runs during which the clock jumped instead of recording them as suspect
+    rerun_suspect: bool,
+    // the make targets whose runtime we measure, more than one for A/B comparisons
+    targets: Vec<String>,
+    // passed to every make invocation as -j
+    make_jobs: Option<u32>,
+    // the make target that cleans the proof directory at the start of every run, so every run
+    // builds from the same state
+    clean_target: String,
+    // measure the whole run including the clean target and goto instead of just the target
+    coarse_timing: bool,
+    // the tools that build a proof, by the file that marks its directory
+    builders: Vec<Builder>,
+    // pause between consecutive runs of a proof to let the machine settle
+    cooldown: Duration,
+    // also pause before every proof a job starts after its first
+    cooldown_between_proofs: bool,
+    // the state of the page cache at the start of every measured run
+    page_cache: Option<PageCache>,
+    // when not to start any runs
+    pause: PauseConditions,
+    // report errors and panics of a job or while keeping track of it and carry on
+    robust: bool,
+    // stop the session like an interrupt once a run of a proof has failed
+    fail_fast: bool,
+    // subtracted from every recorded runtime
+    make_overhead: Duration,
+    // run make without network access
+    no_network: bool,
+    // repeat a failed run up to this many times before recording it as failed
+    retries: u32,
+    // count hardware events of the measured command with perf stat
+    perf_stat: bool,
+    // make targets run and timed one after another after the measured target of every run
+    phases: Vec<String>,
+    // time every invocation of the goto and cbmc tools with time-tool
+    tool_times: bool,
+    // replace runs deviating by more than this many standard deviations from the others by an
+    // extra iteration
+    outlier_threshold: Option<f64>,
+    // mount a fresh writable overlay over the source tree of a proof for every run, in this
+    // directory
+    overlay_dir: Option<PathBuf>,
+    // capture the output of every run into a log file below this directory
+    log_dir: Option<PathBuf>,
+    // for the log files once their run is done
+    log_compression: Option<Compression>,
+    keep_logs: KeepLogs,
+    // when to kill the measured command of a run
+    limits: monitor::Limits,
+    // time out the runs of a proof after its first successful one at this multiple of its runtime
+    auto_timeout: Option<f64>,
+    // the timeouts of the proofs matching a pattern instead of the above, the first match counts
+    proof_timeouts: Vec<ProofTimeout>,
+    // with --workers the host the job runs its proofs on
+    worker: Option<String>,
+    // run every make invocation as an AWS Batch job
+    aws_batch: Option<BatchTarget>,
+    // run every make invocation in a fresh container
+    container: Option<Container>,
+}
+
+impl std::fmt::Display for Iterations {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            Iterations::Fixed(iterations) => write!(f, "{}", iterations),
+            Iterations::Adaptive {
+                min,
+                max,
+                target_relative_width,
+            } => write!(
+                f,
+                "{} to {}, until the 95% confidence interval is within ±{}%",
+                min,
+                max,
+                100.0 * target_relative_width
+            ),
+        }
+    }
+}
+
+// options that decide which proofs run when
+struct ScheduleOptions {
+    parallel_jobs: u32,
+    groups: Vec<ProofGroup>,
+    // with --pin-cpus the disjoint set of CPUs of every job
+    cpu_sets: Option<Vec<Vec<usize>>>,
+    // with --workers the hosts the jobs run on, parallel_jobs is the sum of their jobs
+    workers: Vec<Worker>,
+    // with --adaptive-jobs when to hold back proofs, parallel_jobs is the most that run at once
+    admission: Option<Admission>,
+    // with --memory-aware how much the expected peak memory of the running proofs may add up to,
+    // and that of the proofs for which it is known
+    memory_budget: Option<u64>,
+    expected_memory: HashMap<PathBuf, u64>,
+    // how long the session may take before no more runs start, and whether the ones in progress
+    // are killed then
+    max_total_time: Option<Duration>,
+    kill_at_time_limit: bool,
+    // run iteration k of every proof before iteration k+1 of any proof
+    interleave: bool,
+    // the proofs every proof has to run after, from --dependencies-file
+    dependencies: HashMap<PathBuf, Vec<PathBuf>>,
+    // the wildcard patterns of the proofs that run on their own, besides those marked in their
+    // Makefile
+    exclusive: Vec<String>,
+}
+
+#[derive(Clone)]
 struct RunProofMessage {
     job_path: PathBuf,
-    iterations: u32,
+    iterations: Iterations,
+    // names of the concurrency groups this proof belongs to
+    groups: Vec<String>,
+    // the peak memory the proof is expected to take up with --memory-aware, if known
+    memory_bytes: Option<u64>,
+    // with --interleave the round (from 1) and the number of rounds, the job makes one iteration
+    // per round
+    round: Option<(u32, u32)>,
+    // the proofs that have to finish before this one starts
+    after: Vec<PathBuf>,
+    // whether no other proofs may run at the same time
+    exclusive: bool,
+    // how often the job was requeued because the job thread running it died
+    restarts: u32,
 }
 
-fn run_make(make_command: &str, working_directory: &Path) -> IOResult<ExitStatus> {
-    use std::process::{Command, Stdio};
-    Command::new("make")
-        .arg(make_command)
-        .current_dir(working_directory)
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
+fn proof_name(path: &Path) -> String {
+    path.file_name()
+        .expect("proof paths do not end in ..")
+        .to_string_lossy()
+        .into_owned()
 }
 
-fn run_proof(path: &Path, iterations: u32, sender: &Sender<JobMessage>) -> IOResult<()> {
-    use JobMessagePayload::*;
+// the options for the proof in path, with the timeout of the first proof timeout matching it
+fn proof_options(path: &Path, options: &RunOptions) -> RunOptions {
+    let name = proof_name(path);
+    let mut proof_options = options.clone();
+    if let Some(proof_timeout) = options
+        .proof_timeouts
+        .iter()
+        .find(|proof_timeout| pattern::matches(&proof_timeout.pattern, &name))
+    {
+        let (time, auto_timeout) = match proof_timeout.timeout {
+            Timeout::Fixed(timeout) => (Some(timeout), None),
+            Timeout::Auto(factor) => (None, Some(factor)),
+        };
+        proof_options.limits.time = time;
+        proof_options.auto_timeout = auto_timeout;
+    }
+    proof_options
+}
+
+// the name of the results of target for the proof in path, which includes the target if there
+// is more than one
+fn variant_name(path: &Path, target: &str, options: &RunOptions) -> String {
+    if options.targets.len() > 1 {
+        format!("{}@{}", proof_name(path), target)
+    } else {
+        proof_name(path)
+    }
+}
+
+// where the output of the run_nr-th run of target for the proof in path goes with --log-dir
+fn log_path(path: &Path, target: &str, run_nr: u32, options: &RunOptions) -> Option<PathBuf> {
+    options.log_dir.as_ref().map(|log_dir| {
+        log_dir
+            .join(variant_name(path, target, options))
+            .join(format!("run-{}.log", run_nr))
+    })
+}
+
+// the builder invocation for make_command in working_directory, with the id of the run it is part
+// of in BENCH_RUN_ID, under perf stat writing to perf_output if given
+fn builder_command(
+    make_command: &str,
+    working_directory: &Path,
+    options: &RunOptions,
+    run_id: Option<&str>,
+    perf_output: Option<&Path>,
+) -> IOResult<std::process::Command> {
+    use std::process::Command;
+    let builder = builder::detect(&options.builders, working_directory).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("don't know how to build {}", working_directory.display()),
+        )
+    })?;
+    let mut make = match perf_output {
+        Some(perf_output) => {
+            let mut perf = perf::command(perf_output);
+            perf.arg(builder.program());
+            perf
+        }
+        None => Command::new(builder.program()),
+    };
+    make.args(builder.arguments(make_command, options.make_jobs))
+        .current_dir(working_directory);
+    if let Some(run_id) = run_id {
+        make.env(RUN_ID_VARIABLE, run_id);
+        if options.tool_times {
+            make.env(tools::TOOL_TIMES_VARIABLE, tools::times_path(run_id));
+            // other builders only see the tools through the environment
+            for argument in tools::make_arguments()? {
+                match argument.split_once('=') {
+                    Some((variable, value)) if !builder.is_make() => make.env(variable, value),
+                    _ => make.arg(argument),
+                };
+            }
+        }
+    }
+    Ok(make)
+}
+
+// make_command in working_directory like builder_command, where the run happens (in a container,
+// on a worker or as an AWS Batch job), with stdout and stderr appended to log if given, and in a
+// container with the command that kills the container
+fn make_command(
+    make_command: &str,
+    working_directory: &Path,
+    options: &RunOptions,
+    overlay: Option<&Overlay>,
+    log: Option<&File>,
+    run_id: Option<&str>,
+    perf_output: Option<&Path>,
+) -> IOResult<(std::process::Command, Option<std::process::Command>)> {
+    use std::process::Stdio;
+    let mut make = builder_command(
+        make_command,
+        working_directory,
+        options,
+        run_id,
+        perf_output,
+    )?;
+    let (make, kill) = match &options.container {
+        // the container runtime takes care of the network
+        Some(container) => {
+            let (make, kill) = container::command(container, &make, options.no_network, run_id)?;
+            (make, Some(kill))
+        }
+        None => {
+            sandbox::restrict(&mut make, working_directory, options.no_network, overlay);
+            (make, None)
+        }
+    };
+    let mut make = match (&options.worker, &options.aws_batch) {
+        (Some(host), _) => remote::command(host, &make),
+        (None, Some(batch)) => batch::command(batch, make_command, &make)?,
+        (None, None) => make,
+    };
+    if let (Some(_), Some(run_id)) = (&options.aws_batch, run_id) {
+        make.env(batch::BATCH_TIMES_VARIABLE, batch::times_path(run_id));
+    }
+    make.stdin(Stdio::null());
+    match log {
+        Some(log) => make.stdout(log.try_clone()?).stderr(log.try_clone()?),
+        None => make.stdout(Stdio::null()).stderr(Stdio::null()),
+    };
+    Ok((make, kill))
+}
+
+fn run_make(
+    make_command: &str,
+    working_directory: &Path,
+    options: &RunOptions,
+    overlay: Option<&Overlay>,
+    log: Option<&File>,
+    run_id: Option<&str>,
+) -> IOResult<ExitStatus> {
+    // like the measured target, so a hanging build is killed at the limits and with the session
+    let (mut make, kill) = self::make_command(
+        make_command,
+        working_directory,
+        options,
+        overlay,
+        log,
+        run_id,
+        None,
+    )?;
+    monitor::run(&mut make, kill, options.limits).map(|run| run.status)
+}
+
+// a random (version 4) UUID to tell apart all runs ever made
+fn new_run_id() -> IOResult<String> {
+    use std::io::Read;
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+// a fresh overlay over source_root for a single run with --read-only-sources
+fn create_overlay(source_root: Option<&Path>, options: &RunOptions) -> IOResult<Option<Overlay>> {
+    match (source_root, &options.overlay_dir) {
+        (Some(source_root), Some(overlay_dir)) => {
+            Ok(Some(Overlay::create(source_root, overlay_dir)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn remove_overlay(overlay: Option<Overlay>) -> IOResult<()> {
+    match overlay {
+        Some(overlay) => overlay.remove(),
+        None => Ok(()),
+    }
+}
+
+// drop a run of the proof in path that was killed or never started because the session stopped,
+// it is not repeated
+fn drop_killed_run(
+    path: &Path,
+    log_path: Option<&Path>,
+    overlay: Option<Overlay>,
+    sender: &Sender<JobMessage>,
+) -> IOResult<Option<f64>> {
+    if let Some(log_path) = log_path {
+        let _ = std::fs::remove_file(log_path);
+    }
+    remove_overlay(overlay)?;
     sender
-        .send(JobMessage(path.to_path_buf(), Instant::now(), JobStarted))
+        .send(JobMessage(
+            path.to_path_buf(),
+            Instant::now(),
+            JobMessagePayload::RunInterrupted,
+        ))
         .expect("Receiver shouldn't die while we're still sending messages");
-    for _ in 0..iterations {
-        run_make("veryclean", path)?;
-        run_make("goto", path)?;
+    Ok(None)
+}
+
+// run target once in the proof directory (repeating the run if the clock jumps during it and
+// rerun_suspect is set) and return the runtime in seconds if it succeeded
+fn run_target(
+    path: &Path,
+    target: &str,
+    run_nr: u32,
+    options: &RunOptions,
+    source_root: Option<&Path>,
+    suspect_reruns: &mut u32,
+    sender: &Sender<JobMessage>,
+) -> IOResult<Option<f64>> {
+    use JobMessagePayload::*;
+    let mut retries = 0;
+    loop {
+        let overlay = create_overlay(source_root, options)?;
+        // a repeated run is a different run
+        let run_id = new_run_id()?;
+        // a repeated run starts its log from scratch
+        let mut log_path = log_path(path, target, run_nr, options);
+        let log = match &log_path {
+            Some(log_path) => {
+                std::fs::create_dir_all(log_path.parent().expect("log files are in a directory"))?;
+                Some(File::create(log_path)?)
+            }
+            None => None,
+        };
+        // right before the measured part of the run
+        let prepare_page_cache = || match options.page_cache {
+            Some(page_cache) => cache::prepare(page_cache, &sandbox::source_root(path)?),
+            None => Ok(()),
+        };
+        if options.coarse_timing {
+            prepare_page_cache()?;
+        }
+        let build_start_time = Instant::now();
+        let build_start_wall_time = SystemTime::now();
+        let build_start_suspended_time = monitor::suspended_time();
+        let cleaned = run_make(
+            &options.clean_target,
+            path,
+            options,
+            overlay.as_ref(),
+            log.as_ref(),
+            Some(&run_id),
+        );
+        let goto_start_time = Instant::now();
+        let goto_start_suspended_time = monitor::suspended_time();
+        let built = cleaned.and_then(|_| {
+            run_make(
+                "goto",
+                path,
+                options,
+                overlay.as_ref(),
+                log.as_ref(),
+                Some(&run_id),
+            )
+        });
+        match built {
+            Err(_) if monitor::runs_killed() => {
+                return drop_killed_run(path, log_path.as_deref(), overlay, sender)
+            }
+            built => {
+                built?;
+            }
+        }
+        let mut build_time = goto_start_time
+            .elapsed()
+            .saturating_sub(monitor::suspended_time() - goto_start_suspended_time);
+        let perf_output = options
+            .perf_stat
+            .then(|| std::env::temp_dir().join(format!("benchmark-perf-{}.csv", run_id)));
 
+        if !options.coarse_timing {
+            prepare_page_cache()?;
+        }
+        let (start_time, start_wall_time, start_suspended_time) = if options.coarse_timing {
+            (
+                build_start_time,
+                build_start_wall_time,
+                build_start_suspended_time,
+            )
+        } else {
+            (Instant::now(), SystemTime::now(), monitor::suspended_time())
+        };
         sender
-            .send(JobMessage(path.to_path_buf(), Instant::now(), RunStarted))
+            .send(JobMessage(
+                path.to_path_buf(),
+                start_time,
+                RunStarted(run_id.clone()),
+            ))
             .expect("Receiver shouldn't die while we're still sending messages");
-        match run_make("result", path) {
-            Ok(status) if status.success() => {
+        let (mut make, kill) = make_command(
+            target,
+            path,
+            options,
+            overlay.as_ref(),
+            log.as_ref(),
+            Some(&run_id),
+            perf_output.as_deref(),
+        )?;
+        let mut result = monitor::run(&mut make, kill, options.limits);
+        if let Ok(run) = &mut result {
+            // the time the session was suspended in the meantime isn't part of the run
+            run.finish_time -= monitor::suspended_time() - start_suspended_time;
+            // the later phases depend on the measured target
+            if run.status.success() {
+                for phase in options.phases.iter() {
+                    let phase_start_time = Instant::now();
+                    let status = match run_make(
+                        phase,
+                        path,
+                        options,
+                        overlay.as_ref(),
+                        log.as_ref(),
+                        Some(&run_id),
+                    ) {
+                        // the run is dropped below
+                        Err(_) if monitor::runs_killed() => break,
+                        status => status?,
+                    };
+                    run.stats.phase_times.push((
+                        phase.clone(),
+                        Some(phase_start_time.elapsed()).filter(|_| status.success()),
+                    ));
+                }
+            }
+        }
+        drop(log);
+        if options.tool_times {
+            let tool_times = tools::times_path(&run_id);
+            if let Ok(run) = &mut result {
+                run.stats.tool_times = tools::parse_file(&tool_times).unwrap_or_default();
+            }
+            let _ = std::fs::remove_file(tool_times);
+        }
+        if let Some(perf_output) = &perf_output {
+            if let Ok(run) = &mut result {
+                run.stats.perf = perf::parse_file(perf_output).unwrap_or_default();
+            }
+            let _ = std::fs::remove_file(perf_output);
+        }
+        if options.aws_batch.is_some() {
+            // how long the jobs ran, not how long they were queued
+            let batch_times = batch::times_path(&run_id);
+            let times = batch::parse_file(&batch_times).unwrap_or_default();
+            let time = |name: &str| {
+                times
+                    .iter()
+                    .find(|(job, _)| job == name)
+                    .map(|(_, time)| *time)
+            };
+            build_time = time("goto").unwrap_or(build_time);
+            if let Ok(run) = &mut result {
+                let timed: &[&str] = if options.coarse_timing {
+                    &[options.clean_target.as_str(), "goto", target]
+                } else {
+                    &[target]
+                };
+                if let Some(runtime) = timed.iter().map(|name| time(name)).sum() {
+                    run.finish_time = start_time + runtime;
+                }
+                for (phase, phase_time) in run.stats.phase_times.iter_mut() {
+                    if phase_time.is_some() {
+                        *phase_time = time(phase).or(*phase_time);
+                    }
+                }
+            }
+            let _ = std::fs::remove_file(batch_times);
+        }
+        if monitor::runs_killed() {
+            return drop_killed_run(path, log_path.as_deref(), overlay, sender);
+        }
+        if let Ok(run) = &mut result {
+            run.stats.build_time = Some(build_time);
+            // with an overlay the outputs of the run are only in its writable layer
+            let outputs = match &overlay {
+                Some(overlay) => overlay.written_files(path),
+                None => path.to_path_buf(),
+            };
+            run.stats.artifact_bytes = artifacts::disk_usage(&outputs).ok();
+        }
+        let mut log_tail = Vec::new();
+        if let Some(captured_log) = &log_path {
+            let succeeded = match &mut result {
+                Ok(run) if run.status.success() => {
+                    run.stats.cbmc = cbmc::parse_log(captured_log)?;
+                    true
+                }
+                Ok(run) => {
+                    run.stats.cbmc = cbmc::parse_log(captured_log)?;
+                    log_tail = triage::last_lines(captured_log, triage::LOG_TAIL_LINES)?;
+                    false
+                }
+                Err(_) => {
+                    log_tail = triage::last_lines(captured_log, triage::LOG_TAIL_LINES)?;
+                    false
+                }
+            };
+            let keep = match options.keep_logs {
+                KeepLogs::All => true,
+                KeepLogs::Failed => !succeeded,
+                KeepLogs::None => false,
+            };
+            if !keep {
+                std::fs::remove_file(captured_log)?;
+                // the directory of the proof, unless other runs kept their logs in it
+                if let Some(proof_log_dir) = captured_log.parent() {
+                    let _ = std::fs::remove_dir(proof_log_dir);
+                }
+                log_path = None;
+            } else if let Some(compression) = options.log_compression {
+                log_path = Some(compression::compress_file(captured_log, compression)?);
+            }
+        }
+        if let Some(jump) = clock_jump(start_time, start_wall_time) {
+            sender
+                .send(JobMessage(
+                    path.to_path_buf(),
+                    Instant::now(),
+                    ClockJump(jump),
+                ))
+                .expect("Receiver shouldn't die while we're still sending messages");
+            if options.rerun_suspect && *suspect_reruns < MAX_SUSPECT_RERUNS {
+                *suspect_reruns += 1;
+                remove_overlay(overlay)?;
                 sender
-                    .send(JobMessage(path.to_path_buf(), Instant::now(), RunFinished))
+                    .send(JobMessage(path.to_path_buf(), Instant::now(), RunDiscarded))
                     .expect("Receiver shouldn't die while we're still sending messages");
+                continue;
+            }
+        }
+        let outcome = match result {
+            Ok(mut run) if run.status.success() => {
+                run.stats.retries = retries;
+                sender
+                    .send(JobMessage(
+                        path.to_path_buf(),
+                        run.finish_time,
+                        RunFinished(run.stats),
+                    ))
+                    .expect("Receiver shouldn't die while we're still sending messages");
+                Some((run.finish_time - start_time).as_secs_f64())
             }
             _ => {
+                let (finish_time, mut stats, exit) = match result {
+                    Ok(run) if run.stats.memory_limit_exceeded => {
+                        (run.finish_time, run.stats, Some(Exit::MemoryLimit))
+                    }
+                    Ok(run) if run.stats.timed_out => {
+                        (run.finish_time, run.stats, Some(Exit::Timeout))
+                    }
+                    Ok(run) => (run.finish_time, run.stats, Exit::of(run.status)),
+                    Err(_) => (Instant::now(), RunStats::default(), None),
+                };
+                // hitting a limit isn't transient
+                if retries < options.retries
+                    && !matches!(exit, Some(Exit::MemoryLimit) | Some(Exit::Timeout))
+                {
+                    retries += 1;
+                    remove_overlay(overlay)?;
+                    sender
+                        .send(JobMessage(
+                            path.to_path_buf(),
+                            Instant::now(),
+                            RunRetried(exit, retries),
+                        ))
+                        .expect("Receiver shouldn't die while we're still sending messages");
+                    continue;
+                }
+                stats.retries = retries;
+                stats.log = log_path;
+                stats.log_tail = log_tail;
+                // this needs to happen before the next iteration cleans the proof directory
+                if let Some(failure_artifacts) = &options.failure_artifacts {
+                    let artifact_name = variant_name(path, target, options);
+                    // with an overlay the outputs of the run are only in its writable layer
+                    let outputs = match &overlay {
+                        Some(overlay) => overlay.written_files(path),
+                        None => path.to_path_buf(),
+                    };
+                    match artifacts::preserve(failure_artifacts, &outputs, &artifact_name, run_nr) {
+                        Ok(target) => println!("PRESERVED failed run in {}", target.display()),
+                        Err(err) => {
+                            eprintln!("ERROR preserving failed run of {}: {}", path.display(), err)
+                        }
+                    }
+                }
                 sender
-                    .send(JobMessage(path.to_path_buf(), Instant::now(), RunFailed))
+                    .send(JobMessage(
+                        path.to_path_buf(),
+                        finish_time,
+                        RunFailed(stats, exit),
+                    ))
                     .expect("Receiver shouldn't die while we're still sending messages");
+                None
             }
-        }
+        };
+        remove_overlay(overlay)?;
+        return Ok(outcome);
     }
+}
+
+// with round, only the first round starts the job (and makes the warmup runs) and only the last
+// one finishes it
+fn run_proof(
+    path: &Path,
+    iterations: Iterations,
+    round: Option<(u32, u32)>,
+    options: &RunOptions,
+    sender: &Sender<JobMessage>,
+) -> IOResult<()> {
+    use JobMessagePayload::*;
+    let options = &proof_options(path, options);
+    let (first_round, last_round) = match round {
+        Some((round, rounds)) => (round == 1, round == rounds),
+        None => (true, true),
+    };
     sender
-        .send(JobMessage(path.to_path_buf(), Instant::now(), JobFinished))
+        .send(JobMessage(
+            path.to_path_buf(),
+            Instant::now(),
+            if first_round {
+                JobStarted
+            } else {
+                RoundStarted
+            },
+        ))
         .expect("Receiver shouldn't die while we're still sending messages");
+    let mut first_run = true;
+    let mut cool_down = || {
+        if !first_run {
+            std::thread::sleep(options.cooldown);
+        }
+        first_run = false;
+        power::wait_for(&options.pause);
+    };
+    let source_root = match options.overlay_dir {
+        Some(_) => Some(sandbox::source_root(path)?),
+        None => None,
+    };
+    let warmup = if first_round { options.warmup } else { 0 };
+    for _ in 0..warmup {
+        for (variant, target) in options.targets.iter().enumerate() {
+            if monitor::stopping() {
+                break;
+            }
+            cool_down();
+            let overlay = create_overlay(source_root.as_deref(), options)?;
+            let built = run_make(
+                &options.clean_target,
+                path,
+                options,
+                overlay.as_ref(),
+                None,
+                None,
+            )
+            .and_then(|_| run_make("goto", path, options, overlay.as_ref(), None, None));
+            match built {
+                Err(_) if monitor::runs_killed() => {
+                    remove_overlay(overlay)?;
+                    break;
+                }
+                built => {
+                    built?;
+                }
+            }
+            sender
+                .send(JobMessage(
+                    path.to_path_buf(),
+                    Instant::now(),
+                    VariantSelected(variant),
+                ))
+                .expect("Receiver shouldn't die while we're still sending messages");
+            sender
+                .send(JobMessage(
+                    path.to_path_buf(),
+                    Instant::now(),
+                    WarmupStarted,
+                ))
+                .expect("Receiver shouldn't die while we're still sending messages");
+            // warmup runs are only there to get caches etc into a steady state, so we don't
+            // care whether they fail
+            let (mut make, kill) =
+                make_command(target, path, options, overlay.as_ref(), None, None, None)?;
+            let _ = monitor::run(&mut make, kill, options.limits);
+            remove_overlay(overlay)?;
+            sender
+                .send(JobMessage(
+                    path.to_path_buf(),
+                    Instant::now(),
+                    WarmupFinished,
+                ))
+                .expect("Receiver shouldn't die while we're still sending messages");
+        }
+    }
+    // the runs of the rounds before this one, which log and keep their artifacts under their own
+    // numbers
+    let previous_runs = round.map_or(0, |(round, _)| round - 1);
+    let mut runs = 0;
+    let mut runtimes = vec![Vec::new(); options.targets.len()];
+    let mut suspect_reruns = 0;
+    let mut outlier_reruns = 0;
+    while !monitor::stopping()
+        && !runtimes
+            .iter()
+            .all(|variant_runtimes| iterations.done(runs - outlier_reruns, variant_runtimes))
+    {
+        runs += 1;
+        let run_nr = previous_runs + runs;
+        let mut found_outlier = false;
+        for i in 0..options.targets.len() {
+            if monitor::stopping() {
+                break;
+            }
+            // alternate the order of the variants so neither is systematically favoured by
+            // running first
+            let variant = if run_nr % 2 == 1 {
+                i
+            } else {
+                options.targets.len() - 1 - i
+            };
+            sender
+                .send(JobMessage(
+                    path.to_path_buf(),
+                    Instant::now(),
+                    VariantSelected(variant),
+                ))
+                .expect("Receiver shouldn't die while we're still sending messages");
+            cool_down();
+            let run_options = match (options.auto_timeout, runtimes[variant].first()) {
+                (Some(factor), Some(first_runtime)) => RunOptions {
+                    limits: monitor::Limits {
+                        // no limit at all if it is too long for a Duration
+                        time: Duration::try_from_secs_f64(first_runtime * factor).ok(),
+                        ..options.limits
+                    },
+                    ..options.clone()
+                },
+                _ => options.clone(),
+            };
+            if let Some(runtime) = run_target(
+                path,
+                &options.targets[variant],
+                run_nr,
+                &run_options,
+                source_root.as_deref(),
+                &mut suspect_reruns,
+                sender,
+            )? {
+                let outlier_sigmas = options.outlier_threshold.and_then(|threshold| {
+                    stats::outlier_sigmas(runtime, &runtimes[variant])
+                        .filter(|&sigmas| sigmas > threshold)
+                });
+                match outlier_sigmas {
+                    Some(sigmas) if outlier_reruns < MAX_OUTLIER_RERUNS => {
+                        found_outlier = true;
+                        sender
+                            .send(JobMessage(
+                                path.to_path_buf(),
+                                Instant::now(),
+                                RunOutlier(sigmas),
+                            ))
+                            .expect("Receiver shouldn't die while we're still sending messages");
+                    }
+                    _ => runtimes[variant].push(runtime),
+                }
+            }
+        }
+        if found_outlier {
+            outlier_reruns += 1;
+        }
+    }
+    if last_round {
+        sender
+            .send(JobMessage(
+                path.to_path_buf(),
+                Instant::now(),
+                JobFinished(None),
+            ))
+            .expect("Receiver shouldn't die while we're still sending messages");
+    }
     Ok(())
 }
 
-fn to_proof_dir(maybe_entry: IOResult<std::fs::DirEntry>) -> Option<PathBuf> {
+fn to_proof_dir(maybe_entry: IOResult<std::fs::DirEntry>, builders: &[Builder]) -> Option<PathBuf> {
     // A proof directory is any subdirectory of an AWS "proofs"
-    // directory that contains a Makefile
+    // directory that contains a Makefile (or the marker of another builder)
     // We're just silently ignoring IO errors (like not having the right read permissions)
     // because these shouldn't come up in practice anyway.
     maybe_entry.ok().and_then(|entry| {
-        if entry.path().join("Makefile").exists() {
+        if builder::detect(builders, &entry.path()).is_some() {
             Some(entry.path())
         } else {
             None
@@ -88,176 +1092,2591 @@ fn to_proof_dir(maybe_entry: IOResult<std::fs::DirEntry>) -> Option<PathBuf> {
     })
 }
 
-fn start_proof_job(receiver: &Receiver<RunProofMessage>, sender: &Sender<JobMessage>) {
+fn start_proof_job(
+    scheduler: &Arc<Scheduler>,
+    sender: &Sender<JobMessage>,
+    options: &RunOptions,
+    cpus: Option<Vec<usize>>,
+    worker: Option<String>,
+) {
     use std::thread::spawn;
     let job_sender = sender.clone();
-    let job_receiver = receiver.clone();
+    let job_scheduler = scheduler.clone();
+    let mut job_options = options.clone();
+    job_options.worker = worker;
+    // restart the job thread whenever it dies, so the session doesn't go on with fewer jobs
     spawn(move || {
-        while let Ok(run_proof_message) = job_receiver.recv() {
-            if let Err(err) = run_proof(
-                &run_proof_message.job_path,
-                run_proof_message.iterations,
-                &job_sender,
-            ) {
+        // the job the job thread is running, to requeue it if the thread dies
+        let current = Arc::new(Mutex::new(None));
+        loop {
+            let job_thread = {
+                let (scheduler, sender, options, cpus, current) = (
+                    job_scheduler.clone(),
+                    job_sender.clone(),
+                    job_options.clone(),
+                    cpus.clone(),
+                    current.clone(),
+                );
+                spawn(move || run_jobs(&scheduler, &sender, &options, cpus.as_deref(), &current))
+            };
+            let panic = match job_thread.join() {
+                Ok(()) => return,
+                Err(panic) => panic,
+            };
+            eprintln!(
+                "ERROR a job thread died ({}), starting a new one",
+                panic_message(&panic)
+            );
+            let job: Option<RunProofMessage> = current
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take();
+            if let Some(job) = &job {
+                // a requeued round doesn't start the job again, so the runs it made so far would
+                // be recorded twice
+                let _ = job_sender.send(JobMessage(
+                    job.job_path.clone(),
+                    Instant::now(),
+                    JobMessagePayload::JobDied,
+                ));
+            }
+            match job {
+                Some(job) if job.restarts < MAX_JOB_RESTARTS && !monitor::stopping() => {
+                    eprintln!("REQUEUED {}", job.job_path.display());
+                    job_scheduler.requeue(RunProofMessage {
+                        restarts: job.restarts + 1,
+                        ..job
+                    });
+                }
+                Some(job) => {
+                    eprintln!(
+                        "ERROR giving up on {} after its job thread died {} times",
+                        job.job_path.display(),
+                        job.restarts + 1
+                    );
+                    // like an error in the job, its later rounds are left out
+                    if job.round.is_some() {
+                        job_scheduler.drop_jobs(&job.job_path);
+                    }
+                    let _ = job_sender.send(JobMessage(
+                        job.job_path.clone(),
+                        Instant::now(),
+                        JobMessagePayload::JobFinished(Some(format!(
+                            "job thread died {} times",
+                            job.restarts + 1
+                        ))),
+                    ));
+                    job_scheduler.finish_job(&job);
+                }
+                None => {}
+            }
+        }
+    });
+}
+
+// run the jobs the scheduler hands out until there are none left, keeping the one in progress in
+// current
+fn run_jobs(
+    scheduler: &Scheduler,
+    sender: &Sender<JobMessage>,
+    options: &RunOptions,
+    cpus: Option<&[usize]>,
+    current: &Mutex<Option<RunProofMessage>>,
+) {
+    if let Some(cpus) = cpus {
+        if let Err(err) = affinity::pin_current_thread(cpus) {
+            eprintln!(
+                "ERROR pinning a job to CPUs {}, it runs unpinned: {}",
+                affinity::format_cpus(cpus),
+                err
+            );
+        }
+    }
+    let mut first_proof = true;
+    while let Some(run_proof_message) = scheduler.next_job() {
+        *current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(run_proof_message.clone());
+        // the proofs that didn't start before an interrupt or running out of time are left out
+        if !monitor::stopping() {
+            if options.cooldown_between_proofs && !first_proof {
+                std::thread::sleep(options.cooldown);
+            }
+            first_proof = false;
+            let run = || {
+                run_proof(
+                    &run_proof_message.job_path,
+                    run_proof_message.iterations,
+                    run_proof_message.round,
+                    options,
+                    sender,
+                )
+            };
+            let error = if options.robust {
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(run)) {
+                    Ok(result) => result.err().map(|err| err.to_string()),
+                    Err(panic) => Some(format!("panicked: {}", panic_message(&panic))),
+                }
+            } else {
+                run().err().map(|err| err.to_string())
+            };
+            if let Some(error) = error {
                 eprintln!(
                     "ERROR running job {}: {}",
-                    &run_proof_message.job_path.to_str().unwrap(),
-                    err
+                    run_proof_message.job_path.display(),
+                    error
                 );
+                // the proof is done with its first error, so it is only finished (and its results
+                // written) once
+                if run_proof_message.round.is_some() {
+                    scheduler.drop_jobs(&run_proof_message.job_path);
+                }
+                // the job didn't get to finish itself, without --robust its runs are lost
+                if options.robust {
+                    let _ = sender.send(JobMessage(
+                        run_proof_message.job_path.clone(),
+                        Instant::now(),
+                        JobMessagePayload::JobFinished(Some(error)),
+                    ));
+                }
             }
         }
-    });
+        scheduler.finish_job(&run_proof_message);
+        *current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
 }
 
-// run all proofs in proofs_path in parallel with parallel_jobs parallel jobs and send run messages
-// to sender.
-fn run_all_proofs_in(
+// mark the results of a proof as errored, once
+fn tag_errored(variants: &mut [ProofResults]) {
+    for variant_results in variants.iter_mut() {
+        if !variant_results.tags.iter().any(|tag| tag == "errored") {
+            variant_results.tags.push("errored".to_string());
+        }
+    }
+}
+
+// the message a panic was started with, as far as it is a string
+fn panic_message(panic: &Box<dyn std::any::Any + Send>) -> String {
+    match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "without a message".to_string(),
+    }
+}
+
+// AWS proof Makefiles mark harnesses that take very long with EXPENSIVE = true
+fn is_expensive(proof_dir: &Path) -> bool {
+    makefile_variable(proof_dir, "EXPENSIVE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+// whether the proof in proof_dir runs with no other proofs at the same time, because its Makefile
+// sets BENCHMARK_EXCLUSIVE = true or its name matches one of the patterns
+fn is_exclusive(proof_dir: &Path, patterns: &[String]) -> bool {
+    pattern::matches_any(patterns, &proof_name(proof_dir))
+        || makefile_variable(proof_dir, EXCLUSIVE_VARIABLE)
+            .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+// the value a variable is set to in the Makefile of a proof, if it is set there
+fn makefile_variable(proof_dir: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(proof_dir.join("Makefile"))
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            let (variable, value) = line.split_once('=')?;
+            let variable = variable.trim_end_matches([':', '?', '+']).trim();
+            Some(value.trim().to_string()).filter(|_| variable == name)
+        })
+}
+
+// the hardware requirements the Makefile of a proof declares
+fn proof_requirements(proof_dir: &Path) -> GenericResult<Vec<Requirement>> {
+    match makefile_variable(proof_dir, requirements::REQUIRES_VARIABLE) {
+        Some(value) => requirements::parse(&value)
+            .map_err(|err| format!("{}: {}", proof_dir.join("Makefile").display(), err).into()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn proof_tags(proof_dir: &Path) -> Vec<String> {
+    let mut tags = Vec::new();
+    if is_expensive(proof_dir) {
+        tags.push("expensive".to_string());
+    }
+    tags
+}
+
+// files and directories that only show up in build output, which can have a Makefile as well
+const BUILD_OUTPUT_MARKERS: &[&str] = &[
+    "CMakeCache.txt",
+    "CMakeFiles",
+    // litani's cache and run directories
+    ".litani_cache_dir",
+    "run.json",
+];
+
+// the marker that gives away dir as build output rather than a proof, if any
+fn build_output_marker(dir: &Path) -> Option<&'static str> {
+    BUILD_OUTPUT_MARKERS
+        .iter()
+        .find(|marker| dir.join(marker).exists())
+        .copied()
+}
+
+// whether proof_dir looks like build output, with a warning if it does
+fn is_build_output(proof_dir: &Path) -> bool {
+    match build_output_marker(proof_dir) {
+        Some(marker) => {
+            println!(
+                "WARNING: skipping {}, it looks like build output rather than a proof (has {})",
+                proof_dir.display(),
+                marker
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+// add the proof directories below directory to proof_dirs, which are those directly below
+// proofs_path (top) and those with a "proofs" ancestor, not looking deeper than depth levels or into
+// proofs, build output, hidden directories and symlinks
+fn walk_proof_dirs(
+    directory: &Path,
+    top: bool,
+    in_proofs: bool,
+    depth: Option<usize>,
+    builders: &[Builder],
+    proof_dirs: &mut Vec<PathBuf>,
+) -> IOResult<()> {
+    if depth == Some(0) {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(directory)?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        if builder::detect(builders, &path).is_some() {
+            if is_build_output(&path) {
+                continue;
+            }
+            if top || in_proofs {
+                proof_dirs.push(path);
+                continue;
+            }
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let in_proofs = in_proofs || entry.file_name() == "proofs";
+        // like everywhere else, IO errors below the proofs directory are ignored
+        let _ = walk_proof_dirs(
+            &path,
+            false,
+            in_proofs,
+            depth.map(|depth| depth - 1),
+            builders,
+            proof_dirs,
+        );
+    }
+    Ok(())
+}
+
+// find all proof directories directly below proofs_path, or with recursive anywhere below it up to
+// max_depth levels, in sorted order, skipping build output
+fn discover_proof_dirs(
     proofs_path: &Path,
-    iterations: u32,
-    parallel_jobs: u32,
+    builders: &[Builder],
+    recursive: bool,
+    max_depth: Option<usize>,
+) -> GenericResult<Vec<PathBuf>> {
+    let mut proof_dirs = Vec::new();
+    if recursive {
+        // proofs_path can be the proofs directory itself or a directory further up
+        let in_proofs = proofs_path
+            .canonicalize()?
+            .file_name()
+            .is_some_and(|name| name == "proofs");
+        walk_proof_dirs(
+            proofs_path,
+            true,
+            in_proofs,
+            max_depth,
+            builders,
+            &mut proof_dirs,
+        )?;
+    } else {
+        proof_dirs = std::fs::read_dir(proofs_path)?
+            .filter_map(|entry| to_proof_dir(entry, builders))
+            .filter(|proof_dir| !is_build_output(proof_dir))
+            .collect();
+    }
+    proof_dirs.sort();
+    // everything is keyed by the name of the proof directory
+    for (index, proof_dir) in proof_dirs.iter().enumerate() {
+        let name = proof_name(proof_dir);
+        if let Some(other) = proof_dirs[index + 1..]
+            .iter()
+            .find(|other| proof_name(other) == name)
+        {
+            return Err(format!(
+                "{} and {} are both proofs named {}, pass the ones to benchmark with --proof-dir",
+                proof_dir.display(),
+                other.display(),
+                name
+            )
+            .into());
+        }
+    }
+    Ok(proof_dirs)
+}
+
+// names of the groups the proof in proof_dir belongs to
+fn proof_groups(proof_dir: &Path, groups: &[ProofGroup]) -> Vec<String> {
+    let name = proof_name(proof_dir);
+    groups
+        .iter()
+        .filter(|group| group.contains(&name))
+        .map(|group| group.name.clone())
+        .collect()
+}
+
+// run all proofs in proof_dirs in parallel with parallel_jobs parallel jobs and send run messages
+// to sender.
+fn run_all_proofs(
+    proof_dirs: Vec<PathBuf>,
+    iterations: Iterations,
+    options: &RunOptions,
+    schedule: &ScheduleOptions,
     sender: Sender<JobMessage>,
 ) -> IOResult<usize> {
-    use std::fs::read_dir;
-    let proof_dirs = {
-        let mut proof_dirs_mut: Vec<PathBuf> =
-            read_dir(proofs_path)?.filter_map(to_proof_dir).collect();
-        proof_dirs_mut.sort();
-        proof_dirs_mut
-    };
     let nr_of_jobs = proof_dirs.len();
-    let (job_run_sender, job_run_receiver) = crossbeam_channel::unbounded();
+
+    // Queue up proof jobs, with --interleave a job per round and proof, round after round
+    let rounds: Vec<Option<(u32, u32)>> = if schedule.interleave {
+        let rounds = iterations.max();
+        (1..=rounds).map(|round| Some((round, rounds))).collect()
+    } else {
+        vec![None]
+    };
+    let jobs = rounds
+        .into_iter()
+        .flat_map(|round| {
+            proof_dirs.iter().map(move |proof_dir| RunProofMessage {
+                groups: proof_groups(proof_dir, &schedule.groups),
+                memory_bytes: schedule.expected_memory.get(proof_dir).cloned(),
+                job_path: proof_dir.clone(),
+                iterations: match round {
+                    Some(_) => Iterations::Fixed(1),
+                    None => iterations,
+                },
+                round,
+                after: schedule
+                    .dependencies
+                    .get(proof_dir)
+                    .cloned()
+                    .unwrap_or_default(),
+                exclusive: is_exclusive(proof_dir, &schedule.exclusive),
+                restarts: 0,
+            })
+        })
+        .collect();
+    let scheduler = Arc::new(Scheduler::new(
+        jobs,
+        &schedule.groups,
+        schedule.admission,
+        schedule.memory_budget,
+    ));
+
+    let cpu_sets: Vec<Option<Vec<usize>>> = match &schedule.cpu_sets {
+        Some(cpu_sets) => {
+            for (job, cpu_set) in cpu_sets.iter().enumerate() {
+                println!(
+                    "PINNED job {} to CPUs {}",
+                    job + 1,
+                    affinity::format_cpus(cpu_set)
+                );
+            }
+            cpu_sets.iter().cloned().map(Some).collect()
+        }
+        None => vec![None; schedule.parallel_jobs as usize],
+    };
+
+    // with --workers as many jobs per worker host as it takes
+    let workers: Vec<Option<String>> = if schedule.workers.is_empty() {
+        vec![None; cpu_sets.len()]
+    } else {
+        for worker in schedule.workers.iter() {
+            println!("WORKER {} runs {} jobs", worker.host, worker.slots);
+        }
+        schedule
+            .workers
+            .iter()
+            .flat_map(|worker| vec![Some(worker.host.clone()); worker.slots as usize])
+            .collect()
+    };
 
     // Create <parallel-jobs> proof executor threads
-    for _ in 0..parallel_jobs {
-        start_proof_job(&job_run_receiver, &sender);
+    for (cpus, worker) in cpu_sets.into_iter().zip(workers) {
+        start_proof_job(&scheduler, &sender, options, cpus, worker);
     }
 
-    // Queue up proof jobs
+    // Return the number of proofs (just needed for progress message really)
+    Ok(nr_of_jobs)
+}
+
+// value of the variants of the proof in proof_dir in the first of prior_results that has all of
+// them and for which there is a value
+fn from_prior_results<T>(
+    proof_dir: &Path,
+    prior_results: &[&ResultFile],
+    options: &RunOptions,
+    names: &NameMap,
+    value: impl Fn(&[&ProofResults]) -> Option<T>,
+) -> Option<T> {
+    prior_results.iter().find_map(|prior| {
+        let variants = options
+            .targets
+            .iter()
+            .map(|target| {
+                let name = names.display(&variant_name(proof_dir, target, options));
+                prior.proofs.iter().find(|proof| proof.name == name)
+            })
+            .collect::<Option<Vec<&ProofResults>>>()?;
+        value(&variants)
+    })
+}
+
+// the peak memory the proof in proof_dir is expected to take up, as declared with memory>= in its
+// BENCHMARK_REQUIRES or else the highest peak RSS of its variants in prior_results
+fn expected_memory_bytes(
+    proof_dir: &Path,
+    prior_results: &[&ResultFile],
+    options: &RunOptions,
+    names: &NameMap,
+) -> Option<u64> {
+    let declared = proof_requirements(proof_dir)
+        .ok()?
+        .iter()
+        .find_map(|requirement| match requirement {
+            Requirement::Memory(bytes) => Some(*bytes),
+            _ => None,
+        });
+    declared.or_else(|| {
+        from_prior_results(proof_dir, prior_results, options, names, |variants| {
+            variants
+                .iter()
+                .filter_map(|variant| variant.max_rss_kib)
+                .max()
+        })
+        .map(|kib| kib * 1024)
+    })
+}
+
+// proof_dirs in a random order that only depends on seed (Fisher-Yates)
+fn shuffled(mut proof_dirs: Vec<PathBuf>, seed: u64) -> Vec<PathBuf> {
+    // xorshift needs a state other than 0
+    let mut state = (seed ^ SHUFFLE_SEED_MASK).max(1);
+    for i in (1..proof_dirs.len()).rev() {
+        let j = (stats::xorshift(&mut state) % (i as u64 + 1)) as usize;
+        proof_dirs.swap(i, j);
+    }
+    proof_dirs
+}
+
+// a seed for --shuffle without --seed
+fn random_seed() -> IOResult<u64> {
+    use std::io::Read;
+    let mut bytes = [0u8; 8];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+// the other proofs every proof in proof_dirs has to run after, failing if they depend on each
+// other in a cycle, which would hold them back forever
+fn proof_dependencies(
+    proof_dirs: &[PathBuf],
+    dependencies: &[Dependency],
+) -> GenericResult<HashMap<PathBuf, Vec<PathBuf>>> {
+    let mut after: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
     for proof_dir in proof_dirs.iter() {
-        job_run_sender
-            .send(RunProofMessage {
-                job_path: proof_dir.clone(),
-                iterations,
+        let name = proof_name(proof_dir);
+        let proofs_before: Vec<PathBuf> = proof_dirs
+            .iter()
+            .filter(|other| {
+                *other != proof_dir
+                    && dependencies.iter().any(|dependency| {
+                        pattern::matches(&dependency.pattern, &name)
+                            && pattern::matches_any(&dependency.after, &proof_name(other))
+                    })
             })
-            .expect("there should be always at least one job listening to job run requests");
+            .cloned()
+            .collect();
+        if !proofs_before.is_empty() {
+            after.insert(proof_dir.clone(), proofs_before);
+        }
+    }
+    // depth first, a proof on the current path that is reached again closes a cycle
+    fn check(
+        proof_dir: &Path,
+        after: &HashMap<PathBuf, Vec<PathBuf>>,
+        path: &mut Vec<PathBuf>,
+        checked: &mut HashSet<PathBuf>,
+    ) -> GenericResult<()> {
+        if let Some(start) = path.iter().position(|on_path| on_path == proof_dir) {
+            let cycle: Vec<String> = path[start..]
+                .iter()
+                .chain(std::iter::once(&path[start]))
+                .map(|proof_dir| proof_name(proof_dir))
+                .collect();
+            return Err(format!(
+                "--dependencies-file has a cycle, {} would never start",
+                cycle.join(" after ")
+            )
+            .into());
+        }
+        if !checked.insert(proof_dir.to_path_buf()) {
+            return Ok(());
+        }
+        path.push(proof_dir.to_path_buf());
+        for before in after.get(proof_dir).into_iter().flatten() {
+            check(before, after, path, checked)?;
+        }
+        path.pop();
+        Ok(())
     }
+    let mut checked = HashSet::new();
+    for proof_dir in proof_dirs.iter() {
+        check(proof_dir, &after, &mut Vec::new(), &mut checked)?;
+    }
+    Ok(after)
+}
 
-    // Return the number of proofs (just needed for progress message really)
-    Ok(nr_of_jobs)
+// proof_dirs with those matching priorities first, in the order of the first pattern they match,
+// and the others after them in the order they were in
+fn prioritized(proof_dirs: Vec<PathBuf>, priorities: &[String]) -> Vec<PathBuf> {
+    for priority in priorities.iter() {
+        if !proof_dirs
+            .iter()
+            .any(|proof_dir| pattern::matches(priority, &proof_name(proof_dir)))
+        {
+            println!(
+                "WARNING --priority-file lists {}, which matches no proof",
+                priority
+            );
+        }
+    }
+    let mut ordered: Vec<(usize, PathBuf)> = proof_dirs
+        .into_iter()
+        .map(|proof_dir| {
+            let name = proof_name(&proof_dir);
+            let rank = priorities
+                .iter()
+                .position(|priority| pattern::matches(priority, &name))
+                .unwrap_or(priorities.len());
+            (rank, proof_dir)
+        })
+        .collect();
+    ordered.sort_by_key(|(rank, _)| *rank);
+    let first = ordered
+        .iter()
+        .filter(|(rank, _)| *rank < priorities.len())
+        .count();
+    println!("PRIORITIZED {} proofs, they start first", first);
+    ordered
+        .into_iter()
+        .map(|(_, proof_dir)| proof_dir)
+        .collect()
 }
 
-fn dump_csv<'a, RunResults: Iterator<Item = &'a Option<Duration>>>(
-    job_name: &str,
-    run_results: RunResults,
-    csv_file: &mut File,
-) -> IOResult<()> {
-    csv_file.write(job_name.as_bytes())?;
-    for run in run_results {
-        csv_file.write(",".as_bytes())?;
-        if let Some(runtime) = run {
-            csv_file.write(format!("{}", runtime.as_secs_f32()).as_bytes())?;
+// proof_dirs ordered by their runtime in the first of prior_results that has all their variants,
+// longest first, so a slow proof doesn't start last and hold up the end of the session; proofs
+// without one go first since they may be just as slow, otherwise the order is kept
+fn longest_first(
+    proof_dirs: Vec<PathBuf>,
+    prior_results: &[&ResultFile],
+    options: &RunOptions,
+    names: &NameMap,
+) -> Vec<PathBuf> {
+    let expected_runtime = |proof_dir: &Path| {
+        from_prior_results(proof_dir, prior_results, options, names, |variants| {
+            variants
+                .iter()
+                .map(|variant| variant.runtime(Statistic::Mean))
+                .sum::<Option<f64>>()
+        })
+    };
+    let mut ordered: Vec<(PathBuf, Option<f64>)> = proof_dirs
+        .into_iter()
+        .map(|proof_dir| {
+            let runtime = expected_runtime(&proof_dir);
+            (proof_dir, runtime)
+        })
+        .collect();
+    ordered.sort_by(|(_, a), (_, b)| {
+        b.unwrap_or(f64::INFINITY)
+            .total_cmp(&a.unwrap_or(f64::INFINITY))
+    });
+    let unknown = ordered
+        .iter()
+        .filter(|(_, runtime)| runtime.is_none())
+        .count();
+    println!(
+        "ORDERED proofs longest first by their prior runtimes ({} without one first)",
+        unknown
+    );
+    ordered
+        .into_iter()
+        .map(|(proof_dir, _)| proof_dir)
+        .collect()
+}
+
+// print the effective configuration for each proof, in the order they start in, instead of running
+// anything
+fn print_plan(
+    proof_dirs: &[PathBuf],
+    iterations: Iterations,
+    options: &RunOptions,
+    schedule: &ScheduleOptions,
+) {
+    for (rank, proof_dir) in proof_dirs.iter().enumerate() {
+        println!(
+            "{}",
+            proof_dir
+                .file_name()
+                .expect("proof paths do not end in ..")
+                .to_string_lossy()
+        );
+        println!("  path: {}", proof_dir.display());
+        println!("  start order: {} of {}", rank + 1, proof_dirs.len());
+        println!("  iterations: {}", iterations);
+        println!("  warmup: {}", options.warmup);
+        if let Some(builder) = builder::detect(&options.builders, proof_dir) {
+            println!("  builder: {}", builder);
+        }
+        if let Some(make_jobs) = options.make_jobs {
+            println!("  make jobs: {}", make_jobs);
+        }
+        if options.coarse_timing {
+            println!(
+                "  timing: {}, goto and target together",
+                options.clean_target
+            );
+        }
+        if options.cooldown > Duration::ZERO {
+            println!(
+                "  cooldown: {}s{}",
+                options.cooldown.as_secs_f32(),
+                if options.cooldown_between_proofs {
+                    ", also between proofs"
+                } else {
+                    ""
+                }
+            );
+        }
+        if options.no_network {
+            println!("  network: disabled");
+        }
+        if !schedule.workers.is_empty() {
+            let hosts: Vec<&str> = schedule
+                .workers
+                .iter()
+                .map(|worker| worker.host.as_str())
+                .collect();
+            println!("  workers: {}", hosts.join(", "));
+        }
+        if let Some(container) = &options.container {
+            println!("  container: {}", container.image);
+        }
+        if let Some(batch) = &options.aws_batch {
+            println!(
+                "  aws batch: queue {}, job definition {}",
+                batch.queue, batch.job_definition
+            );
+        }
+        if options.perf_stat {
+            println!("  perf stat: instructions, cycles, cache misses");
+        }
+        if let Some(page_cache) = options.page_cache {
+            println!("  page cache: {}", page_cache);
+        }
+        if !options.phases.is_empty() {
+            println!("  timed phases: {}", options.phases.join(" "));
+        }
+        if options.tool_times {
+            println!("  tool times: goto-cc and goto-instrument, cbmc");
+        }
+        if options.overlay_dir.is_some() {
+            println!(
+                "  read-only sources: {}",
+                sandbox::source_root(proof_dir)
+                    .unwrap_or_else(|_| proof_dir.clone())
+                    .display()
+            );
+        }
+        println!(
+            "  make targets: {} goto {}",
+            options.clean_target,
+            options.targets.join(" | ")
+        );
+        let proof_options = proof_options(proof_dir, options);
+        match (proof_options.limits.time, proof_options.auto_timeout) {
+            (Some(timeout), _) => println!("  timeout: {}s", timeout.as_secs_f64()),
+            (None, Some(factor)) => println!("  timeout: {}x the first successful run", factor),
+            (None, None) => {}
+        }
+        let tags = proof_tags(proof_dir);
+        if !tags.is_empty() {
+            println!("  tags: {}", tags.join(" "));
+        }
+        if let Ok(requirements) = proof_requirements(proof_dir) {
+            if !requirements.is_empty() {
+                let requirements: Vec<String> =
+                    requirements.iter().map(ToString::to_string).collect();
+                println!("  requires: {}", requirements.join(" "));
+            }
+        }
+        let proof_groups = proof_groups(proof_dir, &schedule.groups);
+        if !proof_groups.is_empty() {
+            println!("  groups: {}", proof_groups.join(" "));
+        }
+        if is_exclusive(proof_dir, &schedule.exclusive) {
+            println!("  exclusive: runs with no other proofs");
+        }
+        if let Some(after) = schedule.dependencies.get(proof_dir) {
+            let names: Vec<String> = after.iter().map(|proof| proof_name(proof)).collect();
+            println!("  after: {}", names.join(" "));
+        }
+        if let Some(memory_bytes) = schedule.expected_memory.get(proof_dir) {
+            println!("  expected memory: {}MiB", memory_bytes >> 20);
+        }
+        // on top of the environment of the session, with a placeholder for the id of each run
+        let mut environment: Vec<String> = Vec::new();
+        if let Ok(command) = builder_command(
+            &options.targets[0],
+            proof_dir,
+            options,
+            Some("<run id>"),
+            None,
+        ) {
+            for (variable, value) in command.get_envs() {
+                if let Some(value) = value {
+                    environment.push(format!(
+                        "{}={}",
+                        variable.to_string_lossy(),
+                        value.to_string_lossy()
+                    ));
+                }
+            }
+        }
+        if options.aws_batch.is_some() {
+            environment.push(format!(
+                "{}={}",
+                batch::BATCH_TIMES_VARIABLE,
+                batch::times_path("<run id>").display()
+            ));
+        }
+        println!("  environment: {}", environment.join(" "));
+        if let Some(failure_artifacts) = &options.failure_artifacts {
+            println!(
+                "  failure artifacts: {}",
+                failure_artifacts.directory.display()
+            );
         }
     }
-    csv_file.write("\n".as_bytes())?;
-    csv_file.flush()
 }
 
-fn benchmark_all_proofs_in(
-    path: &Path,
-    iterations: u32,
-    parallel_jobs: u32,
-    csv_path: &Path,
+// where the results of a session are written to
+struct OutputOptions {
+    csv_file: PathBuf,
+    // details of every single measured run
+    runs_file: Option<PathBuf>,
+    // for the runs file once the session is done
+    compression: Option<Compression>,
+    // kept up to date with a snapshot of the progress of the session
+    progress_file: Option<PathBuf>,
+    // a line about the jobs and runs so far every timeline_interval
+    timeline_file: Option<PathBuf>,
+    timeline_interval: Duration,
+    // every failed run, for triage
+    failures_file: Option<PathBuf>,
+    // every proof once it is done, and whether to skip the proofs already in it
+    state_file: Option<PathBuf>,
+    resume: bool,
+}
+
+fn benchmark_all_proofs(
+    proof_dirs: Vec<PathBuf>,
+    iterations: Iterations,
+    output: &OutputOptions,
+    metadata: &RunMetadata,
+    options: &RunOptions,
+    schedule: &ScheduleOptions,
+    report_options: &ReportOptions,
 ) -> GenericResult<()> {
-    let mut csv_file = OpenOptions::new().create(true).write(true).open(csv_path)?;
+    let mut csv_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&output.csv_file)?;
+    dump_metadata(metadata, &mut csv_file)?;
+    let (mut state_file, completed) = match &output.state_file {
+        Some(state_path) => {
+            let (state_file, completed) = StateFile::open(state_path, output.resume, metadata)?;
+            (Some(state_file), completed)
+        }
+        None => (None, Vec::new()),
+    };
+    // the variants of the proofs a previous session with the same state file completed
+    let mut resumed: Vec<Vec<ProofResults>> = Vec::new();
+    let mut completed: HashMap<String, ProofResults> = completed
+        .into_iter()
+        .map(|proof| (proof.name.clone(), proof))
+        .collect();
+    let proof_dirs: Vec<PathBuf> = proof_dirs
+        .into_iter()
+        .filter(|proof_dir| {
+            let names: Vec<String> = options
+                .targets
+                .iter()
+                .map(|target| variant_name(proof_dir, target, options))
+                .collect();
+            if !names.iter().all(|name| completed.contains_key(name)) {
+                return true;
+            }
+            resumed.push(
+                names
+                    .iter()
+                    .filter_map(|name| completed.remove(name))
+                    .collect(),
+            );
+            false
+        })
+        .collect();
+    if output.resume {
+        println!(
+            "RESUMING, skipping {} proofs that were already completed",
+            resumed.len()
+        );
+    }
+    for variants in resumed.iter() {
+        for variant_results in variants.iter() {
+            dump_csv(variant_results, &mut csv_file)?;
+        }
+    }
+    let mut runs_file = match &output.runs_file {
+        Some(runs_path) => {
+            let mut runs_file = File::create(runs_path)?;
+            dump_metadata(metadata, &mut runs_file)?;
+            results::dump_runs_header(&mut runs_file)?;
+            Some(runs_file)
+        }
+        None => None,
+    };
     let (sender, receiver) = crossbeam_channel::unbounded();
-    let mut proof_runtimes: HashMap<PathBuf, Vec<Option<Duration>>> = HashMap::new();
+    // results per variant (see RunOptions::targets)
+    let mut proof_runtimes: HashMap<PathBuf, Vec<ProofResults>> = HashMap::new();
+    let mut current_variant: HashMap<PathBuf, usize> = HashMap::new();
     let mut started_runs: HashMap<PathBuf, Instant> = HashMap::new();
-    let nr_of_jobs = run_all_proofs_in(path, iterations, parallel_jobs, sender)?;
+    let mut run_ids: HashMap<PathBuf, String> = HashMap::new();
+    // how many runs every variant had when the current round of a job started
+    let mut round_start_runs: HashMap<PathBuf, Vec<usize>> = HashMap::new();
+    let mut warmup_runs: HashMap<(PathBuf, usize), u32> = HashMap::new();
+    let mut failures = Vec::new();
+    let mut spawn_times = Vec::new();
+    // where the proofs get built, for the free disk space
+    let build_directory = proof_dirs
+        .first()
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("."));
+    let start_environment = environment::sample(&build_directory);
+    let mut middle_environment = None;
+    let max_iterations = iterations.max();
+    let progress = Progress::new(
+        proof_dirs
+            .iter()
+            .map(|proof_dir| proof_name(proof_dir))
+            .collect(),
+        max_iterations as usize * options.targets.len(),
+    );
+    let progress_writer = output
+        .progress_file
+        .clone()
+        .map(|progress_file| ProgressWriter::start(progress.clone(), progress_file));
+    let timeline_writer = match &output.timeline_file {
+        Some(timeline_file) => Some(ProgressWriter::start_timeline(
+            progress.clone(),
+            timeline_file.clone(),
+            output.timeline_interval,
+        )?),
+        None => None,
+    };
+    if let Some(max_total_time) = schedule.max_total_time {
+        monitor::stop_after(max_total_time, schedule.kill_at_time_limit);
+    }
+    let nr_of_jobs = run_all_proofs(proof_dirs, iterations, options, schedule, sender)?;
     let mut completed_jobs = 0;
+    let mut finished_jobs: HashSet<PathBuf> = HashSet::new();
+    // jobs and messages that ran into an error with --robust
+    let mut robust_errors = 0;
+    // the jobs a message of which couldn't be handled with --robust, their results may be off
+    let mut errored_jobs: HashSet<PathBuf> = HashSet::new();
+    // with --fail-fast the proof whose run failed first
+    let mut failed_fast: Option<String> = None;
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let reporter = Reporter::start();
     while let Ok(JobMessage(proof_path, timestamp, message_type)) = receiver.recv() {
-        let job_name = proof_path
-            .file_name()
-            .expect("proof paths do not end in ..")
-            .to_str()
-            .expect("paths should be convertible to utf-8");
-        use JobMessagePayload::*;
-        match message_type {
-            JobStarted => {
-                println!("STARTING {}", job_name);
-                proof_runtimes.insert(proof_path, Vec::new());
-            }
-            JobFinished => {
-                completed_jobs += 1;
-                dump_csv(job_name, proof_runtimes[&proof_path].iter(), &mut csv_file)?;
-                println!("COMPLETED [{}/{}] jobs", completed_jobs, nr_of_jobs);
-            }
-            RunStarted => {
-                started_runs.insert(proof_path.clone(), timestamp);
-                let run_nr = proof_runtimes
-                    .get(&proof_path)
-                    .expect("can not start a run for a job that hasn't started yet")
-                    .len()
-                    + 1;
-                println!("STARTING RUN [{}/{}] for {}", run_nr, iterations, job_name);
-            }
-            RunFailed => {
-                let start_time = started_runs
-                    .remove(&proof_path)
-                    .expect("we cannot finish a run we didn't start first");
-                let runtime = Instant::now() - start_time;
-                let proof_runtime = proof_runtimes
-                    .get_mut(&proof_path)
-                    .expect("we cannot fail a run for a job that hasn't been started");
-                println!(
-                    "FAILED RUN [{}/{}] for {} after {}s",
-                    proof_runtime.len(),
-                    iterations,
-                    job_name,
-                    runtime.as_secs_f32()
-                );
-                proof_runtime.push(None);
-            }
-            RunFinished => {
-                let start_time = started_runs
-                    .remove(&proof_path)
-                    .expect("we cannot finish a run we didn't start first");
-                let runtime = timestamp - start_time;
-                let proof_runtime = proof_runtimes
-                    .get_mut(&proof_path)
-                    .expect("we cannot finish a run in a job that hasn't started yet");
-                proof_runtime.push(Some(runtime));
-                println!(
-                    "FINISHED RUN [{}/{}] for {} after {}s",
-                    proof_runtime.len(),
-                    iterations,
+        // with --robust a panic while handling a message is reported and the message dropped,
+        // instead of taking down the whole session
+        let handle = || -> GenericResult<()> {
+            let variant = current_variant.get(&proof_path).cloned().unwrap_or(0);
+            let job_name = match proof_runtimes.get(&proof_path) {
+                Some(variants) => variants[variant].name.clone(),
+                None => proof_name(&proof_path),
+            };
+            use JobMessagePayload::*;
+            match message_type {
+                JobStarted => {
+                    let tags = proof_tags(&proof_path);
+                    if tags.is_empty() {
+                        reporter.print(format!("STARTING {}", job_name));
+                    } else {
+                        reporter.print(format!("STARTING {} [{}]", job_name, tags.join(" ")));
+                    }
+                    proof_runtimes.insert(
+                        proof_path.clone(),
+                        options
+                            .targets
+                            .iter()
+                            .map(|target| {
+                                let mut results = ProofResults::new(if options.targets.len() > 1 {
+                                    format!("{}@{}", job_name, target)
+                                } else {
+                                    job_name.clone()
+                                });
+                                results.tags = tags.clone();
+                                results
+                            })
+                            .collect(),
+                    );
+                    round_start_runs.insert(proof_path.clone(), vec![0; options.targets.len()]);
+                }
+                RoundStarted => {
+                    let variants = proof_runtimes
+                        .get(&proof_path)
+                        .ok_or("a round can't start in a job that hasn't started yet")?;
+                    round_start_runs.insert(
+                        proof_path.clone(),
+                        variants
+                            .iter()
+                            .map(|variant_results| variant_results.runs.len())
+                            .collect(),
+                    );
+                }
+                JobDied => {
+                    if started_runs.remove(&proof_path).is_some() {
+                        progress.run_discarded(&proof_name(&proof_path));
+                    }
+                    run_ids.remove(&proof_path);
+                    // nothing to discard if it died before the job started or after it finished
+                    if let (Some(variants), Some(round_start), false) = (
+                        proof_runtimes.get_mut(&proof_path),
+                        round_start_runs.get(&proof_path),
+                        finished_jobs.contains(&proof_path),
+                    ) {
+                        let (mut dropped_runs, mut failed_runs) = (0, 0);
+                        for (variant_results, &runs) in variants.iter_mut().zip(round_start) {
+                            let dropped = variant_results.runs.get(runs..).unwrap_or_default();
+                            dropped_runs += dropped.len();
+                            failed_runs += dropped.iter().filter(|run| run.is_none()).count();
+                            variant_results.truncate(runs);
+                        }
+                        progress.runs_dropped(&proof_name(&proof_path), dropped_runs, failed_runs);
+                        if dropped_runs > 0 {
+                            reporter.print_urgent(format!(
+                                "DISCARDED {} of the runs of {}, its job thread died while making them",
+                                dropped_runs, job_name
+                            ));
+                        }
+                    }
+                }
+                JobFinished(error) => {
+                    progress.job_finished(&proof_name(&proof_path));
+                    finished_jobs.insert(proof_path.clone());
+                    completed_jobs += 1;
+                    let errored = error.is_some() || errored_jobs.contains(&proof_path);
+                    let variants = if errored {
+                        // its job thread can die before the job started
+                        proof_runtimes.entry(proof_path.clone()).or_default()
+                    } else {
+                        proof_runtimes
+                            .get_mut(&proof_path)
+                            .ok_or("we cannot finish a job that hasn't started yet")?
+                    };
+                    if let Some(error) = &error {
+                        robust_errors += 1;
+                        reporter.print_urgent(format!(
+                            "ERRORED {} ({}), keeping the runs it made and carrying on",
+                            job_name, error
+                        ));
+                    }
+                    if errored {
+                        tag_errored(variants);
+                    }
+                    for variant_results in variants.iter() {
+                        dump_csv(variant_results, &mut csv_file)?;
+                    }
+                    // an interrupted, out of time or errored proof may be missing runs and has to
+                    // be run again
+                    if let (Some(state_file), false, false) =
+                        (&mut state_file, monitor::stopping(), errored)
+                    {
+                        state_file.record(variants)?;
+                    }
+                    reporter.print(format!(
+                        "COMPLETED [{}/{}] jobs",
+                        completed_jobs, nr_of_jobs
+                    ));
+                    if middle_environment.is_none() && 2 * completed_jobs >= nr_of_jobs {
+                        middle_environment = Some(environment::sample(&build_directory));
+                    }
+                }
+                VariantSelected(variant) => {
+                    current_variant.insert(proof_path.clone(), variant);
+                }
+                WarmupStarted => {
+                    progress.warmup_started(&proof_name(&proof_path));
+                    started_runs.insert(proof_path.clone(), timestamp);
+                    let warmup_nr = warmup_runs
+                        .entry((proof_path.clone(), variant))
+                        .or_insert(0);
+                    *warmup_nr += 1;
+                    reporter.print(format!(
+                        "STARTING WARMUP RUN [{}/{}] for {}",
+                        warmup_nr, options.warmup, job_name
+                    ));
+                }
+                WarmupFinished => {
+                    let start_time = started_runs
+                        .remove(&proof_path)
+                        .ok_or("we cannot finish a warmup run we didn't start first")?;
+                    reporter.print(format!(
+                        "FINISHED WARMUP RUN [{}/{}] for {} after {}s",
+                        warmup_runs[&(proof_path.clone(), variant)],
+                        options.warmup,
+                        job_name,
+                        (timestamp - start_time).as_secs_f32()
+                    ));
+                }
+                RunStarted(run_id) => {
+                    progress.run_started(&proof_name(&proof_path));
+                    started_runs.insert(proof_path.clone(), timestamp);
+                    let run_nr = proof_runtimes
+                        .get(&proof_path)
+                        .ok_or("can not start a run for a job that hasn't started yet")?[variant]
+                        .runs
+                        .len()
+                        + 1;
+                    reporter.print(format!(
+                        "STARTING RUN [{}/{}] for {} ({})",
+                        run_nr, max_iterations, job_name, run_id
+                    ));
+                    run_ids.insert(proof_path.clone(), run_id);
+                }
+                ClockJump(jump) => {
+                    let proof_runtime = &mut proof_runtimes
+                        .get_mut(&proof_path)
+                        .ok_or("the clock can't jump in a job that hasn't started yet")?[variant];
+                    let run_index = proof_runtime.runs.len();
+                    proof_runtime.suspect_runs.push(run_index);
+                    reporter.print(format!(
+                    "WARNING clock jumped by {}s during RUN [{}/{}] for {}, its runtime is suspect",
+                    jump.as_secs_f32(),
+                    run_index + 1,
+                    max_iterations,
+                    job_name
+                ));
+                }
+                discarded @ (RunDiscarded | RunInterrupted) => {
+                    progress.run_discarded(&proof_name(&proof_path));
+                    started_runs.remove(&proof_path);
+                    run_ids.remove(&proof_path);
+                    let proof_runtime = &mut proof_runtimes
+                        .get_mut(&proof_path)
+                        .ok_or("we cannot discard a run in a job that hasn't started yet")?
+                        [variant];
+                    let run_index = proof_runtime.runs.len();
+                    proof_runtime
+                        .suspect_runs
+                        .retain(|&suspect_run| suspect_run != run_index);
+                    reporter.print(format!(
+                        "DISCARDED RUN [{}/{}] for {}, {}",
+                        run_index + 1,
+                        max_iterations,
+                        job_name,
+                        if matches!(discarded, RunInterrupted) {
+                            "it was killed"
+                        } else {
+                            "running it again"
+                        }
+                    ));
+                }
+                RunRetried(exit, retry) => {
+                    progress.run_discarded(&proof_name(&proof_path));
+                    started_runs.remove(&proof_path);
+                    run_ids.remove(&proof_path);
+                    let proof_runtime = &proof_runtimes
+                        .get(&proof_path)
+                        .ok_or("we cannot retry a run in a job that hasn't started yet")?[variant];
+                    reporter.print_urgent(format!(
+                        "RETRYING RUN [{}/{}] for {}, it failed ({}), retry {} of {}",
+                        proof_runtime.runs.len() + 1,
+                        max_iterations,
+                        job_name,
+                        exit.map_or("couldn't be started".to_string(), |exit| exit.to_string()),
+                        retry,
+                        options.retries
+                    ));
+                }
+                RunOutlier(sigmas) => {
+                    let proof_runtime = &mut proof_runtimes
+                        .get_mut(&proof_path)
+                        .ok_or("we cannot replace a run in a job that hasn't started yet")?
+                        [variant];
+                    let run_index = proof_runtime.runs.len() - 1;
+                    proof_runtime.outlier_runs.push(run_index);
+                    reporter.print(format!(
+                    "OUTLIER RUN [{}/{}] for {} deviates by {:.1} standard deviations, replacing it with an extra iteration",
+                    run_index + 1,
+                    max_iterations,
                     job_name,
-                    runtime.as_secs_f32()
-                );
+                    sigmas
+                ));
+                }
+                RunFailed(stats, exit) => {
+                    progress.run_finished(&proof_name(&proof_path), true);
+                    let start_time = started_runs
+                        .remove(&proof_path)
+                        .ok_or("we cannot finish a run we didn't start first")?;
+                    let run_id = run_ids.remove(&proof_path).unwrap_or_default();
+                    let runtime = timestamp - start_time;
+                    let proof_runtime = &mut proof_runtimes
+                        .get_mut(&proof_path)
+                        .ok_or("we cannot fail a run for a job that hasn't been started")?[variant];
+                    proof_runtime.build_times.push(stats.build_time);
+                    proof_runtime
+                        .solver_times
+                        .push(stats.cbmc.decision_procedure_time);
+                    proof_runtime.energies.push(stats.energy_joules);
+                    proof_runtime.property_hashes.push(stats.cbmc.property_hash);
+                    proof_runtime.goto_times.push(stats.tool_times.goto);
+                    proof_runtime.cbmc_times.push(stats.tool_times.cbmc);
+                    proof_runtime.push_phase_times(&options.phases, &stats.phase_times);
+                    spawn_times.extend(stats.spawn_time);
+                    proof_runtime.max_artifact_bytes =
+                        proof_runtime.max_artifact_bytes.max(stats.artifact_bytes);
+                    if let Some(exit) = exit {
+                        proof_runtime.exits.push((proof_runtime.runs.len(), exit));
+                    }
+                    proof_runtime.runs.push(None);
+                    proof_runtime.retries.push(stats.retries);
+                    if let Some(pages) = stats.swapped_pages.filter(|pages| *pages > 0) {
+                        proof_runtime
+                            .swapped_runs
+                            .push(proof_runtime.runs.len() - 1);
+                        reporter.print_urgent(format!(
+                        "WARNING the machine swapped {} pages during RUN [{}/{}] for {}, its runtime is worthless",
+                        pages,
+                        proof_runtime.runs.len(),
+                        max_iterations,
+                        job_name
+                    ));
+                    }
+                    if output.failures_file.is_some() {
+                        failures.push(triage::Failure {
+                            proof: proof_runtime.name.clone(),
+                            run: proof_runtime.runs.len(),
+                            run_id: run_id.clone(),
+                            exit,
+                            log: stats.log.clone(),
+                            last_lines: stats.log_tail.clone(),
+                        });
+                    }
+                    if let Some(runs_file) = &mut runs_file {
+                        dump_run(
+                            &proof_runtime.name,
+                            proof_runtime.runs.len(),
+                            &run_id,
+                            runtime,
+                            exit,
+                            &stats,
+                            runs_file,
+                        )?;
+                    }
+                    reporter.print_urgent(format!(
+                        "FAILED RUN [{}/{}] for {} after {}s ({})",
+                        proof_runtime.runs.len(),
+                        max_iterations,
+                        job_name,
+                        runtime.as_secs_f32(),
+                        match exit {
+                            Some(Exit::Code(code)) => format!("exit code {}", code),
+                            Some(Exit::Signal(signal)) => format!("killed by signal {}", signal),
+                            Some(Exit::MemoryLimit) => format!(
+                                "killed for taking up more than {}MiB",
+                                options.limits.memory_kib.unwrap_or(0) / 1024
+                            ),
+                            Some(Exit::Timeout) => "killed for exceeding the timeout".to_string(),
+                            None => "couldn't be started".to_string(),
+                        }
+                    ));
+                    if options.fail_fast && !monitor::interrupted() {
+                        failed_fast = Some(job_name.clone());
+                        reporter.print_urgent(format!(
+                            "FAILING FAST, killed {} running runs, writing the results so far",
+                            monitor::abort()
+                        ));
+                    }
+                    if let Some(failure_artifacts) = &options.failure_artifacts {
+                        for (evicted, size) in artifacts::enforce_size_cap(failure_artifacts)? {
+                            reporter.print(format!(
+                                "EVICTED {} ({} bytes)",
+                                evicted.display(),
+                                size
+                            ));
+                        }
+                    }
+                }
+                RunFinished(stats) => {
+                    progress.run_finished(&proof_name(&proof_path), false);
+                    let start_time = started_runs
+                        .remove(&proof_path)
+                        .ok_or("we cannot finish a run we didn't start first")?;
+                    let run_id = run_ids.remove(&proof_path).unwrap_or_default();
+                    let runtime = (timestamp - start_time).saturating_sub(options.make_overhead);
+                    let proof_runtime = &mut proof_runtimes
+                        .get_mut(&proof_path)
+                        .ok_or("we cannot finish a run in a job that hasn't started yet")?[variant];
+                    proof_runtime.runs.push(Some(runtime));
+                    proof_runtime.retries.push(stats.retries);
+                    if let Some(pages) = stats.swapped_pages.filter(|pages| *pages > 0) {
+                        proof_runtime
+                            .swapped_runs
+                            .push(proof_runtime.runs.len() - 1);
+                        reporter.print_urgent(format!(
+                        "WARNING the machine swapped {} pages during RUN [{}/{}] for {}, its runtime is worthless",
+                        pages,
+                        proof_runtime.runs.len(),
+                        max_iterations,
+                        job_name
+                    ));
+                    }
+                    proof_runtime.build_times.push(stats.build_time);
+                    proof_runtime
+                        .solver_times
+                        .push(stats.cbmc.decision_procedure_time);
+                    proof_runtime.energies.push(stats.energy_joules);
+                    proof_runtime.property_hashes.push(stats.cbmc.property_hash);
+                    proof_runtime.goto_times.push(stats.tool_times.goto);
+                    proof_runtime.cbmc_times.push(stats.tool_times.cbmc);
+                    proof_runtime.push_phase_times(&options.phases, &stats.phase_times);
+                    spawn_times.extend(stats.spawn_time);
+                    proof_runtime.max_rss_kib = proof_runtime.max_rss_kib.max(stats.max_rss_kib);
+                    proof_runtime.max_artifact_bytes =
+                        proof_runtime.max_artifact_bytes.max(stats.artifact_bytes);
+                    if let Some(runs_file) = &mut runs_file {
+                        dump_run(
+                            &proof_runtime.name,
+                            proof_runtime.runs.len(),
+                            &run_id,
+                            runtime,
+                            Some(Exit::Code(0)),
+                            &stats,
+                            runs_file,
+                        )?;
+                    }
+                    let mut details = String::new();
+                    if let (Some(user_time), Some(system_time)) =
+                        (stats.user_time, stats.system_time)
+                    {
+                        details += &format!(
+                            ", user {}s, system {}s",
+                            user_time.as_secs_f32(),
+                            system_time.as_secs_f32()
+                        );
+                    }
+                    if let Some(build_time) = stats.build_time {
+                        details += &format!(", build {}s", build_time.as_secs_f32());
+                    }
+                    if let Some(solver_time) = stats.cbmc.decision_procedure_time {
+                        details += &format!(", solver {}s", solver_time.as_secs_f32());
+                    }
+                    if let Some(goto_time) = stats.tool_times.goto {
+                        details += &format!(", goto-cc {}s", goto_time.as_secs_f32());
+                    }
+                    if let Some(cbmc_time) = stats.tool_times.cbmc {
+                        details += &format!(", cbmc {}s", cbmc_time.as_secs_f32());
+                    }
+                    if let Some(cycles) = stats.perf.cycles {
+                        details += &format!(", {} cycles", cycles);
+                    }
+                    if let Some(energy) = stats.energy_joules {
+                        details += &format!(", energy {:.1}J", energy);
+                    }
+                    if let Some(rss) = stats.max_rss_kib {
+                        details += &format!(", peak RSS {:.1}MiB", rss as f64 / 1024.0);
+                    }
+                    if let Some(artifact_bytes) = stats.artifact_bytes {
+                        details += &format!(
+                            ", artifacts {:.1}MiB",
+                            artifact_bytes as f64 / (1 << 20) as f64
+                        );
+                    }
+                    if stats.retries > 0 {
+                        details += &format!(", {} retries", stats.retries);
+                    }
+                    reporter.print(format!(
+                        "FINISHED RUN [{}/{}] for {} after {}s{}",
+                        proof_runtime.runs.len(),
+                        max_iterations,
+                        job_name,
+                        runtime.as_secs_f32(),
+                        details
+                    ));
+                    if let (Some(utilization), Some(run_utilization)) =
+                        (stats.system_utilization, stats.cpu_utilization)
+                    {
+                        let competing_utilization = utilization * cores as f64 - run_utilization;
+                        if utilization > OVERSUBSCRIBED_UTILIZATION
+                            && competing_utilization > COMPETING_CPU_UTILIZATION
+                        {
+                            reporter.print(format!(
+                            "WARNING the CPUs were {:.0}% busy during RUN [{}/{}] for {}, the machine is probably oversubscribed (consider fewer --parallel-jobs)",
+                            100.0 * utilization,
+                            proof_runtime.runs.len(),
+                            max_iterations,
+                            job_name
+                        ));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        };
+        let handled = if options.robust {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(handle))
+                .unwrap_or_else(|panic| Err(format!("panicked: {}", panic_message(&panic)).into()))
+        } else {
+            handle()
+        };
+        match handled {
+            Ok(()) => {}
+            Err(error) if options.robust => {
+                robust_errors += 1;
+                reporter.print_urgent(format!(
+                    "ERROR keeping track of {} failed ({}), dropped the message and carrying on with the proof marked errored",
+                    proof_name(&proof_path),
+                    error
+                ));
+                errored_jobs.insert(proof_path.clone());
+                if let Some(variants) = proof_runtimes.get_mut(&proof_path) {
+                    tag_errored(variants);
+                }
             }
+            Err(error) => return Err(error),
         }
     }
+    // with --interleave a session that stopped between rounds leaves proofs unfinished
+    let mut unfinished: Vec<&Vec<ProofResults>> = proof_runtimes
+        .iter()
+        .filter(|(proof_path, _)| !finished_jobs.contains(*proof_path))
+        .map(|(_, variants)| variants)
+        .collect();
+    unfinished.sort_by(|a, b| a[0].name.cmp(&b[0].name));
+    for variant_results in unfinished.into_iter().flatten() {
+        dump_csv(variant_results, &mut csv_file)?;
+    }
+    let end_environment = environment::sample(&build_directory);
+    // everything after this goes to stdout directly
+    drop(reporter);
+    drop(progress_writer);
+    drop(timeline_writer);
+    if let Some(proof) = &failed_fast {
+        println!(
+            "FAILED FAST after a run of {} failed, the results below only cover the runs that completed",
+            proof
+        );
+    } else if monitor::interrupted() {
+        println!("INTERRUPTED, the results below only cover the runs that completed");
+    } else if monitor::out_of_time() {
+        println!("OUT OF TIME, the results below only cover the runs that completed");
+    }
+    if let Some(failures_file) = &output.failures_file {
+        triage::write(failures_file, &failures)?;
+        println!(
+            "WROTE {} failed runs to {}",
+            failures.len(),
+            failures_file.display()
+        );
+    }
+    let mut session_results: Vec<Vec<ProofResults>> = proof_runtimes.into_values().collect();
+    session_results.extend(resumed);
+    for variants in session_results.iter_mut() {
+        report_options.names.rename(variants);
+    }
+    session_results.sort_by(|a, b| a[0].name.cmp(&b[0].name));
+    if options.targets.len() == 2 && report_options.audience.wants_tables() {
+        report::report_ab(&session_results, &options.targets);
+    }
+    let session_results: Vec<ProofResults> = session_results.into_iter().flatten().collect();
+    let write_reports = report::should_write_reports(&session_results, report_options);
+    report::report_slowest_proofs(&session_results, report_options, write_reports)?;
+    if report_options.audience.wants_tables() {
+        report::report_bimodal_proofs(&session_results);
+    }
+    report::report_bootstrap(&session_results, report_options);
+    report::report_energy(&session_results, report_options);
+    report::report_phases(&session_results, report_options);
+    report::report_spawn_overhead(&session_results, &spawn_times, report_options);
+    report::report_anomalies(&session_results, report_options);
+    report::report_nondeterministic_results(&session_results, report_options);
+    report::write_speedup_report(&session_results, report_options, write_reports)?;
+    report::report_summary(&session_results, report_options);
+    report::report_swapped_runs(&session_results);
+    report::report_groups(&session_results, report_options);
+    let scores = report::report_suite_score(&session_results, report_options);
+    report::write_templated_reports(
+        &session_results,
+        metadata,
+        &scores,
+        report_options,
+        write_reports,
+    )?;
+    report::report_suite_score_trend(
+        report::suite_score(&session_results, report_options.statistic),
+        report_options,
+    );
+    let mut environments = vec![("at the start", start_environment)];
+    environments.extend(middle_environment.map(|middle| ("in the middle", middle)));
+    environments.push(("at the end", end_environment));
+    report::report_environment_drift(&environments, report_options);
+    let mut meta = scores;
+    if monitor::interrupted() && failed_fast.is_none() {
+        meta.push(("interrupted".to_string(), "true".to_string()));
+    }
+    if monitor::out_of_time() {
+        meta.push(("out_of_time".to_string(), "true".to_string()));
+    }
+    if let Some(proof) = &failed_fast {
+        meta.push(("failed_fast".to_string(), proof.clone()));
+    }
+    if robust_errors > 0 {
+        meta.push(("robust_errors".to_string(), robust_errors.to_string()));
+    }
+    if let Some(pauses) = power::metadata() {
+        meta.push(("pauses".to_string(), pauses));
+    }
+    let suspended_time = monitor::suspended_time();
+    if suspended_time > Duration::ZERO {
+        meta.push((
+            "suspended_secs".to_string(),
+            format!("{:.1}", suspended_time.as_secs_f64()),
+        ));
+    }
+    dump_metadata(&RunMetadata { note: None, meta }, &mut csv_file)?;
     Ok(())
 }
 
 #[derive(StructOpt)]
+enum Command {
+    /// print a per-proof comparison of two result files
+    Compare(compare::CompareArguments),
+    /// report per-proof runtime trends across the sessions stored with --history-dir
+    Trend(history::TrendArguments),
+    /// run a small calibration workload and print its runtime as a machine performance score
+    Calibrate,
+    /// run a CBMC tool and record how long it took (used by --tool-times)
+    #[structopt(setting = AppSettings::Hidden)]
+    TimeTool(tools::TimeToolArguments),
+    /// run a command as an AWS Batch job and wait for it (used by --aws-batch)
+    #[structopt(setting = AppSettings::Hidden)]
+    BatchJob(batch::BatchJobArguments),
+}
+
+// The benchmark arguments are only required when running benchmarks, i.e. when no subcommand is
+// given, hence the Options which are checked in run_benchmarks
+#[derive(StructOpt)]
+#[structopt(setting = AppSettings::SubcommandsNegateReqs)]
 struct Arguments {
+    #[structopt(long, parse(from_os_str), required_unless = "proof-dir")]
+    proofs_path: Option<PathBuf>,
+    /// benchmark exactly this proof directory instead of discovering proofs in --proofs-path
+    /// (can be given multiple times)
+    #[structopt(
+        long,
+        parse(from_os_str),
+        number_of_values = 1,
+        conflicts_with = "proofs-path"
+    )]
+    proof_dir: Vec<PathBuf>,
+    /// also discover proofs nested deeper below --proofs-path, in any directory with a Makefile
+    /// under a "proofs" directory
+    #[structopt(long, requires = "proofs-path")]
+    recursive: bool,
+    /// how many levels (at least 1) below --proofs-path --recursive looks for proofs [default: no
+    /// limit]
+    #[structopt(long, requires = "recursive")]
+    max_depth: Option<NonZeroUsize>,
+    /// don't run proofs marked with EXPENSIVE = true in their Makefile
+    #[structopt(long, conflicts_with = "only-expensive")]
+    skip_expensive: bool,
+    /// only run proofs marked with EXPENSIVE = true in their Makefile
+    #[structopt(long)]
+    only_expensive: bool,
+    /// a capability of this machine that proofs can require with BENCHMARK_REQUIRES in their
+    /// Makefile, besides its CPU flags (can be given multiple times)
+    #[structopt(long, number_of_values = 1)]
+    machine_tag: Vec<String>,
+    /// normal, throughput to run as many proofs as possible at once (one job and make -j per
+    /// core, one iteration by default) and only measure rough runtimes, or accurate to measure
+    /// as precisely as possible (one job at a time pinned to CPUs, 10 iterations and a warmup run
+    /// by default, cooldowns between runs, suspect runs and outliers beyond 3 sigma repeated, and
+    /// only with the performance CPU frequency governor unless --allow-governor is given)
+    #[structopt(long, default_value = "normal")]
+    mode: Mode,
+    /// run in accurate mode even though the CPU frequency governor isn't performance
+    #[structopt(long)]
+    allow_governor: bool,
+    /// number of runs per proof, or the minimum number of runs with --target-ci-width
+    #[structopt(long)]
+    iterations: Option<u32>,
+    /// pause this long between consecutive runs of a proof, like 30s, to let the temperature of
+    /// the CPU and the state of the disk cache settle [default: 5s in accurate mode, none
+    /// otherwise]
+    #[structopt(long, parse(try_from_str = monitor::parse_duration))]
+    cooldown: Option<Duration>,
+    /// also pause for --cooldown before every proof a job starts after its first
+    #[structopt(long)]
+    cooldown_between_proofs: bool,
+    /// the make target that cleans the proof directory at the start of every run (and warmup
+    /// run), so every run builds from the same state instead of rebuilding incrementally
+    #[structopt(long, default_value = "veryclean")]
+    clean_target: String,
+    /// cold to drop the page cache (as root) or warm to read all files of the git checkout of the
+    /// proof (or its directory outside of one) right before every measured run, so all runs
+    /// measure the same cache state and not just the ones after the first
+    #[structopt(long, conflicts_with_all = &["workers", "aws-batch"])]
+    page_cache: Option<PageCache>,
+    /// keep running each proof until the 95% confidence interval of its mean runtime is within
+    /// ± this fraction of the mean (e.g. 0.05), instead of a fixed number of iterations
+    #[structopt(long)]
+    target_ci_width: Option<f64>,
+    /// upper limit on the number of runs per proof with --target-ci-width
+    #[structopt(long)]
+    max_iterations: Option<u32>,
+    #[structopt(long)]
+    parallel_jobs: Option<u32>,
+    /// file with a PATTERN: PATTERN... line for every dependency, the proofs matching the first
+    /// wildcard pattern only start once all proofs matching the others have finished (e.g.
+    /// because they rebuild a shared library)
     #[structopt(long, parse(from_os_str))]
-    proofs_path: PathBuf,
+    dependencies_file: Option<PathBuf>,
+    /// run iteration k of every proof before iteration k+1 of any proof instead of all iterations
+    /// of a proof back to back, to spread drift of the machine state evenly across the proofs
     #[structopt(long)]
-    iterations: u32,
+    interleave: bool,
+    /// start the proofs in a random order instead of alphabetically (or longest first), so
+    /// time-of-day and thermal effects don't always hit the same proofs
     #[structopt(long)]
-    parallel_jobs: u32,
+    shuffle: bool,
+    /// the seed of --shuffle, to repeat the order of an earlier session [default: random]
+    #[structopt(long, requires = "shuffle")]
+    seed: Option<u64>,
+    /// stop starting runs once the session has taken this long, like 90m or 6h, and write the
+    /// results of the runs that completed
+    #[structopt(long, parse(try_from_str = monitor::parse_duration))]
+    max_total_time: Option<Duration>,
+    /// kill the runs in progress when --max-total-time is up instead of letting them finish
+    #[structopt(long, requires = "max-total-time")]
+    kill_at_time_limit: bool,
+    /// file with a proof name (or wildcard pattern) per line, the proofs it lists start first and
+    /// in that order, the others after them
     #[structopt(long, parse(from_os_str))]
-    csv_file: PathBuf,
+    priority_file: Option<PathBuf>,
+    /// run make (or the other builder) with -j N in every job
+    #[structopt(long)]
+    make_jobs: Option<u32>,
+    /// the CPUs all parallel jobs together may keep busy: without --make-jobs (and with --mode
+    /// throughput) every job gets an equal share as make -j, and a warning is printed if
+    /// --make-jobs times --parallel-jobs exceeds it [default: the number of cores]
+    #[structopt(long)]
+    cpu_budget: Option<u32>,
+    /// pin every parallel job, and everything it runs, to a disjoint set of the CPUs available to
+    /// us, so runs don't migrate between CPUs and get in each other's way
+    #[structopt(long)]
+    pin_cpus: bool,
+    /// don't pin the jobs to CPUs in accurate mode
+    #[structopt(long, conflicts_with = "pin-cpus")]
+    no_pin_cpus: bool,
+    /// run the proofs on these hosts over ssh instead of here, as HOST[:JOBS] (e.g.
+    /// build1,user@build2:4) with one job per host by default, finding them at the same path
+    /// there. Only the runtimes, exit codes and output of the runs are measured
+    #[structopt(
+        long,
+        use_delimiter = true,
+        conflicts_with_all = &["parallel-jobs", "pin-cpus", "adaptive-jobs", "memory-aware", "memory-limit", "no-network", "read-only-sources", "perf-stat", "tool-times"]
+    )]
+    workers: Vec<Worker>,
+    /// copy the git checkouts of the proofs (or the proof directories outside of one) to every
+    /// worker with rsync before starting, instead of relying on a shared checkout
+    #[structopt(long, requires = "workers")]
+    sync_workers: bool,
+    /// run every make invocation as a job in this AWS Batch queue with this job definition, as
+    /// QUEUE:JOB_DEFINITION, with the aws CLI. The job definition has to mount the proofs at the
+    /// same path as here, runtimes are how long the jobs ran without their time in the queue
+    #[structopt(
+        long,
+        conflicts_with_all = &["workers", "pin-cpus", "adaptive-jobs", "memory-aware", "memory-limit", "no-network", "read-only-sources", "perf-stat", "tool-times"]
+    )]
+    aws_batch: Option<BatchTarget>,
+    /// run every make invocation in a fresh container of this image (with --container-runtime),
+    /// with the git checkout of the proof mounted at the same path and as our own user, for the
+    /// same toolchain everywhere
+    #[structopt(
+        long,
+        conflicts_with_all = &["aws-batch", "pin-cpus", "memory-limit", "read-only-sources", "perf-stat", "tool-times"]
+    )]
+    container: Option<String>,
+    /// the program that runs the --container, docker or podman [default: docker]
+    #[structopt(long, requires = "container")]
+    container_runtime: Option<String>,
+    /// how many CPUs a --container may keep busy (e.g. 2.5)
+    #[structopt(long, requires = "container")]
+    container_cpus: Option<f64>,
+    /// the memory a --container may take up (e.g. 8G), the run fails once it is exceeded
+    #[structopt(long, parse(try_from_str = artifacts::parse_size), requires = "container")]
+    container_memory: Option<u64>,
+    /// treat --parallel-jobs (the number of cores by default) as the most proofs that run at once,
+    /// and hold back further ones while the load reaches --max-load or less than
+    /// --min-free-memory is available
+    #[structopt(long)]
+    adaptive_jobs: bool,
+    /// the load average (or the number of runnable tasks right now) at which --adaptive-jobs holds
+    /// back proofs [default: the number of cores]
+    #[structopt(long, requires = "adaptive-jobs")]
+    max_load: Option<f64>,
+    /// the available memory, like 4G, below which --adaptive-jobs holds back proofs
+    #[structopt(long, default_value = "2G", parse(try_from_str = artifacts::parse_size))]
+    min_free_memory: u64,
+    /// only start a proof when its expected peak memory (memory>= in its BENCHMARK_REQUIRES, or
+    /// its peak RSS in --baseline or --history-dir) fits next to that of the running proofs
+    #[structopt(long)]
+    memory_aware: bool,
+    /// the memory the running proofs may take up together with --memory-aware, like 48G
+    /// [default: the memory available at the start]
+    #[structopt(long, parse(try_from_str = artifacts::parse_size), requires = "memory-aware")]
+    memory_budget: Option<u64>,
+    /// don't start any runs while the machine runs on battery, until it is plugged in again
+    #[structopt(long)]
+    pause_on_battery: bool,
+    /// don't start any runs while the CPU is hotter than this many degrees Celsius, until it
+    /// cooled down 5 degrees below that
+    #[structopt(long)]
+    max_cpu_temperature: Option<f64>,
+    /// run the jobs at this nice value, from -20 (highest priority, needs root) to 19 (lowest)
+    #[structopt(long, allow_hyphen_values = true, parse(try_from_str = priority::parse_nice))]
+    nice: Option<i32>,
+    /// run the jobs in this I/O scheduling class, as CLASS[:LEVEL] like idle, best-effort:7 or
+    /// realtime:0 (needs root)
+    #[structopt(long)]
+    ionice: Option<IoPriority>,
+    /// where to write the results, results-<timestamp>.csv in --output-dir by default
+    #[structopt(long, parse(from_os_str))]
+    csv_file: Option<PathBuf>,
+    /// the directory for the csv file when --csv-file isn't given [default: ~/benchmark-results]
+    #[structopt(long, parse(from_os_str))]
+    output_dir: Option<PathBuf>,
+    /// allow output files and directories inside the git checkouts of the proofs (or the proof
+    /// directories outside of one), where cleaning up the proofs can delete them
+    #[structopt(long)]
+    allow_output_in_proofs: bool,
+    /// also write one line per measured run with details about it (e.g. the CPUs it ran on) to
+    /// this csv file
+    #[structopt(long, parse(from_os_str))]
+    runs_file: Option<PathBuf>,
+    /// keep this file up to date (rewritten every second) with a JSON snapshot of the progress of
+    /// the session: completed, failed and in-flight runs, an ETA and the state of every proof
+    #[structopt(long, parse(from_os_str))]
+    progress_file: Option<PathBuf>,
+    /// append a csv line to this file every --timeline-interval: the time, the jobs working on a
+    /// proof, the runs in progress and the completed and failed runs so far
+    #[structopt(long, parse(from_os_str))]
+    timeline_file: Option<PathBuf>,
+    /// how often a line gets appended to --timeline-file, like 10s or 1m
+    #[structopt(long, default_value = "10s", parse(try_from_str = monitor::parse_duration))]
+    timeline_interval: Duration,
+    /// write every failed run (proof, run, kind of failure, exit status, log file and the last
+    /// lines of its output) to this JSON file for triage
+    #[structopt(long, parse(from_os_str))]
+    failures_file: Option<PathBuf>,
+    /// record every proof in this file as soon as all its runs are done, so a session that gets
+    /// killed can be continued with --resume
+    #[structopt(long, parse(from_os_str))]
+    state_file: Option<PathBuf>,
+    /// skip the proofs already completed in --state-file and take their results from there
+    /// (proofs that were in progress are run again from their first iteration)
+    #[structopt(long, requires = "state-file")]
+    resume: bool,
+    /// at most MAX (default 1) proofs matching any of the wildcard PATTERNs run at the same time,
+    /// given as NAME[:MAX]=PATTERN[,PATTERN...] (can be given multiple times)
+    #[structopt(long, parse(try_from_str = scheduler::parse_group), number_of_values = 1)]
+    group: Vec<ProofGroup>,
+    /// run the proofs matching this wildcard pattern with no other proofs at the same time, like
+    /// those whose Makefile sets BENCHMARK_EXCLUSIVE = true, e.g. because they need all the memory
+    /// of the machine (can be given multiple times)
+    #[structopt(long, number_of_values = 1)]
+    exclusive: Vec<String>,
+    /// A/B mode: the make target for the A variant (instead of result), runs interleaved with
+    /// --target-b on every proof and reports paired comparisons at the end
+    #[structopt(long, requires = "target-b")]
+    target_a: Option<String>,
+    /// A/B mode: the make target for the B variant
+    #[structopt(long, requires = "target-a")]
+    target_b: Option<String>,
+    /// number of runs per proof before the measured iterations, which are not recorded
+    #[structopt(long)]
+    warmup: Option<u32>,
+    /// repeat runs during which the system clock jumped (e.g. suspend/resume or NTP steps)
+    /// instead of only marking them as suspect with a ? in the results
+    #[structopt(long)]
+    rerun_suspect: bool,
+    /// run all make invocations in a new network namespace without network access, so builds
+    /// that download things fail instead of adding network latency to the runtimes
+    #[structopt(long)]
+    no_network: bool,
+    /// run the measured command of every run under perf stat to count its instructions, cycles
+    /// and cache misses, which are recorded in the runs file
+    #[structopt(long)]
+    perf_stat: bool,
+    /// after the measured target of every successful run, also run this make target (e.g. report)
+    /// and time it separately, without it counting towards the runtime (can be given multiple
+    /// times, the targets run in the order given)
+    #[structopt(long, number_of_values = 1)]
+    phase: Vec<String>,
+    /// time every invocation of goto-cc and goto-instrument, and of cbmc in every run (by
+    /// overriding the GOTO_CC, GOTO_INSTRUMENT and CBMC make variables), to tell the time spent
+    /// building goto binaries from the time spent in the analysis
+    #[structopt(long)]
+    tool_times: bool,
+    /// build proofs whose directory has the file MARKER with COMMAND, where {target} stands for
+    /// the target to build and is appended if missing (e.g. build.ninja=ninja-build; can be given
+    /// multiple times, checked in the order given before the defaults Makefile=make,
+    /// build.ninja=ninja and CMakePresets.json="cmake --build --preset default --target
+    /// {target}")
+    #[structopt(long, number_of_values = 1, parse(try_from_str = builder::parse_builder_arg))]
+    builder: Vec<Builder>,
+    /// run every iteration of a proof with a fresh writable overlay mounted over its source tree
+    /// (its git checkout), so runs can't modify the actual sources and don't see the build
+    /// outputs of earlier runs. The overlays are created in $TMPDIR
+    #[structopt(long)]
+    read_only_sources: bool,
+    /// replace runs whose runtime deviates by more than this many standard deviations (e.g. 3)
+    /// from the other runs of the proof by an extra iteration (at most 2 per proof), the
+    /// outliers are marked with a ! in the results and left out of the statistics
+    #[structopt(long)]
+    rerun_outliers: Option<f64>,
+    /// repeat a failed run up to this many times before recording it as failed, for transient
+    /// failures like flaky downloads in make (runs killed for exceeding --timeout or
+    /// --memory-limit are not repeated)
+    #[structopt(long, default_value = "0")]
+    retries: u32,
+    /// instead of panicking on an internal error, mark the proof it happened in as errored (with
+    /// the runs it made so far), report it and carry on with the other proofs, for long
+    /// unattended sessions
+    #[structopt(long)]
+    robust: bool,
+    /// kill the runs in progress and stop the session (writing the results so far, like an
+    /// interrupt) as soon as a run has failed, since one failure already makes it worthless
+    #[structopt(long)]
+    fail_fast: bool,
+    /// copy the proof directory of every failed run into this directory
+    #[structopt(long, parse(from_os_str))]
+    failure_artifacts_dir: Option<PathBuf>,
+    /// total size the failure artifacts may take up (e.g. 500M or 2G), the oldest ones are
+    /// deleted first when it is exceeded
+    #[structopt(long, parse(try_from_str = artifacts::parse_size), requires = "failure-artifacts-dir")]
+    failure_artifacts_max_size: Option<u64>,
+    /// compress failure artifacts (into tarballs) as they are preserved, --log-dir logs as their
+    /// run finishes, and the --runs-file and the results stored in --history-dir at the end of
+    /// the session, with gzip or zstd
+    #[structopt(long)]
+    compress: Option<Compression>,
+    /// kill a run (including warmup runs) as soon as all its processes together take up more than
+    /// this many MiB of resident memory, checked every 100ms, and record it as failed with
+    /// memory-limit
+    #[structopt(long)]
+    memory_limit: Option<u64>,
+    /// kill a run (including warmup runs) that takes longer than this, in seconds or with a unit
+    /// like 90s, 30m or 2h, checked every 100ms, and record it as failed with timeout; with
+    /// auto:<factor>x (e.g. auto:3x) the runs of a proof after its first successful one are
+    /// killed when they take longer than that many times its runtime
+    #[structopt(long)]
+    timeout: Option<Timeout>,
+    /// use this timeout (like --timeout) instead for the proofs matching the wildcard PATTERN,
+    /// given as PATTERN=TIMEOUT, e.g. s2n_*=2h (can be given multiple times, the first matching
+    /// one counts)
+    #[structopt(long, number_of_values = 1)]
+    proof_timeout: Vec<ProofTimeout>,
+    /// file with a PATTERN=TIMEOUT line for every proof timeout, checked after --proof-timeout
+    #[structopt(long, parse(from_os_str))]
+    proof_timeouts_file: Option<PathBuf>,
+    /// capture the output of every measured run (including its veryclean and goto steps) into
+    /// DIR/<proof>/run-<n>.log, compressed with --compress
+    #[structopt(long, parse(from_os_str))]
+    log_dir: Option<PathBuf>,
+    /// which logs to keep in --log-dir: all, failed to delete the logs of successful runs once
+    /// they are done, or none (the logs are still captured for CBMC's statistics and
+    /// --failures-file)
+    #[structopt(long, default_value = "all")]
+    keep_logs: KeepLogs,
+    /// run the calibration workload before benchmarking and record its runtime as calibration
+    /// metadata (for compare --normalize-by calibration)
+    #[structopt(long)]
+    calibrate: bool,
+    /// measure the time make takes on an empty target and subtract it from every recorded
+    /// runtime (the overhead is also measured and recorded as metadata with --calibrate)
+    #[structopt(long)]
+    subtract_make_overhead: bool,
+    /// results of a previous run to relate this one to in the reports at the end
+    #[structopt(long, parse(from_os_str))]
+    baseline: Option<PathBuf>,
+    /// print the effective configuration of every proof and exit without running anything
+    #[structopt(long)]
+    print_plan: bool,
+    /// how to summarize the runtimes of each proof in the reports: mean, median, min or best-of-N
+    #[structopt(long, default_value = "mean")]
+    statistic: Statistic,
+    /// print bootstrap estimates of the mean and median runtime of every proof with their 95%
+    /// confidence intervals, computed from this many resamples (e.g. 10000)
+    #[structopt(long)]
+    bootstrap_samples: Option<usize>,
+    /// which sections the reports at the end consist of: developer (all per-proof tables), ci
+    /// (only failures, anomalies and warnings) or manager (summaries and the suite score trend
+    /// across --history-dir)
+    #[structopt(long, default_value = "developer")]
+    audience: report::Audience,
+    /// file with lines of raw-name,display-name[,group] giving proofs readable names in all
+    /// reports, and adding a table of totals per group; the csv and runs files keep the raw names
+    #[structopt(long, parse(from_os_str))]
+    name_map: Option<PathBuf>,
+    /// only show the N slowest and N most variable proofs in the summary at the end
+    #[structopt(long)]
+    top: Option<usize>,
+    /// also write the report of proofs sorted by runtime printed at the end to this file
+    #[structopt(long, parse(from_os_str))]
+    slowest_report: Option<PathBuf>,
+    /// also write a report laid out by the template file TEMPLATE (e.g. Markdown or HTML) to
+    /// OUTPUT, given as TEMPLATE=OUTPUT (can be given multiple times, see the README for the
+    /// placeholders)
+    #[structopt(long, parse(try_from_str = template::parse_template_arg), number_of_values = 1)]
+    report_template: Vec<(PathBuf, PathBuf)>,
+    /// store the results of this session in this directory, for the trend subcommand
+    #[structopt(long, parse(from_os_str))]
+    history_dir: Option<PathBuf>,
+    /// write a per-proof speedup report against --baseline to this file
+    #[structopt(long, parse(from_os_str), requires = "baseline")]
+    speedup_report: Option<PathBuf>,
+    /// don't write --slowest-report, --speedup-report and --report-template if no proof changed
+    /// significantly against --baseline (runtime, failures, added or removed proofs)
+    #[structopt(long, requires = "baseline")]
+    report_on_change_only: bool,
+    /// free form note about why this run was made, recorded in the results
+    #[structopt(long)]
+    note: Option<String>,
+    /// additional key=value metadata recorded in the results (can be given multiple times)
+    #[structopt(long, parse(try_from_str = results::parse_key_value), number_of_values = 1)]
+    meta: Vec<(String, String)>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+fn run_benchmarks(args: &Arguments) -> GenericResult<()> {
+    monitor::handle_signals();
+    let builders = builder::builders(&args.builder);
+    let mut proof_dirs = match &args.proofs_path {
+        Some(proofs_path) => discover_proof_dirs(
+            proofs_path,
+            &builders,
+            args.recursive,
+            args.max_depth.map(NonZeroUsize::get),
+        )?,
+        None => {
+            for proof_dir in args.proof_dir.iter() {
+                if builder::detect(&builders, proof_dir).is_none() {
+                    return Err(format!(
+                        "{} is not a proof directory (none of {})",
+                        proof_dir.display(),
+                        builders
+                            .iter()
+                            .map(|builder| builder.marker.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    )
+                    .into());
+                }
+            }
+            args.proof_dir.clone()
+        }
+    };
+    if args.skip_expensive {
+        proof_dirs.retain(|proof_dir| !is_expensive(proof_dir));
+    } else if args.only_expensive {
+        proof_dirs.retain(|proof_dir| is_expensive(proof_dir));
+    }
+    let capabilities = requirements::Capabilities::detect(&args.machine_tag);
+    let mut unsatisfiable = Vec::new();
+    for proof_dir in proof_dirs.iter() {
+        let requirements = proof_requirements(proof_dir)?;
+        let unmet = capabilities.unmet(&requirements);
+        if !unmet.is_empty() {
+            let unmet: Vec<String> = unmet.iter().map(ToString::to_string).collect();
+            println!(
+                "UNSATISFIABLE {} needs {}, which this machine ({}) doesn't offer, not running it",
+                proof_name(proof_dir),
+                unmet.join(" "),
+                capabilities
+            );
+            unsatisfiable.push(proof_dir.clone());
+        }
+    }
+    proof_dirs.retain(|proof_dir| !unsatisfiable.contains(proof_dir));
+    let throughput = args.mode == Mode::Throughput;
+    let accurate = args.mode == Mode::Accurate;
+    if throughput && (args.target_ci_width.is_some() || args.warmup.is_some()) {
+        return Err("--target-ci-width and --warmup can't be used with --mode throughput".into());
+    }
+    // they need all runs of a proof in one job
+    if args.interleave
+        && (args.target_ci_width.is_some()
+            || args.rerun_outliers.is_some()
+            || matches!(args.timeout, Some(Timeout::Auto(_))))
+    {
+        return Err(
+            "--target-ci-width, --rerun-outliers and --timeout auto can't be used with --interleave"
+                .into(),
+        );
+    }
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get() as u32);
+    let iterations = match args.iterations {
+        Some(iterations) => iterations,
+        None if throughput => 1,
+        None if accurate => ACCURATE_ITERATIONS,
+        None => return Err("--iterations is required".into()),
+    };
+    let iterations = match args.target_ci_width {
+        Some(target_relative_width) => Iterations::Adaptive {
+            // we need at least two runs to have any idea about the variance
+            min: iterations.max(2),
+            max: args
+                .max_iterations
+                .ok_or("--max-iterations is required with --target-ci-width")?,
+            target_relative_width,
+        },
+        None => Iterations::Fixed(iterations),
+    };
+    let parallel_jobs = match args.parallel_jobs {
+        Some(parallel_jobs) => parallel_jobs,
+        None if !args.workers.is_empty() => args.workers.iter().map(|worker| worker.slots).sum(),
+        None if throughput || args.adaptive_jobs => cores,
+        None if accurate => 1,
+        None => return Err("--parallel-jobs is required".into()),
+    };
+    // accurate mode pins the jobs unless they run somewhere else, where they can't be pinned
+    let pin_cpus = args.pin_cpus
+        || (accurate
+            && !args.no_pin_cpus
+            && args.workers.is_empty()
+            && args.aws_batch.is_none()
+            && args.container.is_none());
+    // the clock speed of other governors varies with the load, and so would the runtimes
+    if let Some(governor) = results::cpu_governor()
+        .filter(|governor| accurate && !args.allow_governor && governor != "performance")
+    {
+        return Err(format!(
+            "the CPU frequency governor is {} instead of performance, set it with cpupower frequency-set -g performance or give --allow-governor",
+            governor
+        )
+        .into());
+    }
+    // the CPUs all jobs together may keep busy, shared out between them as make -j
+    let cpu_budget = args.cpu_budget.unwrap_or(cores);
+    let make_jobs = match args.make_jobs {
+        Some(make_jobs) => Some(make_jobs),
+        // the cores here say nothing about the workers
+        None if args.workers.is_empty() && (throughput || args.cpu_budget.is_some()) => {
+            Some((cpu_budget / parallel_jobs.max(1)).max(1))
+        }
+        None => None,
+    };
+    if let Some(make_jobs) = make_jobs
+        .filter(|make_jobs| make_jobs * parallel_jobs > cpu_budget && args.workers.is_empty())
+    {
+        println!(
+            "WARNING {} parallel jobs running make -j{} can keep {} CPUs busy, more than the {} of --cpu-budget, which makes runtimes worse and noisier",
+            parallel_jobs,
+            make_jobs,
+            make_jobs * parallel_jobs,
+            cpu_budget
+        );
+    }
+    let mut run_options = RunOptions {
+        targets: match (&args.target_a, &args.target_b) {
+            (Some(target_a), Some(target_b)) => vec![target_a.clone(), target_b.clone()],
+            _ => vec!["result".to_string()],
+        },
+        warmup: match args.warmup {
+            Some(warmup) => warmup,
+            None if accurate => ACCURATE_WARMUP,
+            None => 0,
+        },
+        rerun_suspect: args.rerun_suspect || accurate,
+        make_jobs,
+        clean_target: args.clean_target.clone(),
+        coarse_timing: throughput,
+        builders,
+        cooldown: match args.cooldown {
+            Some(cooldown) => cooldown,
+            None if accurate => ACCURATE_COOLDOWN,
+            None => Duration::ZERO,
+        },
+        cooldown_between_proofs: args.cooldown_between_proofs,
+        page_cache: args.page_cache,
+        robust: args.robust,
+        fail_fast: args.fail_fast,
+        pause: PauseConditions {
+            on_battery: args.pause_on_battery,
+            max_temperature: args.max_cpu_temperature,
+        },
+        make_overhead: Duration::ZERO,
+        no_network: args.no_network,
+        retries: args.retries,
+        perf_stat: args.perf_stat,
+        phases: args.phase.clone(),
+        tool_times: args.tool_times,
+        outlier_threshold: match args.rerun_outliers {
+            Some(threshold) => Some(threshold),
+            None if accurate && !args.interleave => Some(ACCURATE_OUTLIER_THRESHOLD),
+            None => None,
+        },
+        overlay_dir: if args.read_only_sources {
+            Some(std::env::temp_dir().join(format!("benchmark-overlays-{}", std::process::id())))
+        } else {
+            None
+        },
+        failure_artifacts: args
+            .failure_artifacts_dir
+            .as_ref()
+            .map(|directory| FailureArtifacts {
+                directory: directory.clone(),
+                max_size: args.failure_artifacts_max_size,
+                compression: args.compress,
+            }),
+        log_dir: args.log_dir.clone(),
+        log_compression: args.compress,
+        keep_logs: args.keep_logs,
+        limits: monitor::Limits {
+            memory_kib: args.memory_limit.map(|memory_limit| memory_limit * 1024),
+            time: match args.timeout {
+                Some(Timeout::Fixed(timeout)) => Some(timeout),
+                _ => None,
+            },
+        },
+        auto_timeout: match args.timeout {
+            Some(Timeout::Auto(factor)) => Some(factor),
+            _ => None,
+        },
+        proof_timeouts: match &args.proof_timeouts_file {
+            Some(proof_timeouts_file) => args
+                .proof_timeout
+                .iter()
+                .cloned()
+                .chain(monitor::load_proof_timeouts(proof_timeouts_file)?)
+                .collect(),
+            None => args.proof_timeout.clone(),
+        },
+        worker: None,
+        aws_batch: args.aws_batch.clone(),
+        container: args.container.as_ref().map(|image| Container {
+            image: image.clone(),
+            runtime: args
+                .container_runtime
+                .clone()
+                .unwrap_or_else(|| "docker".to_string()),
+            cpus: args.container_cpus,
+            memory_bytes: args.container_memory,
+        }),
+    };
+    // like --timeout auto, they only see the runtimes of the round they are in
+    if args.interleave
+        && run_options
+            .proof_timeouts
+            .iter()
+            .any(|proof_timeout| matches!(proof_timeout.timeout, Timeout::Auto(_)))
+    {
+        return Err("auto proof timeouts can't be used with --interleave".into());
+    }
+    let cpu_sets = if pin_cpus {
+        let cpus = affinity::available_cpus()?;
+        Some(
+            affinity::partition(&cpus, parallel_jobs as usize).ok_or_else(|| {
+                format!(
+                    "pinning the jobs (with --pin-cpus or in accurate mode) needs a CPU per job, but there are only {} CPUs for {} jobs",
+                    cpus.len(),
+                    parallel_jobs
+                )
+            })?,
+        )
+    } else {
+        None
+    };
+    let mut schedule = ScheduleOptions {
+        parallel_jobs,
+        groups: args.group.clone(),
+        cpu_sets,
+        workers: args.workers.clone(),
+        admission: Some(Admission {
+            max_load: args.max_load.unwrap_or(cores as f64),
+            min_free_memory_bytes: args.min_free_memory,
+        })
+        .filter(|_| args.adaptive_jobs),
+        memory_budget: None,
+        expected_memory: HashMap::new(),
+        max_total_time: args.max_total_time,
+        kill_at_time_limit: args.kill_at_time_limit,
+        interleave: args.interleave,
+        dependencies: HashMap::new(),
+        exclusive: args.exclusive.clone(),
+    };
+    let names = match &args.name_map {
+        Some(name_map) => NameMap::load(name_map)?,
+        None => NameMap::default(),
+    };
+    let baseline = match &args.baseline {
+        Some(baseline) => {
+            let mut baseline = results::read_results(baseline)?;
+            names.rename(&mut baseline.proofs);
+            Some(baseline)
+        }
+        None => None,
+    };
+    let history = match &args.history_dir {
+        Some(history_dir) if history_dir.exists() => {
+            let mut sessions = history::load(history_dir)?;
+            for (_, session) in sessions.iter_mut() {
+                names.rename(&mut session.proofs);
+            }
+            sessions
+        }
+        _ => Vec::new(),
+    };
+    // the baseline first, then the sessions in the history from the latest one back
+    let prior_results: Vec<&ResultFile> = baseline
+        .iter()
+        .chain(history.iter().rev().map(|(_, session)| session))
+        .collect();
+    // the order the proofs start in, so --print-plan can show it too
+    let mut shuffle_seed = None;
+    let proof_dirs = if args.shuffle {
+        let seed = match args.seed {
+            Some(seed) => seed,
+            None => random_seed()?,
+        };
+        println!("SHUFFLED proofs with --seed {}", seed);
+        shuffle_seed = Some(seed);
+        shuffled(proof_dirs, seed)
+    } else if prior_results.is_empty() {
+        proof_dirs
+    } else {
+        longest_first(proof_dirs, &prior_results, &run_options, &names)
+    };
+    let proof_dirs = match &args.priority_file {
+        Some(priority_file) => prioritized(proof_dirs, &pattern::load(priority_file)?),
+        None => proof_dirs,
+    };
+    if let Some(dependencies_file) = &args.dependencies_file {
+        schedule.dependencies = proof_dependencies(
+            &proof_dirs,
+            &scheduler::load_dependencies(dependencies_file)?,
+        )?;
+    }
+    if args.memory_aware {
+        let budget = match args.memory_budget {
+            Some(budget) => budget,
+            None => {
+                std::fs::read_to_string("/proc/meminfo")
+                    .ok()
+                    .and_then(|meminfo| environment::meminfo_field(&meminfo, "MemAvailable"))
+                    .ok_or("--memory-aware needs --memory-budget without /proc/meminfo")?
+                    * 1024
+            }
+        };
+        schedule.memory_budget = Some(budget);
+        for proof_dir in proof_dirs.iter() {
+            if let Some(memory) =
+                expected_memory_bytes(proof_dir, &prior_results, &run_options, &names)
+            {
+                schedule.expected_memory.insert(proof_dir.clone(), memory);
+            }
+        }
+        println!(
+            "RESERVING memory for {} of {} proofs (declared or from prior results), {}MiB for all running proofs together",
+            schedule.expected_memory.len(),
+            proof_dirs.len(),
+            budget >> 20
+        );
+    }
+    if args.print_plan {
+        print_plan(&proof_dirs, iterations, &run_options, &schedule);
+        return Ok(());
+    }
+    if let Some(page_cache) = args.page_cache {
+        cache::check(page_cache).map_err(|err| {
+            format!(
+                "can't drop the page cache for --page-cache {} (it takes root): {}",
+                page_cache, err
+            )
+        })?;
+    }
+    // before any job threads start, so they all inherit it
+    if let Some(nice) = args.nice {
+        priority::set_nice(nice).map_err(|err| format!("can't set --nice {}: {}", nice, err))?;
+    }
+    if let Some(ionice) = args.ionice {
+        priority::set_io_priority(ionice)
+            .map_err(|err| format!("can't set --ionice {}: {}", ionice, err))?;
+    }
+    let mut metadata = RunMetadata {
+        note: args.note.clone(),
+        meta: args.meta.clone(),
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0);
+    metadata
+        .meta
+        .push(("timestamp".to_string(), timestamp.to_string()));
+    let csv_file = match (&args.csv_file, &args.output_dir) {
+        (Some(csv_file), _) => csv_file.clone(),
+        (None, Some(output_dir)) => output_dir.join(format!("results-{}.csv", timestamp)),
+        (None, None) => outputs::default_dir()
+            .ok_or("--csv-file is required without a home directory")?
+            .join(format!("results-{}.csv", timestamp)),
+    };
+    if !args.allow_output_in_proofs {
+        let mut output_paths: Vec<(&str, &Path)> = vec![("--csv-file", &csv_file)];
+        for (option, path) in [
+            ("--runs-file", &args.runs_file),
+            ("--progress-file", &args.progress_file),
+            ("--timeline-file", &args.timeline_file),
+            ("--failures-file", &args.failures_file),
+            ("--state-file", &args.state_file),
+            ("--log-dir", &args.log_dir),
+            ("--failure-artifacts-dir", &args.failure_artifacts_dir),
+            ("--slowest-report", &args.slowest_report),
+            ("--speedup-report", &args.speedup_report),
+            ("--history-dir", &args.history_dir),
+        ] {
+            if let Some(path) = path {
+                output_paths.push((option, path));
+            }
+        }
+        for (_, output) in args.report_template.iter() {
+            output_paths.push(("--report-template", output));
+        }
+        let proof_trees: Vec<PathBuf> = args
+            .proofs_path
+            .iter()
+            .chain(proof_dirs.iter())
+            .cloned()
+            .collect();
+        outputs::check(&output_paths, &outputs::protected_trees(&proof_trees)?)?;
+    }
+    if args.sync_workers {
+        for tree in outputs::protected_trees(&proof_dirs)?.iter() {
+            for worker in args.workers.iter() {
+                println!("SYNCING {} to {}", tree.display(), worker.host);
+                remote::sync(&worker.host, tree)?;
+            }
+        }
+    }
+    if args.csv_file.is_none() {
+        println!("WRITING results to {}", csv_file.display());
+    }
+    if let Some(csv_dir) = csv_file.parent() {
+        std::fs::create_dir_all(csv_dir)?;
+    }
+    let git_revision = proof_dirs
+        .first()
+        .and_then(|proof_dir| history::git_revision(proof_dir));
+    if let Some(git_revision) = &git_revision {
+        metadata
+            .meta
+            .push(("git_revision".to_string(), git_revision.clone()));
+    }
+    if metadata.get("host").is_none() {
+        if let Some(hostname) = results::hostname() {
+            metadata.meta.push(("host".to_string(), hostname));
+        }
+    }
+    for (key, value) in system::metadata() {
+        if metadata.get(&key).is_none() {
+            metadata.meta.push((key, value));
+        }
+    }
+    if let Some(governor) = results::cpu_governor() {
+        if accurate && governor != "performance" {
+            println!(
+                "WARNING the CPU frequency governor is {} instead of performance, runtimes will vary with the clock speed",
+                governor
+            );
+        }
+        metadata.meta.push(("cpu_governor".to_string(), governor));
+    }
+    if args.calibrate && metadata.get(calibration::CALIBRATION_KEY).is_none() {
+        println!("CALIBRATING");
+        let calibration = calibration::calibrate();
+        println!("CALIBRATION {}s", calibration);
+        metadata.meta.push((
+            calibration::CALIBRATION_KEY.to_string(),
+            calibration.to_string(),
+        ));
+    }
+    if args.no_network {
+        metadata
+            .meta
+            .push(("network".to_string(), "disabled".to_string()));
+    }
+    if args.perf_stat {
+        perf::check()?;
+        metadata
+            .meta
+            .push(("perf_stat".to_string(), "true".to_string()));
+    }
+    if args.tool_times {
+        metadata
+            .meta
+            .push(("tool_times".to_string(), "true".to_string()));
+    }
+    if pin_cpus {
+        metadata
+            .meta
+            .push(("pin_cpus".to_string(), "true".to_string()));
+    }
+    if let Some(container) = &run_options.container {
+        let mut value = format!("{} ({})", container.image, container.runtime);
+        if let Some(cpus) = container.cpus {
+            value.push_str(&format!(" cpus={}", cpus));
+        }
+        if let Some(memory_bytes) = container.memory_bytes {
+            value.push_str(&format!(" memory={}", memory_bytes));
+        }
+        metadata.meta.push(("container".to_string(), value));
+    }
+    if let Some(aws_batch) = &args.aws_batch {
+        metadata
+            .meta
+            .push(("aws_batch".to_string(), aws_batch.to_string()));
+    }
+    if !args.workers.is_empty() {
+        let workers: Vec<String> = args.workers.iter().map(ToString::to_string).collect();
+        metadata
+            .meta
+            .push(("workers".to_string(), workers.join(",")));
+    }
+    if let Some(admission) = &schedule.admission {
+        metadata.meta.push((
+            "adaptive_jobs".to_string(),
+            format!(
+                "max_load={} min_free_memory={}",
+                admission.max_load, admission.min_free_memory_bytes
+            ),
+        ));
+    }
+    if args.clean_target != "veryclean" {
+        metadata
+            .meta
+            .push(("clean_target".to_string(), args.clean_target.clone()));
+    }
+    if let Some(page_cache) = run_options.page_cache {
+        metadata
+            .meta
+            .push(("page_cache".to_string(), page_cache.to_string()));
+    }
+    if run_options.cooldown > Duration::ZERO {
+        metadata.meta.push((
+            "cooldown".to_string(),
+            format!(
+                "{}s{}",
+                run_options.cooldown.as_secs_f64(),
+                if run_options.cooldown_between_proofs {
+                    " between proofs"
+                } else {
+                    ""
+                }
+            ),
+        ));
+    }
+    if let Some(make_jobs) = run_options.make_jobs {
+        metadata
+            .meta
+            .push(("make_jobs".to_string(), make_jobs.to_string()));
+    }
+    if let Some(nice) = args.nice {
+        metadata.meta.push(("nice".to_string(), nice.to_string()));
+    }
+    if let Some(ionice) = args.ionice {
+        metadata
+            .meta
+            .push(("ionice".to_string(), ionice.to_string()));
+    }
+    if !unsatisfiable.is_empty() {
+        let names: Vec<String> = unsatisfiable
+            .iter()
+            .map(|proof_dir| proof_name(proof_dir))
+            .collect();
+        metadata
+            .meta
+            .push(("unsatisfiable_proofs".to_string(), names.join(" ")));
+    }
+    if args.read_only_sources {
+        metadata
+            .meta
+            .push(("read_only_sources".to_string(), "true".to_string()));
+    }
+    if let Some(memory_limit) = args.memory_limit {
+        metadata
+            .meta
+            .push(("memory_limit_mib".to_string(), memory_limit.to_string()));
+    }
+    match args.timeout {
+        Some(Timeout::Fixed(timeout)) => metadata.meta.push((
+            "timeout_secs".to_string(),
+            timeout.as_secs_f64().to_string(),
+        )),
+        Some(Timeout::Auto(factor)) => metadata
+            .meta
+            .push(("timeout_factor".to_string(), factor.to_string())),
+        None => {}
+    }
+    if args.calibrate || args.subtract_make_overhead {
+        let make_overhead = calibration::make_overhead(&run_options)?;
+        println!("MAKE OVERHEAD {}s", make_overhead.as_secs_f64());
+        metadata.meta.push((
+            calibration::MAKE_OVERHEAD_KEY.to_string(),
+            make_overhead.as_secs_f64().to_string(),
+        ));
+        if args.subtract_make_overhead {
+            run_options.make_overhead = make_overhead;
+            metadata
+                .meta
+                .push(("make_overhead_subtracted".to_string(), "true".to_string()));
+        }
+    }
+    if let Some(failure_artifacts_dir) = &args.failure_artifacts_dir {
+        std::fs::create_dir_all(failure_artifacts_dir)?;
+    }
+    let templates = args
+        .report_template
+        .iter()
+        .map(|(template, output)| ReportTemplate::load(template, output))
+        .collect::<GenericResult<Vec<_>>>()?;
+    if let Some(seed) = shuffle_seed {
+        metadata
+            .meta
+            .push(("shuffle_seed".to_string(), seed.to_string()));
+    }
+    if let Some(budget) = schedule.memory_budget {
+        metadata
+            .meta
+            .push(("memory_budget".to_string(), budget.to_string()));
+    }
+    let output = OutputOptions {
+        csv_file,
+        runs_file: args.runs_file.clone(),
+        progress_file: args.progress_file.clone(),
+        timeline_file: args.timeline_file.clone(),
+        timeline_interval: args.timeline_interval,
+        failures_file: args.failures_file.clone(),
+        state_file: args.state_file.clone(),
+        resume: args.resume,
+        compression: args.compress,
+    };
+    benchmark_all_proofs(
+        proof_dirs,
+        iterations,
+        &output,
+        &metadata,
+        &run_options,
+        &schedule,
+        &ReportOptions {
+            baseline,
+            slowest_report: args.slowest_report.clone(),
+            templates,
+            statistic: args.statistic,
+            top: args.top,
+            speedup_report: args.speedup_report.clone(),
+            bootstrap_samples: args.bootstrap_samples,
+            report_on_change_only: args.report_on_change_only,
+            history,
+            audience: args.audience,
+            names,
+        },
+    )?;
+    if let (Some(runs_file), Some(compression)) = (&output.runs_file, output.compression) {
+        let compressed = compression::compress_file(runs_file, compression)?;
+        println!("COMPRESSED run details into {}", compressed.display());
+    }
+    if let Some(overlay_dir) = &run_options.overlay_dir {
+        // every overlay is removed after its run, so this is empty unless something went wrong
+        let _ = std::fs::remove_dir(overlay_dir);
+    }
+    if monitor::interrupted() {
+        return Err("interrupted, the results are incomplete".into());
+    }
+    if let Some(history_dir) = &args.history_dir {
+        let stored = history::store(
+            history_dir,
+            &output.csv_file,
+            timestamp,
+            git_revision.as_deref(),
+            args.compress,
+        )?;
+        println!("STORED results in {}", stored.display());
+    }
+    Ok(())
 }
 
 fn main() -> GenericResult<()> {
     let args = Arguments::from_args();
 
-    benchmark_all_proofs_in(
-        &args.proofs_path,
-        args.iterations,
-        args.parallel_jobs,
-        &args.csv_file,
-    )
+    match &args.command {
+        Some(Command::Compare(compare_args)) => compare::compare_result_files(compare_args),
+        Some(Command::Trend(trend_args)) => history::print_trend(trend_args),
+        Some(Command::TimeTool(time_tool_args)) => tools::time_tool(time_tool_args),
+        Some(Command::BatchJob(batch_job_args)) => batch::batch_job(batch_job_args),
+        Some(Command::Calibrate) => {
+            println!(
+                "{}={}",
+                calibration::CALIBRATION_KEY,
+                calibration::calibrate()
+            );
+            Ok(())
+        }
+        None => run_benchmarks(&args),
+    }
 }
@@ -0,0 +1,821 @@
+// Running a measured command while watching its process tree through /proc.
+use crate::cbmc::CbmcStats;
+use crate::energy;
+use crate::perf::PerfCounters;
+use crate::tools::ToolTimes;
+use crate::GenericResult;
+use crossbeam_channel::{bounded, RecvTimeoutError};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Error, ErrorKind, Result as IOResult};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// how often the process tree of a run gets sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+// the process groups of the runs in progress, every run gets its own so we can take down
+// everything it started (including processes that were orphaned when their parent died) at once,
+// each with the command that stops what the run started outside of it (like a container), until
+// that was run
+static RUNNING_GROUPS: Mutex<BTreeMap<u32, Option<Command>>> = Mutex::new(BTreeMap::new());
+
+// set once the session was interrupted, after which it only writes the results it has so far
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// set (while holding the lock on RUNNING_GROUPS) once the runs in progress were killed, because the
+// session was interrupted or ran out of time, no runs start after that
+static KILLED: AtomicBool = AtomicBool::new(false);
+
+// set once --max-total-time is up, the runs in progress finish but no further ones start
+static OUT_OF_TIME: AtomicBool = AtomicBool::new(false);
+
+// how long the session was suspended with SIGTSTP so far, which doesn't count towards the
+// runtimes of the runs that were in progress at the time
+static SUSPENDED_TIME: Mutex<Duration> = Mutex::new(Duration::ZERO);
+
+// whether the session was interrupted, after which it only writes the results it has so far
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+// whether the runs in progress were killed, so a run that ended since didn't end on its own
+pub fn runs_killed() -> bool {
+    KILLED.load(Ordering::SeqCst)
+}
+
+// whether the session ran out of time
+pub fn out_of_time() -> bool {
+    OUT_OF_TIME.load(Ordering::SeqCst)
+}
+
+pub fn suspended_time() -> Duration {
+    *SUSPENDED_TIME
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// whether no further runs (or proofs) should start, because the session was interrupted or ran out
+// of time
+pub fn stopping() -> bool {
+    interrupted() || out_of_time()
+}
+
+// kill all running runs, returning how many there were, and with interrupt mark the session as
+// interrupted
+fn kill_running_groups(interrupt: bool) -> usize {
+    // holding the lock keeps runs from starting or being reaped in the meantime
+    let mut groups = RUNNING_GROUPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if interrupt {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+    KILLED.store(true, Ordering::SeqCst);
+    for (group, kill) in groups.iter_mut() {
+        kill_group(*group);
+        run_kill(kill.take());
+    }
+    groups.len()
+}
+
+// stop the session like an interrupt does, returning how many runs were killed
+pub fn abort() -> usize {
+    kill_running_groups(true)
+}
+
+fn kill_group(group: u32) {
+    // a group stays valid as long as its leader isn't reaped, which only happens after it was
+    // removed from the running groups
+    unsafe {
+        libc::kill(-(group as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+// stop what a run started outside of its process group, as far as we can
+fn run_kill(kill: Option<Command>) {
+    if let Some(mut kill) = kill {
+        let _ = kill
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+fn signal_set(signals: &[libc::c_int]) -> libc::sigset_t {
+    // all zeroes is a valid sigset_t, it is only filled in by sigemptyset anyway
+    let mut set: libc::sigset_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        libc::sigemptyset(&mut set);
+        for signal in signals.iter() {
+            libc::sigaddset(&mut set, *signal);
+        }
+    }
+    set
+}
+
+// stop all running runs and then the session itself, and once it is continued (SIGCONT, e.g. fg in
+// the shell) all of them again. Only our own process is stopped, not the rest of our process group,
+// which can include a script or make that started us
+fn suspend() {
+    // holding the lock keeps runs from starting or being reaped in the meantime
+    let groups = RUNNING_GROUPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    eprintln!(
+        "SUSPENDED with {} running runs, continue with fg or SIGCONT",
+        groups.len()
+    );
+    let start_time = Instant::now();
+    unsafe {
+        for group in groups.keys() {
+            libc::kill(-(*group as libc::pid_t), libc::SIGSTOP);
+        }
+        // returns once we are continued
+        libc::raise(libc::SIGSTOP);
+        for group in groups.keys() {
+            libc::kill(-(*group as libc::pid_t), libc::SIGCONT);
+        }
+    }
+    let suspended = start_time.elapsed();
+    *SUSPENDED_TIME
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) += suspended;
+    eprintln!(
+        "CONTINUED after {}s, the time suspended doesn't count towards runtimes",
+        suspended.as_secs()
+    );
+}
+
+// the runs are in process groups of their own, which don't get the SIGINT of a Ctrl-C or the
+// SIGTSTP of a Ctrl-Z in the terminal, so on SIGINT, SIGTERM or SIGHUP kill all of them and stop
+// starting new ones, so the session can write the results it has so far (a second signal exits
+// right away), and on SIGTSTP suspend them along with the session. This has to be called before
+// any other threads are started, so they inherit the blocked signals
+pub fn handle_signals() {
+    let interrupts = signal_set(&[libc::SIGINT, libc::SIGTERM, libc::SIGHUP]);
+    let suspends = signal_set(&[libc::SIGTSTP]);
+    let all = signal_set(&[libc::SIGINT, libc::SIGTERM, libc::SIGHUP, libc::SIGTSTP]);
+    // children start with an empty signal mask again
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_BLOCK, &all, std::ptr::null_mut());
+    }
+    std::thread::spawn(move || loop {
+        let mut signal = 0;
+        // safe because both pointers are valid for the duration of the call
+        if unsafe { libc::sigwait(&suspends, &mut signal) } == 0 && !interrupted() {
+            suspend();
+        }
+    });
+    let signals = interrupts;
+    std::thread::spawn(move || {
+        let mut signal = 0;
+        // safe because both pointers are valid for the duration of the call
+        while unsafe { libc::sigwait(&signals, &mut signal) } != 0 {}
+        eprintln!(
+            "INTERRUPTED, killed {} running runs, writing the results so far (interrupt again to exit right away)",
+            kill_running_groups(true)
+        );
+        while unsafe { libc::sigwait(&signals, &mut signal) } != 0 {}
+        kill_running_groups(true);
+        eprintln!("INTERRUPTED again, exiting");
+        std::process::exit(128 + signal);
+    });
+}
+
+// once limit has passed, stop starting runs and let the ones in progress finish, or kill them like
+// an interrupt does, but without failing the session
+pub fn stop_after(limit: Duration, kill: bool) {
+    std::thread::spawn(move || {
+        std::thread::sleep(limit);
+        if kill {
+            OUT_OF_TIME.store(true, Ordering::SeqCst);
+            eprintln!(
+                "OUT OF TIME after {}s, killed {} running runs, writing the results so far",
+                limit.as_secs(),
+                kill_running_groups(false)
+            );
+            return;
+        }
+        let running = {
+            let groups = RUNNING_GROUPS
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            OUT_OF_TIME.store(true, Ordering::SeqCst);
+            groups.len()
+        };
+        eprintln!(
+            "OUT OF TIME after {}s, letting {} running runs finish and not starting any more",
+            limit.as_secs(),
+            running
+        );
+    });
+}
+
+// what we observed about the process tree of a run
+#[derive(Clone, Default)]
+pub struct RunStats {
+    // the CPUs any process of the run was seen running on
+    pub cpus: BTreeSet<usize>,
+    // the peak resident set size of the biggest process in the tree in KiB, as reported by wait4
+    pub max_rss_kib: Option<u64>,
+    // CPU time spent in user and kernel mode by all processes in the tree
+    pub user_time: Option<Duration>,
+    pub system_time: Option<Duration>,
+    // page faults that did and didn't need I/O, summed over the tree
+    pub major_faults: Option<u64>,
+    pub minor_faults: Option<u64>,
+    // context switches because a process waited for something or was preempted
+    pub voluntary_switches: Option<u64>,
+    pub involuntary_switches: Option<u64>,
+    // bytes the tree caused to be read from and written to storage (not just the page cache)
+    pub io_read_bytes: Option<u64>,
+    pub io_write_bytes: Option<u64>,
+    // average and peak number of CPUs the tree kept busy
+    pub cpu_utilization: Option<f64>,
+    pub max_cpu_utilization: Option<f64>,
+    // average and peak fraction of the whole machine's CPU time that was in use, by anything
+    pub system_utilization: Option<f64>,
+    pub max_system_utilization: Option<f64>,
+    // the 1 minute load average of the machine when the run started and when it finished
+    pub start_load_average: Option<f64>,
+    pub end_load_average: Option<f64>,
+    // the energy the CPU packages of the whole machine used during the run, where RAPL is
+    // available
+    pub energy_joules: Option<f64>,
+    // pages the whole machine swapped in or out during the run, which makes its runtime worthless
+    pub swapped_pages: Option<u64>,
+    // how long spawning the measured command (fork and exec of make) took, which is part of the
+    // runtime of every run
+    pub spawn_time: Option<Duration>,
+    // how many times the run failed and was repeated with --retries before this attempt
+    pub retries: u32,
+    // disk space taken up by the proof directory after the run, including the build artifacts
+    pub artifact_bytes: Option<u64>,
+    // how long building the proof (make goto) took right before the run
+    pub build_time: Option<Duration>,
+    // what CBMC reported in the output of the run, only known with --log-dir
+    pub cbmc: CbmcStats,
+    // hardware counters of the measured command, only known with --perf-stat
+    pub perf: PerfCounters,
+    // how long goto-cc and goto-instrument, and cbmc took, only known with --tool-times
+    pub tool_times: ToolTimes,
+    // how long each make target run after the measured one took, None if it failed
+    pub phase_times: Vec<(String, Option<Duration>)>,
+    // where the output of a failed run went with --log-dir, and how it ended
+    pub log: Option<PathBuf>,
+    pub log_tail: Vec<String>,
+    // the tree was killed because all its processes together took up more than the memory limit
+    pub memory_limit_exceeded: bool,
+    // the tree was killed because the run took longer than the timeout
+    pub timed_out: bool,
+}
+
+// when to kill the process tree of a run
+#[derive(Clone, Copy, Default)]
+pub struct Limits {
+    // the resident memory of all its processes together in KiB
+    pub memory_kib: Option<u64>,
+    // the time since it was started
+    pub time: Option<Duration>,
+}
+
+// --timeout, either fixed or auto:<factor>x, a multiple of the runtime of the first successful
+// run of a proof for the runs after it
+#[derive(Clone, Copy)]
+pub enum Timeout {
+    Fixed(Duration),
+    Auto(f64),
+}
+
+impl FromStr for Timeout {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Timeout, String> {
+        match arg.strip_prefix("auto:") {
+            Some(factor) => match factor.trim_end_matches('x').parse::<f64>() {
+                Ok(factor) if factor >= 1.0 && factor.is_finite() => Ok(Timeout::Auto(factor)),
+                _ => Err(format!(
+                    "invalid timeout factor in {}, expected something like auto:3x",
+                    arg
+                )),
+            },
+            None => parse_duration(arg).map(Timeout::Fixed),
+        }
+    }
+}
+
+// a duration given as seconds, optionally with a unit like 90s, 1.5m or 2h
+pub fn parse_duration(arg: &str) -> Result<Duration, String> {
+    let (number, unit) = match arg.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(position) => arg.split_at(position),
+        None => (arg, "s"),
+    };
+    let factor = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}' in {}, expected s, m or h",
+                unit, arg
+            ))
+        }
+    };
+    match number.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => {
+            Duration::try_from_secs_f64(number * factor)
+                .map_err(|_| format!("invalid duration {}, it is too long", arg))
+        }
+        _ => Err(format!(
+            "invalid duration {}, expected something like 90s",
+            arg
+        )),
+    }
+}
+
+// the timeout for the proofs whose name matches a wildcard pattern instead of --timeout, given
+// as PATTERN=TIMEOUT
+#[derive(Clone)]
+pub struct ProofTimeout {
+    pub pattern: String,
+    pub timeout: Timeout,
+}
+
+impl FromStr for ProofTimeout {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<ProofTimeout, String> {
+        match arg.split_once('=') {
+            Some((pattern, timeout)) if !pattern.trim().is_empty() => Ok(ProofTimeout {
+                pattern: pattern.trim().to_string(),
+                timeout: timeout.trim().parse()?,
+            }),
+            _ => Err(format!(
+                "invalid proof timeout '{}', expected PATTERN=TIMEOUT (e.g. s2n_*=2h)",
+                arg
+            )),
+        }
+    }
+}
+
+// the proof timeouts in a file with a PATTERN=TIMEOUT line for each, # starts a comment
+pub fn load_proof_timeouts(path: &Path) -> GenericResult<Vec<ProofTimeout>> {
+    let mut proof_timeouts = Vec::new();
+    for (line_nr, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        proof_timeouts.push(
+            line.parse()
+                .map_err(|err| format!("{}:{}: {}", path.display(), line_nr + 1, err))?,
+        );
+    }
+    Ok(proof_timeouts)
+}
+
+pub struct MonitoredRun {
+    pub status: ExitStatus,
+    // when the command exited, unaffected by the time it takes to stop monitoring
+    pub finish_time: Instant,
+    pub stats: RunStats,
+}
+
+// the fields of /proc/<pid>/stat we care about
+struct ProcessStat {
+    parent: u32,
+    // whether it is running or waiting for a CPU
+    runnable: bool,
+    // resident set size in pages
+    rss_pages: u64,
+    // the CPU the process last ran on
+    processor: usize,
+    // user and system time so far, in clock ticks
+    cpu_ticks: u64,
+}
+
+fn process_stat(pid: u32) -> Option<ProcessStat> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name in parentheses can contain spaces and parentheses itself, so we only
+    // split what comes after it, starting with the state (field 3)
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    Some(ProcessStat {
+        parent: fields.get(1)?.parse().ok()?,
+        runnable: *fields.first()? == "R",
+        rss_pages: fields.get(21)?.parse().ok()?,
+        processor: fields.get(36)?.parse().ok()?,
+        cpu_ticks: fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?,
+    })
+}
+
+fn all_processes() -> HashMap<u32, ProcessStat> {
+    match std::fs::read_dir("/proc") {
+        Ok(entries) => entries
+            .filter_map(|entry| {
+                let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+                Some((pid, process_stat(pid)?))
+            })
+            .collect(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+// pid and stat of root and all its descendants
+fn process_tree(root: u32) -> Vec<(u32, ProcessStat)> {
+    split_tree(root, &mut all_processes())
+}
+
+// remove root and all its descendants from processes and return their pids and stats
+fn split_tree(root: u32, processes: &mut HashMap<u32, ProcessStat>) -> Vec<(u32, ProcessStat)> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (&pid, stat) in processes.iter() {
+        children.entry(stat.parent).or_default().push(pid);
+    }
+    let mut tree = Vec::new();
+    let mut pending = vec![root];
+    while let Some(pid) = pending.pop() {
+        if let Some(stat) = processes.remove(&pid) {
+            tree.push((pid, stat));
+        }
+        pending.extend(children.remove(&pid).unwrap_or_default());
+    }
+    tree
+}
+
+fn page_kib() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 / 1024 }
+}
+
+// resident memory of all processes in tree in KiB
+fn tree_rss_kib(tree: &[(u32, ProcessStat)]) -> u64 {
+    tree.iter()
+        .map(|(_, process)| process.rss_pages * page_kib())
+        .sum()
+}
+
+// the number of runnable processes outside the process tree of root, and the resident memory of
+// the tree in KiB, to tell what the rest of the machine is doing besides root
+pub fn outside_tree(root: u32) -> (usize, u64) {
+    let mut processes = all_processes();
+    let tree = split_tree(root, &mut processes);
+    (
+        processes
+            .values()
+            .filter(|process| process.runnable)
+            .count(),
+        tree_rss_kib(&tree),
+    )
+}
+
+// total and idle (including waiting for I/O) CPU time of the whole machine from /proc/stat, in
+// clock ticks
+fn system_cpu_ticks() -> Option<(u64, u64)> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let times: Vec<u64> = stat
+        .lines()
+        .next()?
+        .strip_prefix("cpu ")?
+        .split_whitespace()
+        .map(|time| time.parse().ok())
+        .collect::<Option<_>>()?;
+    Some((times.iter().sum(), times.get(3)? + times.get(4)?))
+}
+
+// the 1 minute load average from /proc/loadavg
+fn load_average() -> Option<f64> {
+    std::fs::read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+// the pages swapped in and out since boot from /proc/vmstat
+fn swapped_pages() -> Option<u64> {
+    let vmstat = std::fs::read_to_string("/proc/vmstat").ok()?;
+    let counter = |name: &str| -> Option<u64> {
+        vmstat.lines().find_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            value.trim().parse().ok().filter(|_| key == name)
+        })
+    };
+    Some(counter("pswpin")? + counter("pswpout")?)
+}
+
+fn clock_ticks_per_second() -> f64 {
+    unsafe { libc::sysconf(libc::_SC_CLK_TCK) as f64 }
+}
+
+fn max(current: Option<f64>, value: f64) -> Option<f64> {
+    Some(current.map_or(value, |current| current.max(value)))
+}
+
+// the state of the background sampling of a run
+struct Sampler {
+    root: u32,
+    limits: Limits,
+    start_time: Instant,
+    // the suspended_time() at start_time
+    start_suspended_time: Duration,
+    stats: RunStats,
+    // CPU ticks of the processes in the tree as of the last sample
+    process_ticks: HashMap<u32, u64>,
+    last_sample_time: Instant,
+    first_system_ticks: Option<(u64, u64)>,
+    last_system_ticks: Option<(u64, u64)>,
+}
+
+impl Sampler {
+    fn new(
+        root: u32,
+        limits: Limits,
+        start_time: Instant,
+        start_suspended_time: Duration,
+    ) -> Sampler {
+        let system_ticks = system_cpu_ticks();
+        Sampler {
+            root,
+            limits,
+            start_time,
+            start_suspended_time,
+            stats: RunStats::default(),
+            process_ticks: HashMap::new(),
+            last_sample_time: Instant::now(),
+            first_system_ticks: system_ticks,
+            last_system_ticks: system_ticks,
+        }
+    }
+
+    fn sample_system(&mut self) {
+        let system_ticks = system_cpu_ticks();
+        if let (Some((last_total, last_idle)), Some((total, idle))) =
+            (self.last_system_ticks, system_ticks)
+        {
+            if total > last_total {
+                let busy = (total - last_total).saturating_sub(idle - last_idle);
+                self.stats.max_system_utilization = max(
+                    self.stats.max_system_utilization,
+                    busy as f64 / (total - last_total) as f64,
+                );
+            }
+        }
+        if let (Some((first_total, first_idle)), Some((total, idle))) =
+            (self.first_system_ticks, system_ticks)
+        {
+            if total > first_total {
+                let busy = (total - first_total).saturating_sub(idle - first_idle);
+                self.stats.system_utilization = Some(busy as f64 / (total - first_total) as f64);
+            }
+        }
+        self.last_system_ticks = system_ticks;
+    }
+
+    fn sample(&mut self) {
+        let now = Instant::now();
+        let mut busy_ticks = 0;
+        let mut process_ticks = HashMap::new();
+        let tree = process_tree(self.root);
+        let memory_limit_exceeded = self
+            .limits
+            .memory_kib
+            .is_some_and(|memory_kib| tree_rss_kib(&tree) > memory_kib);
+        let timed_out = self.limits.time.is_some_and(|time| {
+            (now - self.start_time).saturating_sub(suspended_time() - self.start_suspended_time)
+                > time
+        });
+        if memory_limit_exceeded || timed_out {
+            // taken before the run is killed, after that it can be removed from the running groups
+            let kill = RUNNING_GROUPS
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .get_mut(&self.root)
+                .and_then(Option::take);
+            kill_group(self.root);
+            run_kill(kill);
+            // processes that moved to a group of their own are still in the tree
+            for (pid, _) in tree.iter() {
+                // the root can't have been reaped and its pid reused yet, because we only reap it
+                // after sampling has stopped, and its descendants are reaped by it
+                unsafe {
+                    libc::kill(*pid as libc::pid_t, libc::SIGKILL);
+                }
+            }
+            // whichever limit was hit first is the one the run gets recorded with
+            if !self.stats.memory_limit_exceeded && !self.stats.timed_out {
+                self.stats.memory_limit_exceeded = memory_limit_exceeded;
+                self.stats.timed_out = !memory_limit_exceeded;
+            }
+        }
+        for (pid, process) in tree {
+            self.stats.cpus.insert(process.processor);
+            // processes that started since the last sample spent all their time in this interval
+            busy_ticks += process.cpu_ticks
+                - self
+                    .process_ticks
+                    .get(&pid)
+                    .cloned()
+                    .unwrap_or(0)
+                    .min(process.cpu_ticks);
+            process_ticks.insert(pid, process.cpu_ticks);
+        }
+        let elapsed = (now - self.last_sample_time).as_secs_f64();
+        // the first sample right after starting has nothing to compare to
+        if !self.process_ticks.is_empty() && elapsed > 0.0 {
+            self.stats.max_cpu_utilization = max(
+                self.stats.max_cpu_utilization,
+                busy_ticks as f64 / clock_ticks_per_second() / elapsed,
+            );
+        }
+        self.process_ticks = process_ticks;
+        self.last_sample_time = now;
+        self.sample_system();
+    }
+}
+
+fn to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+// a field of /proc/<pid>/io
+fn io_field(io: &str, name: &str) -> Option<u64> {
+    io.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// the storage I/O of pid and all its descendants that it has waited for, in bytes read and
+// written (without writes that were cancelled because the file got deleted before it was written
+// back)
+fn process_io(pid: u32) -> Option<(u64, u64)> {
+    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    Some((
+        io_field(&io, "read_bytes")?,
+        io_field(&io, "write_bytes")?
+            .saturating_sub(io_field(&io, "cancelled_write_bytes").unwrap_or(0)),
+    ))
+}
+
+// wait for the process pid to exit without reaping it, so its /proc entry stays around
+fn wait_exited(pid: u32) -> IOResult<()> {
+    // all zeroes is a valid siginfo_t, it only consists of integers
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    loop {
+        // safe because info is valid for writes for the duration of the call
+        let result = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if result >= 0 {
+            return Ok(());
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+// wait for the process pid to exit, with the resource usage of it and all its descendants
+fn wait4(pid: u32) -> IOResult<(ExitStatus, libc::rusage)> {
+    use std::os::unix::process::ExitStatusExt;
+    let mut status = 0;
+    // all zeroes is a valid rusage, it only consists of integers
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    loop {
+        // safe because status and usage are valid for writes for the duration of the call
+        let result = unsafe { libc::wait4(pid as libc::pid_t, &mut status, 0, &mut usage) };
+        if result >= 0 {
+            return Ok((ExitStatus::from_raw(status), usage));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+// run command to completion, sampling its process tree in the background and killing it if it
+// exceeds the limits, or the session is stopped, along with running kill
+pub fn run(command: &mut Command, kill: Option<Command>, limits: Limits) -> IOResult<MonitoredRun> {
+    // we reap the child ourselves with wait4 instead of through Child::wait to get its resource
+    // usage, dropping the Child doesn't wait for or kill it
+    let start_load_average = load_average();
+    let start_energy = energy::sample();
+    let start_swapped_pages = swapped_pages();
+    let start_time = Instant::now();
+    let (pid, spawn_time) = {
+        let mut groups = RUNNING_GROUPS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if runs_killed() {
+            return Err(Error::new(
+                ErrorKind::Interrupted,
+                "the session was stopped",
+            ));
+        }
+        let spawn_start_time = Instant::now();
+        let pid = command.process_group(0).spawn()?.id();
+        let spawn_time = spawn_start_time.elapsed();
+        groups.insert(pid, kill);
+        (pid, spawn_time)
+    };
+    let (stop_sender, stop_receiver) = bounded::<()>(0);
+    let sampler = std::thread::spawn(move || {
+        let mut sampler = Sampler::new(pid, limits, start_time, suspended_time());
+        loop {
+            sampler.sample();
+            match stop_receiver.recv_timeout(SAMPLE_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => {
+                    sampler.sample_system();
+                    return sampler.stats;
+                }
+            }
+        }
+    });
+    let exited = wait_exited(pid);
+    let finish_time = Instant::now();
+    let end_load_average = load_average();
+    let end_energy = energy::sample();
+    let end_swapped_pages = swapped_pages();
+    // the I/O counters of a process include those of the children it reaped, so at this point
+    // they cover the whole tree
+    let io = process_io(pid);
+    // whatever the run left behind would eat into the CPU time of the runs after it
+    kill_group(pid);
+    RUNNING_GROUPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(&pid);
+    let result = exited.and_then(|_| wait4(pid));
+    drop(stop_sender);
+    let mut stats = sampler.join().expect("the sampler thread doesn't panic");
+    let (status, usage) = result?;
+    if let Some((read_bytes, write_bytes)) = io {
+        stats.io_read_bytes = Some(read_bytes);
+        stats.io_write_bytes = Some(write_bytes);
+    }
+    // ru_maxrss is in KiB on Linux
+    stats.spawn_time = Some(spawn_time);
+    stats.start_load_average = start_load_average;
+    stats.end_load_average = end_load_average;
+    if let (Some(start_energy), Some(end_energy)) = (start_energy, end_energy) {
+        stats.energy_joules = end_energy.joules_since(&start_energy);
+    }
+    if let (Some(start), Some(end)) = (start_swapped_pages, end_swapped_pages) {
+        stats.swapped_pages = Some(end.saturating_sub(start));
+    }
+    stats.max_rss_kib = Some(usage.ru_maxrss as u64);
+    stats.user_time = Some(to_duration(usage.ru_utime));
+    stats.system_time = Some(to_duration(usage.ru_stime));
+    let runtime = (finish_time - start_time).as_secs_f64();
+    if runtime > 0.0 {
+        stats.cpu_utilization = Some(
+            (to_duration(usage.ru_utime) + to_duration(usage.ru_stime)).as_secs_f64() / runtime,
+        );
+    }
+    stats.major_faults = Some(usage.ru_majflt as u64);
+    stats.minor_faults = Some(usage.ru_minflt as u64);
+    stats.voluntary_switches = Some(usage.ru_nvcsw as u64);
+    stats.involuntary_switches = Some(usage.ru_nivcsw as u64);
+    Ok(MonitoredRun {
+        status,
+        finish_time,
+        stats,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1.5m"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("5"), Ok(Duration::from_secs(5)));
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("-1s").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration("abc").is_err());
+        assert!(parse_duration("inf").is_err());
+        assert_eq!(
+            parse_duration("100000000000000000000000h"),
+            Err("invalid duration 100000000000000000000000h, it is too long".to_string())
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// Human-friendly display names and groups for proofs in reports, read from a mapping file with
+// lines of raw-name,display-name[,group]. Machine outputs like the csv and runs files keep the
+// raw names.
+use crate::results::ProofResults;
+use crate::GenericResult;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Default)]
+pub struct NameMap {
+    display_names: HashMap<String, String>,
+    // by display name
+    groups: HashMap<String, String>,
+}
+
+// a proof name without the @target suffix of A/B comparisons, and the suffix
+fn split_variant(name: &str) -> (&str, &str) {
+    match name.find('@') {
+        Some(position) => name.split_at(position),
+        None => (name, ""),
+    }
+}
+
+impl NameMap {
+    pub fn load(path: &Path) -> GenericResult<NameMap> {
+        let mut names = NameMap::default();
+        let mut raw_names: HashMap<String, String> = HashMap::new();
+        for (line_nr, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            let (raw_name, display_name, group) = match fields.as_slice() {
+                [raw_name, display_name] => (*raw_name, *display_name, None),
+                [raw_name, display_name, group] => (*raw_name, *display_name, Some(*group)),
+                _ => {
+                    return Err(format!(
+                        "{}:{}: expected raw-name,display-name[,group]",
+                        path.display(),
+                        line_nr + 1
+                    )
+                    .into())
+                }
+            };
+            if raw_name.is_empty() || display_name.is_empty() {
+                return Err(format!("{}:{}: empty name", path.display(), line_nr + 1).into());
+            }
+            if names.display_names.contains_key(raw_name) {
+                return Err(format!(
+                    "{}:{}: {} is mapped more than once",
+                    path.display(),
+                    line_nr + 1,
+                    raw_name
+                )
+                .into());
+            }
+            // proofs are matched up by name across sessions, so display names must stay unique
+            if let Some(other) = raw_names.insert(display_name.to_string(), raw_name.to_string()) {
+                return Err(format!(
+                    "{}:{}: display name {} is used for both {} and {}",
+                    path.display(),
+                    line_nr + 1,
+                    display_name,
+                    other,
+                    raw_name
+                )
+                .into());
+            }
+            names
+                .display_names
+                .insert(raw_name.to_string(), display_name.to_string());
+            if let Some(group) = group.filter(|group| !group.is_empty()) {
+                names
+                    .groups
+                    .insert(display_name.to_string(), group.to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    // the display name of a raw proof name, the raw name itself if it isn't mapped
+    pub fn display(&self, name: &str) -> String {
+        let (proof, variant) = split_variant(name);
+        match self.display_names.get(proof) {
+            Some(display_name) => format!("{}{}", display_name, variant),
+            None => name.to_string(),
+        }
+    }
+
+    // the group of a proof by its display name
+    pub fn group(&self, display_name: &str) -> Option<&str> {
+        self.groups
+            .get(split_variant(display_name).0)
+            .map(String::as_str)
+    }
+
+    pub fn has_groups(&self) -> bool {
+        !self.groups.is_empty()
+    }
+
+    // give proofs their display names, for everything that goes into reports
+    pub fn rename(&self, proofs: &mut [ProofResults]) {
+        for proof in proofs.iter_mut() {
+            proof.name = self.display(&proof.name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_name_maps() {
+        let path =
+            std::env::temp_dir().join(format!("benchmark-test-names-{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "# raw,display,group\n\naws_array_list, Array list, containers\nhash_table,Hash table\n",
+        )
+        .unwrap();
+        let names = NameMap::load(&path).unwrap();
+        assert_eq!(names.display("aws_array_list"), "Array list");
+        assert_eq!(names.display("aws_array_list@new"), "Array list@new");
+        assert_eq!(names.display("unmapped"), "unmapped");
+        assert_eq!(names.group("Array list"), Some("containers"));
+        assert_eq!(names.group("Array list@new"), Some("containers"));
+        assert_eq!(names.group("Hash table"), None);
+        assert!(names.has_groups());
+
+        for (contents, error) in [
+            (
+                "a,b\nonly-one-field\n",
+                ":2: expected raw-name,display-name[,group]",
+            ),
+            ("a,\n", ":1: empty name"),
+            ("a,b\na,c\n", ":2: a is mapped more than once"),
+            ("a,b\nc,b\n", ":2: display name b is used for both a and c"),
+        ] {
+            std::fs::write(&path, contents).unwrap();
+            let err = NameMap::load(&path).err().unwrap().to_string();
+            assert!(err.ends_with(error), "{}", err);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+// Checks that the files a session writes end up outside the source trees of the proofs, since
+// make veryclean or git clean in there has wiped out results before.
+use crate::sandbox;
+use crate::GenericResult;
+use std::io::Result as IOResult;
+use std::path::{Path, PathBuf};
+
+// where the csv file goes without --csv-file or --output-dir, below $HOME
+const DEFAULT_OUTPUT_DIR: &str = "benchmark-results";
+
+// the directory results go to by default, None if there is no home directory
+pub fn default_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(DEFAULT_OUTPUT_DIR))
+}
+
+// the absolute path without symlinks, also for paths that don't exist yet (as far as they do)
+fn resolve(path: &Path) -> IOResult<PathBuf> {
+    let path = std::env::current_dir()?.join(path);
+    let mut existing = path.as_path();
+    let mut missing = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            // like a/.. for a missing a, which can't be resolved without creating a
+            _ => return Ok(path.clone()),
+        }
+    }
+    Ok(missing
+        .iter()
+        .rev()
+        .fold(existing.canonicalize()?, |resolved, name| {
+            resolved.join(name)
+        }))
+}
+
+// the trees proof builds and cleanups can touch, the git checkouts containing the proofs (and the
+// directory they were discovered in) or the directories themselves outside of one
+pub fn protected_trees(dirs: &[PathBuf]) -> IOResult<Vec<PathBuf>> {
+    let mut trees: Vec<PathBuf> = Vec::new();
+    for dir in dirs.iter() {
+        let tree = sandbox::source_root(dir)?;
+        if !trees.contains(&tree) {
+            trees.push(tree);
+        }
+    }
+    Ok(trees)
+}
+
+// fail if any of the outputs, given with the option that names them, is inside a protected tree
+pub fn check(outputs: &[(&str, &Path)], trees: &[PathBuf]) -> GenericResult<()> {
+    for (option, output) in outputs.iter() {
+        let resolved = resolve(output)?;
+        if let Some(tree) = trees.iter().find(|tree| resolved.starts_with(tree)) {
+            return Err(format!(
+                "{} {} is inside {}, where cleaning up the proofs can delete it; write it somewhere \
+                 else (e.g. with --output-dir) or pass --allow-output-in-proofs",
+                option,
+                output.display(),
+                tree.display()
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+// Simple wildcard patterns for selecting proofs by name: * matches any sequence of characters
+// and ? any single character.
+use crate::GenericResult;
+use std::path::Path;
+
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // classic backtracking matcher, we only need to remember the position of the last *
+    let (mut p, mut n) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            last_star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = last_star {
+            p = star_p + 1;
+            n = star_n + 1;
+            last_star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+pub fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| matches(pattern, name))
+}
+
+// the patterns in a file with one per line, skipping empty lines and # comments
+pub fn load(path: &Path) -> GenericResult<Vec<String>> {
+    Ok(std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("aws_array_list", "aws_array_list"));
+        assert!(!matches("aws_array_list", "aws_array_list_get"));
+        assert!(matches("aws_*", "aws_array_list"));
+        assert!(matches("aws_*", "aws_"));
+        assert!(matches("*list*", "aws_array_list_get"));
+        assert!(matches("*_get", "aws_array_list_get"));
+        assert!(!matches("*_get", "aws_array_list_set"));
+        assert!(matches("aws_?ash", "aws_hash"));
+        assert!(!matches("aws_?ash", "aws_ash"));
+        assert!(matches("a*b*c", "aXbYbZc"));
+        assert!(!matches("a*b*c", "aXbYbZ"));
+        assert!(matches("*", ""));
+        assert!(!matches("", "a"));
+        assert!(matches_any(
+            &["s2n_*".to_string(), "aws_*".to_string()],
+            "aws_hash"
+        ));
+        assert!(!matches_any(&[], "aws_hash"));
+    }
+}
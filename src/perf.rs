@@ -0,0 +1,65 @@
+// Hardware counters of a run from wrapping its command in perf stat, which are far less noisy
+// than wall clock time on a shared machine.
+use std::io::{Error, Result as IOResult};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const EVENTS: &str = "instructions,cycles,cache-misses";
+
+// summed over all processes of a run, None where perf couldn't count it
+#[derive(Clone, Default)]
+pub struct PerfCounters {
+    pub instructions: Option<u64>,
+    pub cycles: Option<u64>,
+    pub cache_misses: Option<u64>,
+}
+
+// perf stat writing the counters of the command appended to it to output
+pub fn command(output: &Path) -> Command {
+    let mut perf = Command::new("perf");
+    perf.args(["stat", "-x", ",", "-e", EVENTS, "-o"])
+        .arg(output)
+        .arg("--");
+    perf
+}
+
+// fail early if perf is missing or not allowed to count (see perf_event_paranoid)
+pub fn check() -> IOResult<()> {
+    let output = Command::new("perf")
+        .args(["stat", "-x", ",", "-e", EVENTS, "true"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .output()
+        .map_err(|err| Error::new(err.kind(), format!("can't run perf: {}", err)))?;
+    if !output.status.success() {
+        return Err(Error::other(format!(
+            "perf stat doesn't work here: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+// the csv output of perf stat -x, with lines like 123456,,instructions:u,1000,100.00,,
+pub fn parse(output: &str) -> PerfCounters {
+    let mut counters = PerfCounters::default();
+    for line in output.lines().filter(|line| !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+        // <not counted> and <not supported> don't parse
+        let value = fields[0].parse().ok();
+        match fields[2].split(':').next() {
+            Some("instructions") => counters.instructions = value,
+            Some("cycles") => counters.cycles = value,
+            Some("cache-misses") => counters.cache_misses = value,
+            _ => {}
+        }
+    }
+    counters
+}
+
+pub fn parse_file(path: &Path) -> IOResult<PerfCounters> {
+    Ok(parse(&std::fs::read_to_string(path)?))
+}
@@ -0,0 +1,132 @@
+// Pausing a session while a laptop runs on battery or its CPU is too hot, both of which throttle
+// the CPU, so no runs start under those conditions. The pauses are recorded so the runs that were
+// already going when one started can be discounted.
+use crate::monitor;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+const THERMAL_DIR: &str = "/sys/class/thermal";
+
+// how often the conditions are checked again during a pause
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+// how far the temperature has to fall below the limit before a pause ends, so we don't start
+// runs right at the edge of throttling
+const TEMPERATURE_HYSTERESIS: f64 = 5.0;
+
+#[derive(Clone, Copy, Default)]
+pub struct PauseConditions {
+    pub on_battery: bool,
+    // in degrees Celsius
+    pub max_temperature: Option<f64>,
+}
+
+// from when to when (in seconds since the epoch) no runs were started, and why
+struct PauseWindow {
+    start: u64,
+    end: u64,
+    reason: String,
+}
+
+// held while a job waits for the conditions to recover, so every other job waits behind it and
+// each pause is only recorded once
+static PAUSES: Mutex<Vec<PauseWindow>> = Mutex::new(Vec::new());
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_string())
+}
+
+// whether the machine has a battery and no external power supply is online
+fn on_battery() -> bool {
+    let supplies: Vec<_> = match fs::read_dir(POWER_SUPPLY_DIR) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok()).collect(),
+        Err(_) => return false,
+    };
+    let property = |supply: &fs::DirEntry, name: &str| read_trimmed(&supply.path().join(name));
+    supplies
+        .iter()
+        .any(|supply| property(supply, "type").as_deref() == Some("Battery"))
+        && !supplies.iter().any(|supply| {
+            matches!(
+                property(supply, "type").as_deref(),
+                Some("Mains") | Some("USB")
+            ) && property(supply, "online").as_deref() == Some("1")
+        })
+}
+
+// the highest temperature of all thermal zones in degrees Celsius, None if there are none
+fn temperature() -> Option<f64> {
+    fs::read_dir(THERMAL_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("thermal_zone")
+        })
+        .filter_map(|zone| read_trimmed(&zone.path().join("temp"))?.parse::<f64>().ok())
+        // in millidegrees
+        .map(|temperature| temperature / 1000.0)
+        .reduce(f64::max)
+}
+
+// why runs shouldn't start now, None if they can, with max_temperature as the temperature that
+// counts as too hot
+fn pause_reason(conditions: &PauseConditions, max_temperature: Option<f64>) -> Option<String> {
+    if conditions.on_battery && on_battery() {
+        return Some("on battery power".to_string());
+    }
+    match (max_temperature, temperature()) {
+        (Some(limit), Some(temperature)) if temperature > limit => Some(format!(
+            "CPU temperature {:.0}°C above {:.0}°C",
+            temperature, limit
+        )),
+        _ => None,
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+// block until runs may start under the conditions, unless the session stops starting runs
+pub fn wait_for(conditions: &PauseConditions) {
+    if !conditions.on_battery && conditions.max_temperature.is_none() {
+        return;
+    }
+    let mut pauses = PAUSES.lock().expect("no job panics while pausing");
+    let reason = match pause_reason(conditions, conditions.max_temperature) {
+        Some(reason) => reason,
+        None => return,
+    };
+    println!("PAUSED starting runs, {}", reason);
+    let start = now();
+    let resume_temperature = conditions
+        .max_temperature
+        .map(|limit| limit - TEMPERATURE_HYSTERESIS);
+    while !monitor::stopping() && pause_reason(conditions, resume_temperature).is_some() {
+        sleep(CHECK_INTERVAL);
+    }
+    let end = now();
+    println!("RESUMED after a pause of {}s", end - start);
+    pauses.push(PauseWindow { start, end, reason });
+}
+
+// the pauses so far, as metadata like 1700000000-1700000300 (on battery power)
+pub fn metadata() -> Option<String> {
+    let pauses = PAUSES.lock().expect("no job panics while pausing");
+    let windows: Vec<String> = pauses
+        .iter()
+        .map(|pause| format!("{}-{} ({})", pause.start, pause.end, pause.reason))
+        .collect();
+    Some(windows.join(", ")).filter(|_| !pauses.is_empty())
+}
@@ -0,0 +1,132 @@
+// The CPU (nice) and I/O (ionice) priority of a session with --nice and --ionice, low to share a
+// workstation with a long session or high on a dedicated host. Both are set on the thread that
+// starts the jobs, so every job thread and everything it runs inherits them.
+use std::fmt;
+use std::io::{Error, Result as IOResult};
+use std::str::FromStr;
+
+// see ioprio_set(2), the class goes above the level
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+#[derive(Clone, Copy)]
+pub enum IoClass {
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+// an I/O scheduling class and, for all but idle, the level within it from 0 (highest) to 7
+#[derive(Clone, Copy)]
+pub struct IoPriority {
+    class: IoClass,
+    level: u32,
+}
+
+impl FromStr for IoPriority {
+    type Err = String;
+
+    // CLASS[:LEVEL] like idle, best-effort:7 or realtime:0, the level defaults to 4 like ionice
+    fn from_str(arg: &str) -> Result<IoPriority, String> {
+        let (class, level) = match arg.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (arg, None),
+        };
+        let class = match class {
+            "realtime" => IoClass::Realtime,
+            "best-effort" => IoClass::BestEffort,
+            "idle" => IoClass::Idle,
+            _ => {
+                return Err(format!(
+                    "invalid I/O class in '{}', expected realtime, best-effort or idle",
+                    arg
+                ))
+            }
+        };
+        let level = match (class, level) {
+            (IoClass::Idle, Some(_)) => return Err("the idle I/O class has no levels".to_string()),
+            (_, Some(level)) => match level.parse() {
+                Ok(level) if level <= 7 => level,
+                _ => return Err(format!("invalid I/O level in '{}', expected 0 to 7", arg)),
+            },
+            (_, None) => 4,
+        };
+        Ok(IoPriority { class, level })
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.class {
+            IoClass::Realtime => write!(f, "realtime:{}", self.level),
+            IoClass::BestEffort => write!(f, "best-effort:{}", self.level),
+            IoClass::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+// a nice value, from -20 (highest priority) to 19
+pub fn parse_nice(arg: &str) -> Result<i32, String> {
+    match arg.parse() {
+        Ok(nice) if (-20..=19).contains(&nice) => Ok(nice),
+        _ => Err(format!("invalid nice value '{}', expected -20 to 19", arg)),
+    }
+}
+
+// set the nice value of the calling thread, which the threads and processes it starts inherit
+pub fn set_nice(nice: i32) -> IOResult<()> {
+    // with who 0 this only applies to the calling thread on Linux
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+// set the I/O priority of the calling thread, which the threads and processes it starts inherit
+pub fn set_io_priority(priority: IoPriority) -> IOResult<()> {
+    let class = match priority.class {
+        IoClass::Realtime => 1,
+        IoClass::BestEffort => 2,
+        IoClass::Idle => 3,
+    };
+    let value = (class << IOPRIO_CLASS_SHIFT) | priority.level;
+    // safe because ioprio_set only takes integers
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, value) } != 0 {
+        return Err(Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(arg: &str) -> Result<String, String> {
+        arg.parse::<IoPriority>()
+            .map(|priority| priority.to_string())
+    }
+
+    #[test]
+    fn parses_io_priorities() {
+        assert_eq!(parse("idle"), Ok("idle".to_string()));
+        assert_eq!(parse("best-effort"), Ok("best-effort:4".to_string()));
+        assert_eq!(parse("best-effort:7"), Ok("best-effort:7".to_string()));
+        assert_eq!(parse("realtime:0"), Ok("realtime:0".to_string()));
+        assert_eq!(
+            parse("idle:3"),
+            Err("the idle I/O class has no levels".to_string())
+        );
+        assert!(parse("realtime:8").is_err());
+        assert!(parse("best-effort:").is_err());
+        assert!(parse("low").is_err());
+    }
+
+    #[test]
+    fn parses_nice_values() {
+        assert_eq!(parse_nice("-20"), Ok(-20));
+        assert_eq!(parse_nice("19"), Ok(19));
+        assert!(parse_nice("20").is_err());
+        assert!(parse_nice("-21").is_err());
+        assert!(parse_nice("low").is_err());
+    }
+}
@@ -0,0 +1,317 @@
+// Progress of a session as a snapshot that can be polled at any time, so something other than the
+// terminal output (like a GUI or an IDE extension) can render it without consuming the messages
+// of the coordinator loop.
+use crossbeam_channel::{bounded, RecvTimeoutError, Sender};
+use std::fs::File;
+use std::io::{Result as IOResult, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// how often --progress-file gets rewritten
+const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProofState {
+    Pending,
+    Warmup,
+    Running,
+    Finished,
+}
+
+impl ProofState {
+    fn name(&self) -> &'static str {
+        match self {
+            ProofState::Pending => "pending",
+            ProofState::Warmup => "warmup",
+            ProofState::Running => "running",
+            ProofState::Finished => "finished",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ProofProgress {
+    pub name: String,
+    pub state: ProofState,
+    // measured runs of all variants, not counting warmup runs
+    pub completed_runs: usize,
+    pub failed_runs: usize,
+}
+
+#[derive(Clone)]
+pub struct ProgressSnapshot {
+    // the runs the session is expected to make, which can change as proofs finish early (with
+    // --target-ci-width) or get extra runs (to replace outliers)
+    pub total_runs: usize,
+    pub completed_runs: usize,
+    pub in_flight_runs: usize,
+    pub failed_runs: usize,
+    pub elapsed: Duration,
+    // extrapolated from the average time per completed run so far
+    pub eta: Option<Duration>,
+    pub proofs: Vec<ProofProgress>,
+}
+
+// updated by the coordinator loop, shared as Arc<Progress> with whoever polls it
+pub struct Progress {
+    start_time: Instant,
+    // at most this many runs per proof
+    runs_per_proof: usize,
+    snapshot: Mutex<ProgressSnapshot>,
+}
+
+impl Progress {
+    pub fn new(proof_names: Vec<String>, runs_per_proof: usize) -> Arc<Progress> {
+        Arc::new(Progress {
+            start_time: Instant::now(),
+            runs_per_proof,
+            snapshot: Mutex::new(ProgressSnapshot {
+                total_runs: proof_names.len() * runs_per_proof,
+                completed_runs: 0,
+                in_flight_runs: 0,
+                failed_runs: 0,
+                elapsed: Duration::ZERO,
+                eta: None,
+                proofs: proof_names
+                    .into_iter()
+                    .map(|name| ProofProgress {
+                        name,
+                        state: ProofState::Pending,
+                        completed_runs: 0,
+                        failed_runs: 0,
+                    })
+                    .collect(),
+            }),
+        })
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        let mut snapshot = self
+            .snapshot
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        snapshot.elapsed = self.start_time.elapsed();
+        if snapshot.completed_runs > 0 {
+            let remaining_runs = snapshot.total_runs.saturating_sub(snapshot.completed_runs);
+            snapshot.eta = Some(
+                snapshot
+                    .elapsed
+                    .mul_f64(remaining_runs as f64 / snapshot.completed_runs as f64),
+            );
+        }
+        snapshot
+    }
+
+    fn update(&self, name: &str, update: impl FnOnce(&mut ProgressSnapshot, usize)) {
+        let mut snapshot = self
+            .snapshot
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(index) = snapshot.proofs.iter().position(|proof| proof.name == name) {
+            update(&mut snapshot, index);
+        }
+    }
+
+    pub fn warmup_started(&self, name: &str) {
+        self.update(name, |snapshot, proof| {
+            snapshot.proofs[proof].state = ProofState::Warmup
+        });
+    }
+
+    pub fn run_started(&self, name: &str) {
+        self.update(name, |snapshot, proof| {
+            snapshot.proofs[proof].state = ProofState::Running;
+            snapshot.in_flight_runs += 1;
+        });
+    }
+
+    // a run that will be repeated, which doesn't count
+    pub fn run_discarded(&self, name: &str) {
+        self.update(name, |snapshot, _| {
+            snapshot.in_flight_runs = snapshot.in_flight_runs.saturating_sub(1);
+        });
+    }
+
+    pub fn run_finished(&self, name: &str, failed: bool) {
+        self.update(name, |snapshot, proof| {
+            snapshot.in_flight_runs = snapshot.in_flight_runs.saturating_sub(1);
+            snapshot.completed_runs += 1;
+            snapshot.proofs[proof].completed_runs += 1;
+            if failed {
+                snapshot.failed_runs += 1;
+                snapshot.proofs[proof].failed_runs += 1;
+            }
+            // an extra run to replace an outlier
+            snapshot.total_runs = snapshot
+                .total_runs
+                .max(snapshot.completed_runs + snapshot.in_flight_runs);
+        });
+    }
+
+    // runs that were already finished but are thrown away again, and how many of them failed
+    pub fn runs_dropped(&self, name: &str, runs: usize, failed: usize) {
+        self.update(name, |snapshot, proof| {
+            snapshot.completed_runs = snapshot.completed_runs.saturating_sub(runs);
+            snapshot.proofs[proof].completed_runs =
+                snapshot.proofs[proof].completed_runs.saturating_sub(runs);
+            snapshot.failed_runs = snapshot.failed_runs.saturating_sub(failed);
+            snapshot.proofs[proof].failed_runs =
+                snapshot.proofs[proof].failed_runs.saturating_sub(failed);
+        });
+    }
+
+    pub fn job_finished(&self, name: &str) {
+        let runs_per_proof = self.runs_per_proof;
+        self.update(name, |snapshot, proof| {
+            // the runs it didn't need
+            let unused_runs = runs_per_proof.saturating_sub(snapshot.proofs[proof].completed_runs);
+            snapshot.total_runs = snapshot.total_runs.saturating_sub(unused_runs);
+            snapshot.proofs[proof].state = ProofState::Finished;
+        });
+    }
+}
+
+// text as a JSON string literal
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+impl ProgressSnapshot {
+    pub fn to_json(&self) -> String {
+        let proofs: Vec<String> = self
+            .proofs
+            .iter()
+            .map(|proof| {
+                format!(
+                    "{{\"name\":{},\"state\":\"{}\",\"completed_runs\":{},\"failed_runs\":{}}}",
+                    json_string(&proof.name),
+                    proof.state.name(),
+                    proof.completed_runs,
+                    proof.failed_runs
+                )
+            })
+            .collect();
+        format!(
+            "{{\"total_runs\":{},\"completed_runs\":{},\"in_flight_runs\":{},\"failed_runs\":{},\"elapsed_secs\":{},\"eta_secs\":{},\"proofs\":[{}]}}\n",
+            self.total_runs,
+            self.completed_runs,
+            self.in_flight_runs,
+            self.failed_runs,
+            self.elapsed.as_secs_f64(),
+            self.eta
+                .map_or("null".to_string(), |eta| eta.as_secs_f64().to_string()),
+            proofs.join(",")
+        )
+    }
+}
+
+fn write_snapshot(progress: &Progress, path: &PathBuf) -> IOResult<()> {
+    // readers never see a half written file
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, progress.snapshot().to_json())?;
+    std::fs::rename(&temporary, path)
+}
+
+const TIMELINE_HEADER: &str =
+    "timestamp,elapsed_secs,active_jobs,in_flight_runs,completed_runs,failed_runs";
+
+impl ProgressSnapshot {
+    // a line of the timeline at timestamp (in seconds since the epoch)
+    fn to_timeline_row(&self, timestamp: u64) -> String {
+        let active_jobs = self
+            .proofs
+            .iter()
+            .filter(|proof| matches!(proof.state, ProofState::Warmup | ProofState::Running))
+            .count();
+        format!(
+            "{},{:.3},{},{},{},{}\n",
+            timestamp,
+            self.elapsed.as_secs_f64(),
+            active_jobs,
+            self.in_flight_runs,
+            self.completed_runs,
+            self.failed_runs
+        )
+    }
+}
+
+fn write_timeline_row(progress: &Progress, file: &mut File) -> IOResult<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs());
+    file.write_all(progress.snapshot().to_timeline_row(timestamp).as_bytes())
+}
+
+// writes the snapshot somewhere every interval, and a last time when stopped
+pub struct ProgressWriter {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ProgressWriter {
+    fn every(interval: Duration, mut write: impl FnMut() + Send + 'static) -> ProgressWriter {
+        let (stop, stop_receiver) = bounded::<()>(0);
+        let thread = std::thread::spawn(move || loop {
+            write();
+            match stop_receiver.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                _ => {
+                    write();
+                    return;
+                }
+            }
+        });
+        ProgressWriter {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    // rewrites path with the current snapshot as JSON every second
+    pub fn start(progress: Arc<Progress>, path: PathBuf) -> ProgressWriter {
+        ProgressWriter::every(WRITE_INTERVAL, move || {
+            if let Err(err) = write_snapshot(&progress, &path) {
+                eprintln!("ERROR writing progress to {}: {}", path.display(), err);
+            }
+        })
+    }
+
+    // appends a csv line with the jobs and runs in progress and the completed and failed runs so
+    // far to path every interval
+    pub fn start_timeline(
+        progress: Arc<Progress>,
+        path: PathBuf,
+        interval: Duration,
+    ) -> IOResult<ProgressWriter> {
+        let mut file = File::create(&path)?;
+        writeln!(file, "{}", TIMELINE_HEADER)?;
+        Ok(ProgressWriter::every(interval, move || {
+            if let Err(err) = write_timeline_row(&progress, &mut file) {
+                eprintln!("ERROR writing timeline to {}: {}", path.display(), err);
+            }
+        }))
+    }
+}
+
+impl Drop for ProgressWriter {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
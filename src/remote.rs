@@ -0,0 +1,151 @@
+// Running the proofs on other machines over SSH with --workers, every worker host taking the runs
+// of some of the jobs. The proofs are found at the same path there, either in a shared checkout or
+// copied over with --sync-workers, and the output of every run comes back through ssh.
+use crate::tools::shell_quote;
+use std::fmt;
+use std::io::{Error, Result as IOResult};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+// a host to run proofs on, as ssh takes it, and how many jobs run on it at once
+#[derive(Clone)]
+pub struct Worker {
+    pub host: String,
+    pub slots: u32,
+}
+
+impl FromStr for Worker {
+    type Err = String;
+
+    // HOST[:SLOTS] like build1 or user@build2:4, one job by default
+    fn from_str(arg: &str) -> Result<Worker, String> {
+        let (host, slots) = match arg.rsplit_once(':') {
+            Some((host, slots)) => match slots.parse() {
+                Ok(slots) if slots > 0 => (host, slots),
+                _ => return Err(format!("invalid number of jobs in worker '{}'", arg)),
+            },
+            None => (arg, 1),
+        };
+        if host.is_empty() {
+            return Err(format!("invalid worker '{}', expected HOST[:JOBS]", arg));
+        }
+        Ok(Worker {
+            host: host.to_string(),
+            slots,
+        })
+    }
+}
+
+impl fmt::Display for Worker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.slots)
+    }
+}
+
+fn ssh(host: &str, options: &[&str]) -> Command {
+    let mut ssh = Command::new("ssh");
+    // fail instead of asking for a password nobody is there to type in
+    ssh.args(["-o", "BatchMode=yes"])
+        .args(options)
+        .args([host, "--"]);
+    ssh
+}
+
+// command as a line for a shell to run, in the same directory with the same environment variables
+pub fn shell_command(command: &Command) -> String {
+    let mut words: Vec<String> = Vec::new();
+    if let Some(directory) = command.get_current_dir() {
+        words.push(format!(
+            "cd {} &&",
+            shell_quote(&directory.to_string_lossy())
+        ));
+    }
+    words.push("exec env".to_string());
+    for (variable, value) in command.get_envs() {
+        if let Some(value) = value {
+            words.push(shell_quote(&format!(
+                "{}={}",
+                variable.to_string_lossy(),
+                value.to_string_lossy()
+            )));
+        }
+    }
+    words.push(shell_quote(&command.get_program().to_string_lossy()));
+    words.extend(
+        command
+            .get_args()
+            .map(|argument| shell_quote(&argument.to_string_lossy())),
+    );
+    words.join(" ")
+}
+
+// command run on host instead, by a shell there; the output still has to be set up on the result
+pub fn command(host: &str, command: &Command) -> Command {
+    // on a terminal, so the remote build is hung up on when a killed run ends the connection
+    // instead of carrying on until it next writes output
+    let mut ssh = ssh(host, &["-tt"]);
+    ssh.arg(shell_command(command));
+    ssh
+}
+
+fn check(what: &str, host: &str, command: &mut Command) -> IOResult<()> {
+    let status = command.stdin(Stdio::null()).status()?;
+    if !status.success() {
+        return Err(Error::other(format!(
+            "{} on {} failed with {}",
+            what, host, status
+        )));
+    }
+    Ok(())
+}
+
+// copy the tree to the same path on host, leaving files that only exist there alone
+pub fn sync(host: &str, tree: &Path) -> IOResult<()> {
+    let tree = tree.to_string_lossy();
+    check(
+        "creating the directory",
+        host,
+        ssh(host, &[]).arg(format!("mkdir -p {}", shell_quote(&tree))),
+    )?;
+    check(
+        "rsync",
+        host,
+        Command::new("rsync")
+            .args(["-a", "--protect-args", "-e", "ssh -o BatchMode=yes"])
+            .arg(format!("{}/", tree))
+            .arg(format!("{}:{}/", host, tree)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_workers() {
+        let worker: Worker = "build1".parse().unwrap();
+        assert_eq!((worker.host.as_str(), worker.slots), ("build1", 1));
+        let worker: Worker = "user@build2:4".parse().unwrap();
+        assert_eq!((worker.host.as_str(), worker.slots), ("user@build2", 4));
+        assert_eq!(worker.to_string(), "user@build2:4");
+        assert!("".parse::<Worker>().is_err());
+        assert!(":2".parse::<Worker>().is_err());
+        assert!("build1:0".parse::<Worker>().is_err());
+        assert!("build1:x".parse::<Worker>().is_err());
+    }
+
+    #[test]
+    fn quotes_shell_commands() {
+        let mut command = Command::new("make");
+        command
+            .current_dir("/proofs/it's here")
+            .env("BENCH_RUN_ID", "1")
+            .args(["result", "a b"]);
+        assert_eq!(
+            shell_command(&command),
+            "cd '/proofs/it'\\''s here' && exec env 'BENCH_RUN_ID=1' 'make' 'result' 'a b'"
+        );
+        assert_eq!(shell_command(&Command::new("true")), "exec env 'true'");
+    }
+}
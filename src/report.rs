@@ -0,0 +1,968 @@
+// Summaries printed (and written) at the end of a benchmark session.
+use crate::compare::proof_status;
+use crate::environment::{self, Environment};
+use crate::history;
+use crate::names::NameMap;
+use crate::results::{ProofResults, ResultFile, RunMetadata};
+use crate::stats::{
+    bimodal_modes, bootstrap, geometric_mean, mann_whitney_u, mean, sample_standard_deviation,
+    wilcoxon_signed_rank, Statistic,
+};
+use crate::template::{self, ReportTemplate};
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::io::Result as IOResult;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+// who the reports at the end of a session are for, which decides the sections they consist of
+#[derive(Clone, Copy)]
+pub enum Audience {
+    // all the per-proof tables
+    Developer,
+    // only what needs acting on: failures, anomalies and warnings
+    Ci,
+    // top-level summaries and the trend of the suite score
+    Manager,
+}
+
+impl FromStr for Audience {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Audience, String> {
+        match arg {
+            "developer" => Ok(Audience::Developer),
+            "ci" => Ok(Audience::Ci),
+            "manager" => Ok(Audience::Manager),
+            _ => Err(format!(
+                "unknown audience '{}', expected developer, ci or manager",
+                arg
+            )),
+        }
+    }
+}
+
+impl Audience {
+    // the per-proof tables like the slowest proofs
+    pub fn wants_tables(&self) -> bool {
+        matches!(self, Audience::Developer)
+    }
+
+    // anomalies and warnings about the measurements
+    pub fn wants_alerts(&self) -> bool {
+        matches!(self, Audience::Developer | Audience::Ci)
+    }
+
+    // a one line summary of the whole session
+    pub fn wants_summary(&self) -> bool {
+        matches!(self, Audience::Ci | Audience::Manager)
+    }
+
+    // totals per group of proofs
+    pub fn wants_groups(&self) -> bool {
+        matches!(self, Audience::Developer | Audience::Manager)
+    }
+
+    // the suite score across the stored sessions
+    pub fn wants_trend(&self) -> bool {
+        matches!(self, Audience::Manager)
+    }
+}
+
+// options for the reports at the end of a session
+pub struct ReportOptions {
+    // results of a previous session to relate this one to
+    pub baseline: Option<ResultFile>,
+    // where to write the slowest proofs report to, in addition to printing it
+    pub slowest_report: Option<PathBuf>,
+    // how to summarize the runtimes of each proof
+    pub statistic: Statistic,
+    // only show this many proofs in the terminal summary
+    pub top: Option<usize>,
+    // where to write the per-proof speedup report against the baseline to
+    pub speedup_report: Option<PathBuf>,
+    // number of resamples for the bootstrap estimates, no bootstrap report if None
+    pub bootstrap_samples: Option<usize>,
+    // don't write the report files if nothing changed significantly against the baseline
+    pub report_on_change_only: bool,
+    // the sessions stored in the history directory before this one, oldest first
+    pub history: Vec<(String, ResultFile)>,
+    pub audience: Audience,
+    // display names and groups of the proofs, which they already have by the time they are
+    // reported
+    pub names: NameMap,
+    // user-provided reports, written together with the other report files
+    pub templates: Vec<ReportTemplate>,
+}
+
+// p-value below which a runtime difference against the baseline counts as a change, the same as
+// the compare default
+const CHANGE_SIGNIFICANCE: f64 = 0.05;
+
+// whether any proof was added, removed, newly failed, got fixed or changed its runtime
+// significantly against the baseline
+fn changed_against_baseline(
+    proofs: &[ProofResults],
+    baseline: &[ProofResults],
+    statistic: Statistic,
+) -> bool {
+    let baseline: HashMap<&str, &ProofResults> = baseline
+        .iter()
+        .map(|proof| (proof.name.as_str(), proof))
+        .collect();
+    let removed = baseline
+        .keys()
+        .any(|name| !proofs.iter().any(|proof| proof.name == *name));
+    removed
+        || proofs.iter().any(|proof| {
+            let old = baseline.get(proof.name.as_str()).copied();
+            let p_value =
+                old.and_then(|old| mann_whitney_u(&old.runtimes_secs(), &proof.runtimes_secs()));
+            !proof_status(old, Some(proof), p_value, CHANGE_SIGNIFICANCE, statistic).is_empty()
+        })
+}
+
+// whether the report files should be written at all, see ReportOptions::report_on_change_only
+pub fn should_write_reports(proofs: &[ProofResults], options: &ReportOptions) -> bool {
+    match &options.baseline {
+        Some(baseline)
+            if options.report_on_change_only
+                && !changed_against_baseline(proofs, &baseline.proofs, options.statistic) =>
+        {
+            println!("NO SIGNIFICANT CHANGES against the baseline, not writing reports");
+            false
+        }
+        _ => true,
+    }
+}
+
+// geometric mean of the runtimes of all proofs with at least one successful run
+pub fn suite_score(proofs: &[ProofResults], statistic: Statistic) -> Option<f64> {
+    let runtimes: Vec<f64> = proofs
+        .iter()
+        .filter_map(|proof| proof.runtime(statistic))
+        .collect();
+    geometric_mean(&runtimes)
+}
+
+// geometric mean of the per-proof runtime ratios against the baseline, for all proofs that have
+// successful runs in both. Below 1 means faster than the baseline.
+pub fn relative_suite_score(
+    proofs: &[ProofResults],
+    baseline: &[ProofResults],
+    statistic: Statistic,
+) -> Option<f64> {
+    let baseline_runtimes: HashMap<&str, f64> = baseline
+        .iter()
+        .filter_map(|proof| {
+            proof
+                .runtime(statistic)
+                .map(|runtime| (proof.name.as_str(), runtime))
+        })
+        .collect();
+    let ratios: Vec<f64> = proofs
+        .iter()
+        .filter_map(|proof| {
+            let baseline_runtime = baseline_runtimes.get(proof.name.as_str())?;
+            Some(proof.runtime(statistic)? / baseline_runtime)
+        })
+        .collect();
+    geometric_mean(&ratios)
+}
+
+// table of the energy the CPU packages used per run of every proof, where RAPL is available,
+// most energy first
+pub fn report_energy(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_tables() {
+        return;
+    }
+    let mut energies: Vec<(&ProofResults, f64)> = proofs
+        .iter()
+        .filter_map(|proof| Some((proof, proof.energy(options.statistic)?)))
+        .collect();
+    if energies.is_empty() {
+        return;
+    }
+    energies.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("energies are never NaN"));
+    let name_width = energies
+        .iter()
+        .map(|(proof, _)| proof.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!("ENERGY (CPU packages of the whole machine)");
+    println!(
+        "{:<name_width$} {:>10} {:>10}",
+        "PROOF",
+        format!("{}(J)", options.statistic.to_string().to_uppercase()),
+        "TOTAL(J)",
+        name_width = name_width
+    );
+    for (proof, energy) in energies.iter() {
+        let total: f64 = proof.energies.iter().filter_map(|energy| *energy).sum();
+        println!(
+            "{:<name_width$} {:>10.1} {:>10.1}",
+            proof.name,
+            energy,
+            total,
+            name_width = name_width
+        );
+    }
+}
+
+// table of how long building, the measured target, the goto and cbmc tools (with --tool-times) and
+// every --phase took for each proof, to see which of them dominates
+pub fn report_phases(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_tables() {
+        return;
+    }
+    let mut phases: Vec<&str> = Vec::new();
+    for proof in proofs.iter() {
+        for (phase, _) in proof.phase_times.iter() {
+            if !phases.contains(&phase.as_str()) {
+                phases.push(phase);
+            }
+        }
+    }
+    let has_tool_times = proofs.iter().any(|proof| {
+        proof
+            .goto_times
+            .iter()
+            .chain(proof.cbmc_times.iter())
+            .any(Option::is_some)
+    });
+    if phases.is_empty() && !has_tool_times {
+        return;
+    }
+    let statistic = options.statistic;
+    let mut headers = vec!["BUILD(s)".to_string(), "RUN(s)".to_string()];
+    if has_tool_times {
+        headers.push("GOTO-CC(s)".to_string());
+        headers.push("CBMC(s)".to_string());
+    }
+    headers.extend(
+        phases
+            .iter()
+            .map(|phase| format!("{}(s)", phase.to_uppercase())),
+    );
+    let widths: Vec<usize> = headers
+        .iter()
+        .map(|header| (header.len() + 1).max(10))
+        .collect();
+    let name_width = proofs
+        .iter()
+        .map(|proof| proof.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!("PHASES ({} times)", statistic);
+    let mut header = format!("{:<name_width$}", "PROOF", name_width = name_width);
+    for (column, width) in headers.iter().zip(widths.iter()) {
+        header += &format!(" {:>width$}", column, width = width);
+    }
+    println!("{}", header);
+    for proof in proofs.iter() {
+        let mut times = vec![proof.build_time(statistic), proof.runtime(statistic)];
+        if has_tool_times {
+            times.push(proof.goto_time(statistic));
+            times.push(proof.cbmc_time(statistic));
+        }
+        times.extend(
+            phases
+                .iter()
+                .map(|phase| proof.phase_time(phase, statistic)),
+        );
+        let mut line = format!("{:<name_width$}", proof.name, name_width = name_width);
+        for (time, width) in times.iter().zip(widths.iter()) {
+            line += &format!(
+                " {:>width$}",
+                time.map_or("-".to_string(), |time| format!("{:.3}", time)),
+                width = width
+            );
+        }
+        println!("{}", line);
+    }
+}
+
+// the fixed cost of spawning make for every measured run, which adds up over thousands of short
+// runs
+pub fn report_spawn_overhead(
+    proofs: &[ProofResults],
+    spawn_times: &[Duration],
+    options: &ReportOptions,
+) {
+    if !options.audience.wants_tables() || spawn_times.is_empty() {
+        return;
+    }
+    let spawn_secs: Vec<f64> = spawn_times.iter().map(Duration::as_secs_f64).collect();
+    let total: f64 = spawn_secs.iter().sum();
+    let total_runtime: f64 = proofs.iter().flat_map(|proof| proof.runtimes_secs()).sum();
+    println!(
+        "SPAWN OVERHEAD {:.3}ms per run on average (at most {:.3}ms), {:.3}s over {} runs{}",
+        1000.0 * mean(&spawn_secs),
+        1000.0 * spawn_secs.iter().cloned().fold(0.0, f64::max),
+        total,
+        spawn_times.len(),
+        if total_runtime > 0.0 {
+            format!(
+                " ({:.2}% of the total runtime)",
+                100.0 * total / total_runtime
+            )
+        } else {
+            String::new()
+        }
+    );
+}
+
+// the number of proofs and how many of them failed, for audiences that don't get the tables
+pub fn report_summary(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_summary() {
+        return;
+    }
+    let failing = proofs
+        .iter()
+        .filter(|proof| proof.failed_runs() > 0)
+        .count();
+    println!(
+        "SUMMARY {} proofs, {} of them with failed runs",
+        proofs.len(),
+        failing
+    );
+}
+
+// the number of proofs, failing proofs and summarized runtimes of every group of proofs in the
+// name mapping, biggest total first
+pub fn report_groups(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_groups() || !options.names.has_groups() {
+        return;
+    }
+    // proofs, failing proofs and total runtime
+    let mut groups: HashMap<&str, (usize, usize, f64)> = HashMap::new();
+    for proof in proofs.iter() {
+        let group = groups
+            .entry(options.names.group(&proof.name).unwrap_or("-"))
+            .or_default();
+        group.0 += 1;
+        if proof.failed_runs() > 0 {
+            group.1 += 1;
+        }
+        group.2 += proof.runtime(options.statistic).unwrap_or(0.0);
+    }
+    let mut groups: Vec<(&str, (usize, usize, f64))> = groups.into_iter().collect();
+    groups.sort_by(|a, b| {
+        (b.1)
+            .2
+            .partial_cmp(&(a.1).2)
+            .expect("runtimes are never NaN")
+            .then(a.0.cmp(b.0))
+    });
+    let total: f64 = groups.iter().map(|(_, (_, _, runtime))| runtime).sum();
+    let name_width = groups
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!("GROUPS");
+    println!(
+        "{:<name_width$} {:>6} {:>7} {:>10} {:>8}",
+        "GROUP",
+        "PROOFS",
+        "FAILING",
+        format!("{}(s)", options.statistic.to_string().to_uppercase()),
+        "SHARE%",
+        name_width = name_width
+    );
+    for (name, (count, failing, runtime)) in groups.iter() {
+        println!(
+            "{:<name_width$} {:>6} {:>7} {:>10.3} {:>8.1}",
+            name,
+            count,
+            failing,
+            runtime,
+            if total > 0.0 {
+                100.0 * runtime / total
+            } else {
+                0.0
+            },
+            name_width = name_width
+        );
+    }
+}
+
+// the suite score of the stored sessions and this one as a chart of one character per session
+pub fn report_suite_score_trend(score: Option<f64>, options: &ReportOptions) {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const TREND_SESSIONS: usize = 20;
+    if !options.audience.wants_trend() {
+        return;
+    }
+    let mut scores: Vec<f64> = options
+        .history
+        .iter()
+        .filter_map(|(_, session)| session.metadata.get("suite_score")?.parse().ok())
+        .collect();
+    scores.extend(score);
+    let skip = scores.len().saturating_sub(TREND_SESSIONS);
+    scores.drain(..skip);
+    if scores.len() < 2 {
+        return;
+    }
+    let min = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let chart: String = scores
+        .iter()
+        .map(|score| {
+            let level = if max > min {
+                (score - min) / (max - min)
+            } else {
+                0.5
+            };
+            BARS[(level * (BARS.len() - 1) as f64).round() as usize]
+        })
+        .collect();
+    let (first, last) = (scores[0], scores[scores.len() - 1]);
+    println!(
+        "SUITE SCORE TREND over the last {} sessions (higher is slower)",
+        scores.len()
+    );
+    println!(
+        "  {} {:.3}s -> {:.3}s ({:+.1}%)",
+        chart,
+        first,
+        last,
+        100.0 * (last - first) / first
+    );
+}
+
+// print the suite score(s) and return them as metadata to be recorded with the results
+pub fn report_suite_score(
+    proofs: &[ProofResults],
+    options: &ReportOptions,
+) -> Vec<(String, String)> {
+    let mut scores = Vec::new();
+    if let Some(score) = suite_score(proofs, options.statistic) {
+        println!(
+            "SUITE SCORE (geometric mean of {} runtimes) {}s",
+            options.statistic, score
+        );
+        scores.push(("suite_score".to_string(), score.to_string()));
+    }
+    if let Some(baseline) = &options.baseline {
+        if let Some(score) = relative_suite_score(proofs, &baseline.proofs, options.statistic) {
+            println!(
+                "SUITE SCORE relative to baseline {} ({})",
+                score,
+                if score <= 1.0 { "faster" } else { "slower" }
+            );
+            scores.push(("suite_score_vs_baseline".to_string(), score.to_string()));
+        }
+    }
+    scores
+}
+
+// table of all proofs with successful runs sorted by runtime, slowest first, together with
+// their (cumulative) share of the total runtime, limited to the first limit rows
+pub fn slowest_proofs_report(
+    proofs: &[ProofResults],
+    statistic: Statistic,
+    limit: Option<usize>,
+) -> String {
+    let mut runtimes: Vec<(&ProofResults, f64)> = proofs
+        .iter()
+        .filter_map(|proof| proof.runtime(statistic).map(|runtime| (proof, runtime)))
+        .collect();
+    runtimes.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("runtimes are never NaN"));
+    let total: f64 = runtimes.iter().map(|(_, runtime)| runtime).sum();
+    let name_width = runtimes
+        .iter()
+        .map(|(proof, _)| proof.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let mut report = String::new();
+    writeln!(
+        report,
+        "{:>4} {:<name_width$} {:>10} {:>10} {:>10} {:>8} {:>8} {:>9} {:>10}  TAGS",
+        "RANK",
+        "PROOF",
+        format!("{}(s)", statistic.to_string().to_uppercase()),
+        "BUILD(s)",
+        "SOLVER(s)",
+        "SHARE%",
+        "CUMUL%",
+        "RSS(MiB)",
+        "DISK(MiB)",
+        name_width = name_width
+    )
+    .expect("writing to a String can't fail");
+    let mut cumulative = 0.0;
+    for (rank, (proof, runtime)) in runtimes
+        .iter()
+        .enumerate()
+        .take(limit.unwrap_or(usize::MAX))
+    {
+        cumulative += runtime;
+        writeln!(
+            report,
+            "{:>4} {:<name_width$} {:>10.3} {:>10} {:>10} {:>8.1} {:>8.1} {:>9} {:>10}  {}",
+            rank + 1,
+            proof.name,
+            runtime,
+            proof
+                .build_time(statistic)
+                .map_or("-".to_string(), |build_time| format!("{:.3}", build_time)),
+            proof
+                .solver_time(statistic)
+                .map_or("-".to_string(), |solver_time| format!("{:.3}", solver_time)),
+            100.0 * runtime / total,
+            100.0 * cumulative / total,
+            proof
+                .max_rss_kib
+                .map_or("-".to_string(), |rss| format!("{:.1}", rss as f64 / 1024.0)),
+            proof
+                .max_artifact_bytes
+                .map_or("-".to_string(), |bytes| format!(
+                    "{:.1}",
+                    bytes as f64 / (1 << 20) as f64
+                )),
+            proof.tags.join(" "),
+            name_width = name_width
+        )
+        .expect("writing to a String can't fail");
+    }
+    report
+}
+
+// table of the limit proofs with the highest coefficient of variation of their runtimes
+pub fn most_variable_proofs_report(proofs: &[ProofResults], limit: usize) -> String {
+    let mut variations: Vec<(&str, f64, f64)> = proofs
+        .iter()
+        .filter_map(|proof| {
+            let runtimes = proof.runtimes_secs();
+            if runtimes.len() < 2 {
+                return None;
+            }
+            let standard_deviation = sample_standard_deviation(&runtimes);
+            Some((
+                proof.name.as_str(),
+                standard_deviation,
+                standard_deviation / mean(&runtimes),
+            ))
+        })
+        .collect();
+    variations.sort_by(|a, b| b.2.partial_cmp(&a.2).expect("runtimes are never NaN"));
+    variations.truncate(limit);
+    let name_width = variations
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let mut report = String::new();
+    writeln!(
+        report,
+        "{:>4} {:<name_width$} {:>10} {:>8}",
+        "RANK",
+        "PROOF",
+        "STDDEV(s)",
+        "CV%",
+        name_width = name_width
+    )
+    .expect("writing to a String can't fail");
+    for (rank, (name, standard_deviation, variation)) in variations.iter().enumerate() {
+        writeln!(
+            report,
+            "{:>4} {:<name_width$} {:>10.3} {:>8.1}",
+            rank + 1,
+            name,
+            standard_deviation,
+            100.0 * variation,
+            name_width = name_width
+        )
+        .expect("writing to a String can't fail");
+    }
+    report
+}
+
+pub fn report_slowest_proofs(
+    proofs: &[ProofResults],
+    options: &ReportOptions,
+    write_report: bool,
+) -> IOResult<()> {
+    if options.audience.wants_tables() {
+        println!("SLOWEST PROOFS");
+        print!(
+            "{}",
+            slowest_proofs_report(proofs, options.statistic, options.top)
+        );
+        if let Some(top) = options.top {
+            println!("MOST VARIABLE PROOFS");
+            print!("{}", most_variable_proofs_report(proofs, top));
+        }
+    }
+    if let (Some(path), true) = (&options.slowest_report, write_report) {
+        std::fs::write(path, slowest_proofs_report(proofs, options.statistic, None))?;
+    }
+    Ok(())
+}
+
+// fill in the --report-template templates with the sections of the report and the metadata of the
+// session (including the suite scores) and write them
+pub fn write_templated_reports(
+    proofs: &[ProofResults],
+    metadata: &RunMetadata,
+    scores: &[(String, String)],
+    options: &ReportOptions,
+    write_report: bool,
+) -> IOResult<()> {
+    if options.templates.is_empty() || !write_report {
+        return Ok(());
+    }
+    let mut values: HashMap<String, String> = HashMap::new();
+    let mut value = |name: &str, value: String| values.insert(name.to_string(), value);
+    value("note", metadata.note.clone().unwrap_or_default());
+    value("statistic", options.statistic.to_string());
+    value("proofs", proofs.len().to_string());
+    value(
+        "failing_proofs",
+        proofs
+            .iter()
+            .filter(|proof| proof.failed_runs() > 0)
+            .count()
+            .to_string(),
+    );
+    value(
+        "suite_score",
+        suite_score(proofs, options.statistic).map_or("-".to_string(), |score| score.to_string()),
+    );
+    value(
+        "slowest_proofs",
+        slowest_proofs_report(proofs, options.statistic, options.top),
+    );
+    value(
+        "most_variable_proofs",
+        most_variable_proofs_report(proofs, options.top.unwrap_or(proofs.len())),
+    );
+    value(
+        "speedup",
+        options.baseline.as_ref().map_or(String::new(), |baseline| {
+            speedup_report(proofs, &baseline.proofs, options.statistic)
+        }),
+    );
+    values.extend(template::meta_values(&metadata.meta));
+    values.extend(template::meta_values(scores));
+    for report_template in options.templates.iter() {
+        std::fs::write(&report_template.output, report_template.render(&values))?;
+        println!("WROTE report to {}", report_template.output.display());
+    }
+    Ok(())
+}
+
+// table of the speedup of each proof against the baseline (baseline runtime / new runtime),
+// biggest speedup first
+pub fn speedup_report(
+    proofs: &[ProofResults],
+    baseline: &[ProofResults],
+    statistic: Statistic,
+) -> String {
+    let baseline_runtimes: HashMap<&str, f64> = baseline
+        .iter()
+        .filter_map(|proof| {
+            proof
+                .runtime(statistic)
+                .map(|runtime| (proof.name.as_str(), runtime))
+        })
+        .collect();
+    let mut speedups: Vec<(&str, f64, f64, f64)> = proofs
+        .iter()
+        .filter_map(|proof| {
+            let baseline_runtime = *baseline_runtimes.get(proof.name.as_str())?;
+            let runtime = proof.runtime(statistic)?;
+            Some((
+                proof.name.as_str(),
+                baseline_runtime,
+                runtime,
+                baseline_runtime / runtime,
+            ))
+        })
+        .collect();
+    speedups.sort_by(|a, b| b.3.partial_cmp(&a.3).expect("runtimes are never NaN"));
+    let name_width = speedups
+        .iter()
+        .map(|(name, _, _, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let mut report = String::new();
+    writeln!(
+        report,
+        "{:<name_width$} {:>12} {:>10}  SPEEDUP",
+        "PROOF",
+        "BASELINE(s)",
+        "NEW(s)",
+        name_width = name_width
+    )
+    .expect("writing to a String can't fail");
+    for (name, baseline_runtime, runtime, speedup) in speedups.iter() {
+        let speedup = if *speedup >= 1.0 {
+            format!("{:.2}x faster", speedup)
+        } else {
+            format!("{:.2}x slower", 1.0 / speedup)
+        };
+        writeln!(
+            report,
+            "{:<name_width$} {:>12.3} {:>10.3}  {}",
+            name,
+            baseline_runtime,
+            runtime,
+            speedup,
+            name_width = name_width
+        )
+        .expect("writing to a String can't fail");
+    }
+    report
+}
+
+pub fn write_speedup_report(
+    proofs: &[ProofResults],
+    options: &ReportOptions,
+    write_report: bool,
+) -> IOResult<()> {
+    if let (Some(path), Some(baseline), true) =
+        (&options.speedup_report, &options.baseline, write_report)
+    {
+        std::fs::write(
+            path,
+            speedup_report(proofs, &baseline.proofs, options.statistic),
+        )?;
+        println!("WROTE speedup report to {}", path.display());
+    }
+    Ok(())
+}
+
+// paired comparison of the two variants of each proof in A/B mode: the runs of an iteration
+// happened right after each other, so we compare them pairwise instead of as two independent
+// samples
+pub fn report_ab(proofs: &[Vec<ProofResults>], targets: &[String]) {
+    println!("A/B COMPARISON (A = {}, B = {})", targets[0], targets[1]);
+    let name_width = proofs
+        .iter()
+        .map(|variants| variants[0].name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:<name_width$} {:>5} {:>10} {:>10} {:>8} {:>8}",
+        "PROOF",
+        "PAIRS",
+        "A(s)",
+        "B(s)",
+        "B/A",
+        "P",
+        name_width = name_width
+    );
+    let a_suffix = format!("@{}", targets[0]);
+    for variants in proofs.iter() {
+        let (a, b) = (&variants[0], &variants[1]);
+        let name = a.name.trim_end_matches(a_suffix.as_str());
+        let pairs: Vec<(f64, f64)> = a
+            .runs
+            .iter()
+            .zip(b.runs.iter())
+            .enumerate()
+            .filter(|(run_index, _)| {
+                !a.outlier_runs.contains(run_index) && !b.outlier_runs.contains(run_index)
+            })
+            .filter_map(|(_, (a_run, b_run))| {
+                Some(((*a_run)?.as_secs_f64(), (*b_run)?.as_secs_f64()))
+            })
+            .collect();
+        if pairs.is_empty() {
+            println!("{:<name_width$} {:>5}", name, 0, name_width = name_width);
+            continue;
+        }
+        let a_runtimes: Vec<f64> = pairs.iter().map(|(a, _)| *a).collect();
+        let b_runtimes: Vec<f64> = pairs.iter().map(|(_, b)| *b).collect();
+        let ratios: Vec<f64> = pairs.iter().map(|(a, b)| b / a).collect();
+        let differences: Vec<f64> = pairs.iter().map(|(a, b)| b - a).collect();
+        println!(
+            "{:<name_width$} {:>5} {:>10.3} {:>10.3} {:>8} {:>8}",
+            name,
+            pairs.len(),
+            mean(&a_runtimes),
+            mean(&b_runtimes),
+            geometric_mean(&ratios).map_or("-".to_string(), |ratio| format!("{:.3}", ratio)),
+            wilcoxon_signed_rank(&differences).map_or("-".to_string(), |p| format!("{:.3}", p)),
+            name_width = name_width
+        );
+    }
+}
+
+// bootstrap estimates of the mean and median runtime of every proof with their 95% confidence
+// intervals, which unlike the t-based intervals don't assume normally distributed runtimes
+pub fn report_bootstrap(proofs: &[ProofResults], options: &ReportOptions) {
+    let resamples = match options.bootstrap_samples {
+        Some(resamples) if options.audience.wants_tables() => resamples,
+        _ => return,
+    };
+    let estimates: Vec<_> = proofs
+        .iter()
+        .filter_map(|proof| {
+            let runtimes = proof.runtimes_secs();
+            Some((
+                proof.name.as_str(),
+                bootstrap(&runtimes, Statistic::Mean, resamples)?,
+                bootstrap(&runtimes, Statistic::Median, resamples)?,
+            ))
+        })
+        .collect();
+    if estimates.is_empty() {
+        return;
+    }
+    println!(
+        "BOOTSTRAP ESTIMATES ({} resamples, 95% confidence intervals)",
+        resamples
+    );
+    let name_width = estimates
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:<name_width$} {:>10} {:>21} {:>10} {:>21}",
+        "PROOF",
+        "MEAN(s)",
+        "MEAN CI(s)",
+        "MEDIAN(s)",
+        "MEDIAN CI(s)",
+        name_width = name_width
+    );
+    for (name, mean, median) in estimates.iter() {
+        println!(
+            "{:<name_width$} {:>10.3} {:>21} {:>10.3} {:>21}",
+            name,
+            mean.value,
+            format!("[{:.3}, {:.3}]", mean.lower, mean.upper),
+            median.value,
+            format!("[{:.3}, {:.3}]", median.lower, median.upper),
+            name_width = name_width
+        );
+    }
+}
+
+// proofs whose runtime in this session is unusual given their history, even without a baseline
+pub fn report_anomalies(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_alerts() {
+        return;
+    }
+    history::print_anomalies(&history::anomalies(
+        &options.history,
+        proofs,
+        options.statistic,
+    ));
+}
+
+// proofs whose runs didn't all produce the same property results
+pub fn report_nondeterministic_results(proofs: &[ProofResults], options: &ReportOptions) {
+    if !options.audience.wants_alerts() {
+        return;
+    }
+    let nondeterministic: Vec<(&str, usize)> = proofs
+        .iter()
+        .map(|proof| (proof.name.as_str(), proof.distinct_property_results()))
+        .filter(|(_, distinct)| *distinct > 1)
+        .collect();
+    if nondeterministic.is_empty() {
+        return;
+    }
+    println!("NONDETERMINISTIC RESULTS");
+    for (name, distinct) in nondeterministic.iter() {
+        println!("  {}: {} different property results", name, distinct);
+    }
+}
+
+// runs during which the machine swapped, for every audience since they make the runtimes of those
+// runs worthless
+pub fn report_swapped_runs(proofs: &[ProofResults]) {
+    let swapped: Vec<&ProofResults> = proofs
+        .iter()
+        .filter(|proof| !proof.swapped_runs.is_empty())
+        .collect();
+    if swapped.is_empty() {
+        return;
+    }
+    println!(
+        "WARNING the machine swapped during {} runs, their runtimes are worthless (consider fewer --parallel-jobs or a --memory-limit)",
+        swapped
+            .iter()
+            .map(|proof| proof.swapped_runs.len())
+            .sum::<usize>()
+    );
+    for proof in swapped.iter() {
+        let run_nrs: Vec<String> = proof
+            .swapped_runs
+            .iter()
+            .map(|run_index| (run_index + 1).to_string())
+            .collect();
+        println!("  {}: runs {}", proof.name, run_nrs.join(", "));
+    }
+}
+
+// warn if the machine changed significantly over the course of the session
+pub fn report_environment_drift(samples: &[(&str, Environment)], options: &ReportOptions) {
+    if !options.audience.wants_alerts() {
+        return;
+    }
+    let drifted = environment::drift(samples);
+    if drifted.is_empty() {
+        return;
+    }
+    println!("WARNING the environment drifted during the session, the early and late proofs weren't measured under equal conditions");
+    for fact in drifted.iter() {
+        println!("  {}", fact);
+    }
+}
+
+// list proofs whose runtimes fall into two clearly separated modes, which usually points at
+// nondeterministic solver behaviour rather than measurement noise
+pub fn report_bimodal_proofs(proofs: &[ProofResults]) {
+    let bimodal: Vec<(&str, crate::stats::Modes)> = proofs
+        .iter()
+        .filter_map(|proof| Some((proof.name.as_str(), bimodal_modes(&proof.runtimes_secs())?)))
+        .collect();
+    if bimodal.is_empty() {
+        return;
+    }
+    println!("BIMODAL PROOFS (likely nondeterministic solver behaviour)");
+    let name_width = bimodal
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    println!(
+        "{:<name_width$} {:>10} {:>5} {:>10} {:>5} {:>10}",
+        "PROOF",
+        "LOW(s)",
+        "RUNS",
+        "HIGH(s)",
+        "RUNS",
+        "SEPARATION",
+        name_width = name_width
+    );
+    for (name, modes) in bimodal.iter() {
+        println!(
+            "{:<name_width$} {:>10.3} {:>5} {:>10.3} {:>5} {:>10.1}",
+            name,
+            modes.low_mean,
+            modes.low_count,
+            modes.high_mean,
+            modes.high_count,
+            modes.separation,
+            name_width = name_width
+        );
+    }
+}
@@ -0,0 +1,173 @@
+// The hardware a proof needs, declared in its Makefile like BENCHMARK_REQUIRES = memory>=16G
+// cores>=32 avx2, matched against the capability tags of the machine, so proofs that can't run
+// properly here are reported instead of scheduled.
+use crate::artifacts;
+use crate::system;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+// the Makefile variable proofs declare their requirements in
+pub const REQUIRES_VARIABLE: &str = "BENCHMARK_REQUIRES";
+
+pub enum Requirement {
+    // at least this many bytes of memory
+    Memory(u64),
+    // at least this many CPUs available to us
+    Cores(usize),
+    // a CPU flag like avx2 or a tag given with --machine-tag
+    Tag(String),
+}
+
+impl FromStr for Requirement {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Requirement, String> {
+        if let Some(memory) = arg.strip_prefix("memory>=") {
+            return artifacts::parse_size(memory).map(Requirement::Memory);
+        }
+        if let Some(cores) = arg.strip_prefix("cores>=") {
+            return cores
+                .parse()
+                .map(Requirement::Cores)
+                .map_err(|err| format!("invalid core count in '{}': {}", arg, err));
+        }
+        if !arg.is_empty()
+            && arg
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Ok(Requirement::Tag(arg.to_string()));
+        }
+        Err(format!(
+            "invalid requirement '{}', expected memory>=<size>, cores>=<count> or a tag",
+            arg
+        ))
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // in the largest unit that keeps it exact, like parse_size takes it
+            Requirement::Memory(bytes) => match [(30, "G"), (20, "M"), (10, "K")]
+                .iter()
+                .find(|(shift, _)| *bytes > 0 && bytes % (1 << shift) == 0)
+            {
+                Some((shift, unit)) => write!(f, "memory>={}{}", bytes >> shift, unit),
+                None => write!(f, "memory>={}", bytes),
+            },
+            Requirement::Cores(cores) => write!(f, "cores>={}", cores),
+            Requirement::Tag(tag) => write!(f, "{}", tag),
+        }
+    }
+}
+
+// the requirements in the value of BENCHMARK_REQUIRES, separated by whitespace
+pub fn parse(value: &str) -> Result<Vec<Requirement>, String> {
+    value.split_whitespace().map(str::parse).collect()
+}
+
+// what the machine we run on offers
+pub struct Capabilities {
+    memory_bytes: Option<u64>,
+    cores: usize,
+    // the CPU flags and the tags given with --machine-tag
+    tags: BTreeSet<String>,
+}
+
+fn cpu_flags() -> Vec<String> {
+    std::fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|cpuinfo| {
+            cpuinfo.lines().find_map(|line| {
+                let (key, flags) = line.split_once(':')?;
+                Some(flags.split_whitespace().map(String::from).collect())
+                    .filter(|_| key.trim() == "flags")
+            })
+        })
+        .unwrap_or_default()
+}
+
+impl Capabilities {
+    pub fn detect(machine_tags: &[String]) -> Capabilities {
+        Capabilities {
+            memory_bytes: system::memory_kib()
+                .and_then(|kib| kib.parse::<u64>().ok().map(|kib| kib * 1024)),
+            cores: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            tags: cpu_flags()
+                .into_iter()
+                .chain(machine_tags.iter().cloned())
+                .collect(),
+        }
+    }
+
+    fn meets(&self, requirement: &Requirement) -> bool {
+        match requirement {
+            // without /proc/meminfo we can't tell, so we don't hold the proof back
+            Requirement::Memory(bytes) => self.memory_bytes.is_none_or(|memory| memory >= *bytes),
+            Requirement::Cores(cores) => self.cores >= *cores,
+            Requirement::Tag(tag) => self.tags.contains(tag),
+        }
+    }
+
+    // the requirements this machine doesn't meet
+    pub fn unmet<'a>(&self, requirements: &'a [Requirement]) -> Vec<&'a Requirement> {
+        requirements
+            .iter()
+            .filter(|requirement| !self.meets(requirement))
+            .collect()
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} cores", self.cores)?;
+        if let Some(bytes) = self.memory_bytes {
+            write!(f, ", {}MiB memory", bytes >> 20)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requirements() {
+        let requirements =
+            parse("  memory>=16G cores>=32\tavx2 memory>=1536M memory>=1000 ").unwrap();
+        let shown: Vec<String> = requirements.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            shown,
+            vec![
+                "memory>=16G",
+                "cores>=32",
+                "avx2",
+                "memory>=1536M",
+                "memory>=1000"
+            ]
+        );
+        assert!(parse("").unwrap().is_empty());
+        assert!(parse("memory>=lots").is_err());
+        assert!(parse("cores>=-1").is_err());
+        assert!(parse("avx2 gpu!").is_err());
+    }
+
+    #[test]
+    fn finds_unmet_requirements() {
+        let capabilities = Capabilities {
+            memory_bytes: Some(8 << 30),
+            cores: 4,
+            tags: std::iter::once("avx2".to_string()).collect(),
+        };
+        let requirements = parse("memory>=8G cores>=8 avx2 avx512f memory>=16G").unwrap();
+        let unmet: Vec<String> = capabilities
+            .unmet(&requirements)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(unmet, vec!["cores>=8", "avx512f", "memory>=16G"]);
+    }
+}
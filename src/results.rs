@@ -0,0 +1,1129 @@
+// Reading and writing of the per-proof result files.
+use crate::compression;
+use crate::monitor::RunStats;
+use crate::stats::Statistic;
+use crate::GenericResult;
+use std::fmt;
+use std::fs::File;
+use std::io::{Result as IOResult, Write};
+use std::path::Path;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+// how the measured command of a run ended
+#[derive(Clone, Copy, PartialEq)]
+pub enum Exit {
+    Code(i32),
+    Signal(i32),
+    // killed by us for exceeding --memory-limit
+    MemoryLimit,
+    // killed by us for exceeding --timeout
+    Timeout,
+}
+
+impl Exit {
+    pub fn of(status: ExitStatus) -> Option<Exit> {
+        use std::os::unix::process::ExitStatusExt;
+        status
+            .code()
+            .map(Exit::Code)
+            .or_else(|| status.signal().map(Exit::Signal))
+    }
+
+    fn parse(field: &str) -> Option<Exit> {
+        if field == "memory-limit" {
+            Some(Exit::MemoryLimit)
+        } else if field == "timeout" {
+            Some(Exit::Timeout)
+        } else if let Some(code) = field.strip_prefix("exit=") {
+            code.parse().ok().map(Exit::Code)
+        } else {
+            field
+                .strip_prefix("signal=")?
+                .parse()
+                .ok()
+                .map(Exit::Signal)
+        }
+    }
+}
+
+// as recorded for a failed run in the csv file
+impl fmt::Display for Exit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exit::Code(code) => write!(f, "exit={}", code),
+            Exit::Signal(signal) => write!(f, "signal={}", signal),
+            Exit::MemoryLimit => write!(f, "memory-limit"),
+            Exit::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+// Runtimes for all iterations of a single proof, None for a failed run
+pub struct ProofResults {
+    pub name: String,
+    pub runs: Vec<Option<Duration>>,
+    // indices of runs whose measurement is suspect because the clock jumped during them
+    pub suspect_runs: Vec<usize>,
+    // indices of runs that were replaced by another run because they were outliers, they don't
+    // count towards the statistics
+    pub outlier_runs: Vec<usize>,
+    // indices of runs during which the machine swapped, whose runtimes are worthless
+    pub swapped_runs: Vec<usize>,
+    // indices of failed runs together with how they ended, for those where we know
+    pub exits: Vec<(usize, Exit)>,
+    // how many failed attempts with --retries preceded each run, recorded as a comment line
+    // before the runtimes unless none did
+    pub retries: Vec<u32>,
+    // tags like expensive, recorded as a comment line before the runtimes
+    pub tags: Vec<String>,
+    // how long building the proof (make goto) took before each run, None where we don't know,
+    // recorded as a comment line before the runtimes
+    pub build_times: Vec<Option<Duration>>,
+    // how long the CBMC decision procedure (the SAT/SMT solver) took in each run, same as above
+    pub solver_times: Vec<Option<Duration>>,
+    // how long goto-cc and goto-instrument, and cbmc took in each run with --tool-times, same as
+    // above
+    pub goto_times: Vec<Option<Duration>>,
+    pub cbmc_times: Vec<Option<Duration>>,
+    // the energy the CPU packages used in each run in joules, same as above
+    pub energies: Vec<Option<f64>>,
+    // the hash of the property results of each run, only known for the current session and in
+    // the per-run details file
+    pub property_hashes: Vec<Option<u64>>,
+    // how long each additional make target (--phase) took in each run, same as the build times
+    pub phase_times: Vec<(String, Vec<Option<Duration>>)>,
+    // peak resident set size over the successful runs in KiB, recorded as a comment line before
+    // the runtimes
+    pub max_rss_kib: Option<u64>,
+    // the most disk space the build artifacts in the proof directory took up after any run,
+    // recorded as a comment line before the runtimes
+    pub max_artifact_bytes: Option<u64>,
+}
+
+fn scaled_times(times: &[Option<Duration>], factor: f64) -> Vec<Option<Duration>> {
+    times
+        .iter()
+        .map(|time| time.map(|time| time.mul_f64(factor)))
+        .collect()
+}
+
+fn summarized_time(times: &[Option<Duration>], statistic: Statistic) -> Option<f64> {
+    let seconds: Vec<f64> = times
+        .iter()
+        .filter_map(|time| time.map(|time| time.as_secs_f64()))
+        .collect();
+    statistic.of(&seconds)
+}
+
+impl ProofResults {
+    pub fn new(name: String) -> ProofResults {
+        ProofResults {
+            name,
+            runs: Vec::new(),
+            suspect_runs: Vec::new(),
+            outlier_runs: Vec::new(),
+            swapped_runs: Vec::new(),
+            exits: Vec::new(),
+            retries: Vec::new(),
+            tags: Vec::new(),
+            build_times: Vec::new(),
+            solver_times: Vec::new(),
+            goto_times: Vec::new(),
+            cbmc_times: Vec::new(),
+            energies: Vec::new(),
+            property_hashes: Vec::new(),
+            phase_times: Vec::new(),
+            max_rss_kib: None,
+            max_artifact_bytes: None,
+        }
+    }
+
+    // runtimes of the successful runs, except for outliers
+    pub fn successful_runs(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.runs
+            .iter()
+            .enumerate()
+            .filter(move |(run_index, _)| !self.outlier_runs.contains(run_index))
+            .filter_map(|(_, run)| *run)
+    }
+
+    pub fn failed_runs(&self) -> usize {
+        self.runs.iter().filter(|run| run.is_none()).count()
+    }
+
+    pub fn exit(&self, run_index: usize) -> Option<Exit> {
+        self.exits
+            .iter()
+            .find(|(failed_run, _)| *failed_run == run_index)
+            .map(|(_, exit)| *exit)
+    }
+
+    pub fn scaled(&self, factor: f64) -> ProofResults {
+        ProofResults {
+            name: self.name.clone(),
+            runs: self
+                .runs
+                .iter()
+                .map(|run| run.map(|runtime| runtime.mul_f64(factor)))
+                .collect(),
+            suspect_runs: self.suspect_runs.clone(),
+            outlier_runs: self.outlier_runs.clone(),
+            swapped_runs: self.swapped_runs.clone(),
+            exits: self.exits.clone(),
+            retries: self.retries.clone(),
+            tags: self.tags.clone(),
+            build_times: scaled_times(&self.build_times, factor),
+            solver_times: scaled_times(&self.solver_times, factor),
+            goto_times: scaled_times(&self.goto_times, factor),
+            cbmc_times: scaled_times(&self.cbmc_times, factor),
+            // only the runtimes depend on the speed of the machine
+            energies: self.energies.clone(),
+            property_hashes: self.property_hashes.clone(),
+            phase_times: self
+                .phase_times
+                .iter()
+                .map(|(phase, times)| (phase.clone(), scaled_times(times, factor)))
+                .collect(),
+            max_rss_kib: self.max_rss_kib,
+            max_artifact_bytes: self.max_artifact_bytes,
+        }
+    }
+
+    // runtimes in seconds of all successful runs
+    pub fn runtimes_secs(&self) -> Vec<f64> {
+        self.successful_runs().map(|d| d.as_secs_f64()).collect()
+    }
+
+    // summarized runtime in seconds of all successful runs, None if no run succeeded
+    pub fn runtime(&self, statistic: Statistic) -> Option<f64> {
+        statistic.of(&self.runtimes_secs())
+    }
+
+    // summarized build time in seconds of all runs we know it for
+    pub fn build_time(&self, statistic: Statistic) -> Option<f64> {
+        summarized_time(&self.build_times, statistic)
+    }
+
+    // summarized solver time in seconds of all runs we know it for
+    pub fn solver_time(&self, statistic: Statistic) -> Option<f64> {
+        summarized_time(&self.solver_times, statistic)
+    }
+
+    // summarized time in seconds goto-cc and goto-instrument took in all runs we know it for
+    pub fn goto_time(&self, statistic: Statistic) -> Option<f64> {
+        summarized_time(&self.goto_times, statistic)
+    }
+
+    // summarized time in seconds cbmc took in all runs we know it for
+    pub fn cbmc_time(&self, statistic: Statistic) -> Option<f64> {
+        summarized_time(&self.cbmc_times, statistic)
+    }
+
+    // summarized time in seconds of a --phase of all runs we know it for
+    pub fn phase_time(&self, phase: &str, statistic: Statistic) -> Option<f64> {
+        let (_, times) = self.phase_times.iter().find(|(name, _)| name == phase)?;
+        summarized_time(times, statistic)
+    }
+
+    // the times of the phases of a run, None for the phases it didn't get to
+    pub fn push_phase_times(&mut self, phases: &[String], times: &[(String, Option<Duration>)]) {
+        for phase in phases.iter() {
+            let time = times
+                .iter()
+                .find(|(name, _)| name == phase)
+                .and_then(|(_, time)| *time);
+            match self.phase_times.iter_mut().find(|(name, _)| name == phase) {
+                Some((_, phase_times)) => phase_times.push(time),
+                None => self.phase_times.push((phase.clone(), vec![time])),
+            }
+        }
+    }
+
+    // forget all but the first runs, like they were never made; the peak RSS and artifact size
+    // are kept
+    pub fn truncate(&mut self, runs: usize) {
+        self.runs.truncate(runs);
+        self.suspect_runs.retain(|&run_index| run_index < runs);
+        self.outlier_runs.retain(|&run_index| run_index < runs);
+        self.swapped_runs.retain(|&run_index| run_index < runs);
+        self.exits.retain(|(run_index, _)| *run_index < runs);
+        self.retries.truncate(runs);
+        self.build_times.truncate(runs);
+        self.solver_times.truncate(runs);
+        self.goto_times.truncate(runs);
+        self.cbmc_times.truncate(runs);
+        self.energies.truncate(runs);
+        self.property_hashes.truncate(runs);
+        for (_, phase_times) in self.phase_times.iter_mut() {
+            phase_times.truncate(runs);
+        }
+    }
+
+    // the number of different property results among the runs we know them for, more than one
+    // means the verification itself is nondeterministic
+    pub fn distinct_property_results(&self) -> usize {
+        let mut hashes: Vec<u64> = self
+            .property_hashes
+            .iter()
+            .filter_map(|hash| *hash)
+            .collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        hashes.len()
+    }
+
+    // summarized energy in joules of all runs we know it for
+    pub fn energy(&self, statistic: Statistic) -> Option<f64> {
+        let energies: Vec<f64> = self.energies.iter().filter_map(|energy| *energy).collect();
+        statistic.of(&energies)
+    }
+}
+
+// Contextual information about why a run was made, stored as comment lines at the top of the csv
+// file
+#[derive(Default)]
+pub struct RunMetadata {
+    pub note: Option<String>,
+    pub meta: Vec<(String, String)>,
+}
+
+impl RunMetadata {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(meta_key, _)| meta_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.note.is_none() && self.meta.is_empty()
+    }
+
+    pub fn print(&self, prefix: &str) {
+        if let Some(note) = &self.note {
+            println!("{}note: {}", prefix, note);
+        }
+        for (key, value) in self.meta.iter() {
+            println!("{}{}={}", prefix, key, value);
+        }
+    }
+}
+
+// parse a --meta argument of the form key=value
+pub fn parse_key_value(arg: &str) -> Result<(String, String), String> {
+    match arg.find('=') {
+        Some(split) if split > 0 => Ok((arg[..split].to_string(), arg[split + 1..].to_string())),
+        _ => Err(format!("expected key=value, got '{}'", arg)),
+    }
+}
+
+pub struct ResultFile {
+    pub metadata: RunMetadata,
+    pub proofs: Vec<ProofResults>,
+}
+
+// name of the machine we're running on, recorded in the metadata so comparisons across machines
+// can be flagged
+pub fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .filter(|hostname| !hostname.is_empty())
+}
+
+// the cpufreq scaling governor(s) of all CPUs, e.g. performance or powersave, if the kernel
+// exposes them
+pub fn cpu_governor() -> Option<String> {
+    let mut governors: Vec<String> = std::fs::read_dir("/sys/devices/system/cpu")
+        .ok()?
+        .filter_map(|entry| {
+            std::fs::read_to_string(entry.ok()?.path().join("cpufreq/scaling_governor")).ok()
+        })
+        .map(|governor| governor.trim().to_string())
+        .collect();
+    governors.sort();
+    governors.dedup();
+    if governors.is_empty() {
+        None
+    } else {
+        Some(governors.join("+"))
+    }
+}
+
+pub fn dump_metadata(metadata: &RunMetadata, csv_file: &mut File) -> IOResult<()> {
+    if let Some(note) = &metadata.note {
+        writeln!(csv_file, "# note: {}", note.replace('\n', " "))?;
+    }
+    for (key, value) in metadata.meta.iter() {
+        writeln!(csv_file, "# meta: {}={}", key, value.replace('\n', " "))?;
+    }
+    csv_file.flush()
+}
+
+// a comment line with the times of a phase of every run, unless we don't know any of them
+fn dump_times(phase: &str, times: &[Option<Duration>], csv_file: &mut File) -> IOResult<()> {
+    if times.iter().all(Option::is_none) {
+        return Ok(());
+    }
+    let times: Vec<String> = times
+        .iter()
+        .map(|time| optional(time.map(|time| time.as_secs_f32())))
+        .collect();
+    writeln!(csv_file, "# {}: {}", phase, times.join(","))
+}
+
+// failed runs are recorded as how they ended (e.g. exit=2 or signal=9) if we know it, suspect runs
+// get a ? appended to their runtime
+pub fn dump_csv(proof: &ProofResults, csv_file: &mut File) -> IOResult<()> {
+    if !proof.tags.is_empty() {
+        writeln!(csv_file, "# tags: {}", proof.tags.join(" "))?;
+    }
+    dump_times("build", &proof.build_times, csv_file)?;
+    dump_times("solver", &proof.solver_times, csv_file)?;
+    dump_times("goto", &proof.goto_times, csv_file)?;
+    dump_times("cbmc", &proof.cbmc_times, csv_file)?;
+    for (phase, times) in proof.phase_times.iter() {
+        dump_times(&format!("phase {}", phase), times, csv_file)?;
+    }
+    if proof.energies.iter().any(Option::is_some) {
+        let energies: Vec<String> = proof
+            .energies
+            .iter()
+            .map(|energy| optional(*energy))
+            .collect();
+        writeln!(csv_file, "# energy: {}", energies.join(","))?;
+    }
+    if let Some(artifact_bytes) = proof.max_artifact_bytes {
+        writeln!(csv_file, "# disk: {}", artifact_bytes)?;
+    }
+    if let Some(rss_kib) = proof.max_rss_kib {
+        writeln!(csv_file, "# rss: {}", rss_kib)?;
+    }
+    if !proof.swapped_runs.is_empty() {
+        // as run numbers, starting at 1
+        let swapped_runs: Vec<String> = proof
+            .swapped_runs
+            .iter()
+            .map(|run_index| (run_index + 1).to_string())
+            .collect();
+        writeln!(csv_file, "# swapped: {}", swapped_runs.join(","))?;
+    }
+    if proof.retries.iter().any(|retries| *retries > 0) {
+        let retries: Vec<String> = proof.retries.iter().map(u32::to_string).collect();
+        writeln!(csv_file, "# retries: {}", retries.join(","))?;
+    }
+    csv_file.write_all(proof.name.as_bytes())?;
+    for (run_index, run) in proof.runs.iter().enumerate() {
+        csv_file.write_all(",".as_bytes())?;
+        if let Some(runtime) = run {
+            csv_file.write_all(format!("{}", runtime.as_secs_f32()).as_bytes())?;
+        } else if let Some(exit) = proof.exit(run_index) {
+            csv_file.write_all(exit.to_string().as_bytes())?;
+        }
+        if proof.outlier_runs.contains(&run_index) {
+            csv_file.write_all("!".as_bytes())?;
+        }
+        if proof.suspect_runs.contains(&run_index) {
+            csv_file.write_all("?".as_bytes())?;
+        }
+    }
+    csv_file.write_all("\n".as_bytes())?;
+    csv_file.flush()
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+// the columns of the per-run details file, in the order dump_run writes them
+const RUNS_COLUMNS: &[&str] = &[
+    "proof",
+    "run",
+    "run_id",
+    "runtime",
+    "success",
+    "exit_code",
+    "signal",
+    "memory_limit_exceeded",
+    "timed_out",
+    "retries",
+    "cpus",
+    "max_rss_kib",
+    "artifact_bytes",
+    "user_time",
+    "system_time",
+    "major_faults",
+    "minor_faults",
+    "voluntary_switches",
+    "involuntary_switches",
+    "io_read_bytes",
+    "io_write_bytes",
+    "cpu_utilization",
+    "max_cpu_utilization",
+    "system_utilization",
+    "max_system_utilization",
+    "energy_joules",
+    "start_load_average",
+    "end_load_average",
+    "swapped_pages",
+    "spawn_time",
+    "build_time",
+    "goto_time",
+    "cbmc_time",
+    "cbmc_vccs",
+    "cbmc_remaining_vccs",
+    "cbmc_program_steps",
+    "cbmc_symex_time",
+    "cbmc_decision_procedure_time",
+    "cbmc_property_hash",
+    "perf_instructions",
+    "perf_cycles",
+    "perf_cache_misses",
+    "phase_times",
+];
+
+pub fn dump_runs_header(runs_file: &mut File) -> IOResult<()> {
+    writeln!(runs_file, "{}", RUNS_COLUMNS.join(","))?;
+    runs_file.flush()
+}
+
+// one line of the per-run details file, the runtime in seconds and the CPUs separated by spaces,
+// empty fields for things we couldn't measure
+pub fn dump_run(
+    name: &str,
+    run_nr: usize,
+    run_id: &str,
+    runtime: Duration,
+    exit: Option<Exit>,
+    stats: &RunStats,
+    runs_file: &mut File,
+) -> IOResult<()> {
+    let cpus: Vec<String> = stats.cpus.iter().map(|cpu| cpu.to_string()).collect();
+    let (exit_code, signal) = match exit {
+        Some(Exit::Code(code)) => (Some(code), None),
+        Some(Exit::Signal(signal)) => (None, Some(signal)),
+        Some(Exit::MemoryLimit) | Some(Exit::Timeout) | None => (None, None),
+    };
+    writeln!(
+        runs_file,
+        "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+        name,
+        run_nr,
+        run_id,
+        runtime.as_secs_f32(),
+        exit == Some(Exit::Code(0)),
+        optional(exit_code),
+        optional(signal),
+        exit == Some(Exit::MemoryLimit),
+        exit == Some(Exit::Timeout),
+        stats.retries,
+        cpus.join(" "),
+        optional(stats.max_rss_kib),
+        optional(stats.artifact_bytes),
+        optional(stats.user_time.map(|time| time.as_secs_f32())),
+        optional(stats.system_time.map(|time| time.as_secs_f32())),
+        optional(stats.major_faults),
+        optional(stats.minor_faults),
+        optional(stats.voluntary_switches),
+        optional(stats.involuntary_switches),
+        optional(stats.io_read_bytes),
+        optional(stats.io_write_bytes),
+        optional(stats.cpu_utilization),
+        optional(stats.max_cpu_utilization),
+        optional(stats.system_utilization),
+        optional(stats.max_system_utilization),
+        optional(stats.energy_joules),
+        optional(stats.start_load_average),
+        optional(stats.end_load_average),
+        optional(stats.swapped_pages),
+        optional(stats.spawn_time.map(|time| time.as_secs_f64())),
+        optional(stats.build_time.map(|time| time.as_secs_f32())),
+        optional(stats.tool_times.goto.map(|time| time.as_secs_f32())),
+        optional(stats.tool_times.cbmc.map(|time| time.as_secs_f32())),
+        optional(stats.cbmc.vccs),
+        optional(stats.cbmc.remaining_vccs),
+        optional(stats.cbmc.program_steps),
+        optional(stats.cbmc.symex_time.map(|time| time.as_secs_f32())),
+        optional(
+            stats
+                .cbmc
+                .decision_procedure_time
+                .map(|time| time.as_secs_f32())
+        ),
+        optional(stats.cbmc.property_hash.map(|hash| format!("{:016x}", hash))),
+        optional(stats.perf.instructions),
+        optional(stats.perf.cycles),
+        optional(stats.perf.cache_misses),
+        stats
+            .phase_times
+            .iter()
+            .map(|(phase, time)| format!(
+                "{}={}",
+                phase,
+                optional(time.map(|time| time.as_secs_f32()))
+            ))
+            .collect::<Vec<_>>()
+            .join(" ")
+    )?;
+    runs_file.flush()
+}
+
+// the layouts of result files we can read
+enum ResultFormat {
+    // one line per proof with the runtimes of all its runs, as written by dump_csv
+    Wide,
+    // a header line and then one line per run, as written by dump_run
+    Long,
+}
+
+// recognize the format of a result file by its contents rather than its name, since result files
+// tend to get renamed on their way through CI artifacts
+fn detect_format<'a>(path: &Path, contents: &'a [u8]) -> GenericResult<(ResultFormat, &'a str)> {
+    const SUPPORTED: &str = "only csv result files as written by --csv-file or --runs-file are \
+                             supported";
+    if contents.starts_with(b"SQLite format 3\0") {
+        return Err(format!("{} is an SQLite database, {}", path.display(), SUPPORTED).into());
+    }
+    let contents = std::str::from_utf8(contents)
+        .map_err(|_| format!("{} is a binary file, {}", path.display(), SUPPORTED))?;
+    // both formats can start with metadata comments
+    match contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        Some(line) if line.starts_with('{') || line.starts_with('[') => {
+            Err(format!("{} looks like JSON, {}", path.display(), SUPPORTED).into())
+        }
+        Some(line) if line.starts_with("proof,run,") => Ok((ResultFormat::Long, contents)),
+        _ => Ok((ResultFormat::Wide, contents)),
+    }
+}
+
+// read a result file, possibly compressed, in the format written by dump_csv or by dump_run
+pub fn read_results(path: &Path) -> GenericResult<ResultFile> {
+    let contents = compression::read(path)?;
+    match detect_format(path, &contents)? {
+        (ResultFormat::Wide, contents) => parse_wide(path, contents),
+        (ResultFormat::Long, contents) => parse_long(path, contents),
+    }
+}
+
+fn parse_runtime(path: &Path, line_nr: usize, field: &str) -> GenericResult<Duration> {
+    let seconds: f64 = field.parse().map_err(|err| {
+        format!(
+            "{}:{}: invalid runtime '{}': {}",
+            path.display(),
+            line_nr + 1,
+            field,
+            err
+        )
+    })?;
+    // from_secs_f64 panics on negative, infinite and NaN runtimes, which a corrupt file can have
+    Duration::try_from_secs_f64(seconds).map_err(|err| {
+        format!(
+            "{}:{}: invalid runtime '{}': {}",
+            path.display(),
+            line_nr + 1,
+            field,
+            err
+        )
+        .into()
+    })
+}
+
+// the results in a per-run details file, which has no metadata and doesn't tell which runs were
+// replaced as outliers
+fn parse_long(path: &Path, contents: &str) -> GenericResult<ResultFile> {
+    let mut metadata = RunMetadata::default();
+    for comment in contents.lines().filter_map(|line| line.strip_prefix('#')) {
+        let comment = comment.trim();
+        if let Some(note) = comment.strip_prefix("note:") {
+            metadata.note = Some(note.trim().to_string());
+        } else if let Some(key_value) = comment.strip_prefix("meta:") {
+            metadata.meta.push(parse_key_value(key_value.trim())?);
+        }
+    }
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let (_, header) = lines
+        .next()
+        .expect("the format was detected by the header line");
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let column = |name: &str| columns.iter().position(|column| *column == name);
+    let (proof_column, runtime_column, success_column) =
+        match (column("proof"), column("runtime"), column("success")) {
+            (Some(proof), Some(runtime), Some(success)) => (proof, runtime, success),
+            _ => {
+                return Err(format!(
+                    "{}: the header line lacks the proof, runtime or success column",
+                    path.display()
+                )
+                .into())
+            }
+        };
+    let (exit_code_column, signal_column) = (column("exit_code"), column("signal"));
+    let memory_limit_column = column("memory_limit_exceeded");
+    let timed_out_column = column("timed_out");
+    let artifact_bytes_column = column("artifact_bytes");
+    let rss_column = column("max_rss_kib");
+    let energy_column = column("energy_joules");
+    let swapped_pages_column = column("swapped_pages");
+    let retries_column = column("retries");
+    let property_hash_column = column("cbmc_property_hash");
+    let phase_times_column = column("phase_times");
+    let (build_time_column, solver_time_column) =
+        (column("build_time"), column("cbmc_decision_procedure_time"));
+    let (goto_time_column, cbmc_time_column) = (column("goto_time"), column("cbmc_time"));
+    let optional_time = |fields: &[&str], column: Option<usize>| {
+        column
+            .and_then(|column| fields[column].parse::<f64>().ok())
+            // not a panic on negative, NaN, infinite or huge times in a corrupt file
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+    };
+    let mut proofs: Vec<ProofResults> = Vec::new();
+    for (line_nr, line) in lines {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != columns.len() {
+            return Err(format!(
+                "{}:{}: expected {} fields, got {}",
+                path.display(),
+                line_nr + 1,
+                columns.len(),
+                fields.len()
+            )
+            .into());
+        }
+        let name = fields[proof_column];
+        let proof = match proofs.iter().position(|proof| proof.name == name) {
+            Some(index) => &mut proofs[index],
+            None => {
+                proofs.push(ProofResults::new(name.to_string()));
+                proofs.last_mut().expect("we just added it")
+            }
+        };
+        let artifact_bytes =
+            artifact_bytes_column.and_then(|column| fields[column].parse::<u64>().ok());
+        proof.max_artifact_bytes = proof.max_artifact_bytes.max(artifact_bytes);
+        let rss_kib = rss_column.and_then(|column| fields[column].parse::<u64>().ok());
+        proof.max_rss_kib = proof.max_rss_kib.max(rss_kib);
+        proof
+            .build_times
+            .push(optional_time(&fields, build_time_column));
+        proof
+            .solver_times
+            .push(optional_time(&fields, solver_time_column));
+        proof
+            .goto_times
+            .push(optional_time(&fields, goto_time_column));
+        proof
+            .cbmc_times
+            .push(optional_time(&fields, cbmc_time_column));
+        proof
+            .energies
+            .push(energy_column.and_then(|column| fields[column].parse().ok()));
+        proof.property_hashes.push(
+            property_hash_column.and_then(|column| u64::from_str_radix(fields[column], 16).ok()),
+        );
+        if let Some(column) = phase_times_column {
+            // like report=1.5 other=, with an empty time for a phase that failed
+            let phase_times: Vec<(String, Option<Duration>)> = fields[column]
+                .split_whitespace()
+                .filter_map(|phase_time| {
+                    let (phase, time) = phase_time.split_once('=')?;
+                    Some((
+                        phase.to_string(),
+                        time.parse::<f64>()
+                            .ok()
+                            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()),
+                    ))
+                })
+                .collect();
+            let phases: Vec<String> = phase_times.iter().map(|(phase, _)| phase.clone()).collect();
+            proof.push_phase_times(&phases, &phase_times);
+        }
+        proof.retries.push(
+            retries_column
+                .and_then(|column| fields[column].parse().ok())
+                .unwrap_or(0),
+        );
+        let swapped_pages =
+            swapped_pages_column.and_then(|column| fields[column].parse::<u64>().ok());
+        if swapped_pages.is_some_and(|pages| pages > 0) {
+            proof.swapped_runs.push(proof.runs.len());
+        }
+        if fields[success_column] == "true" {
+            let runtime = parse_runtime(path, line_nr, fields[runtime_column])?;
+            proof.runs.push(Some(runtime));
+        } else {
+            let exit_code = exit_code_column.and_then(|column| fields[column].parse().ok());
+            let signal = signal_column.and_then(|column| fields[column].parse().ok());
+            let memory_limit_exceeded =
+                memory_limit_column.is_some_and(|column| fields[column] == "true");
+            let timed_out = timed_out_column.is_some_and(|column| fields[column] == "true");
+            let exit = if memory_limit_exceeded {
+                Some(Exit::MemoryLimit)
+            } else if timed_out {
+                Some(Exit::Timeout)
+            } else {
+                exit_code.map(Exit::Code).or(signal.map(Exit::Signal))
+            };
+            if let Some(exit) = exit {
+                proof.exits.push((proof.runs.len(), exit));
+            }
+            proof.runs.push(None);
+        }
+    }
+    Ok(ResultFile { metadata, proofs })
+}
+
+// the times written by dump_times
+fn parse_times(path: &Path, line_nr: usize, times: &str) -> GenericResult<Vec<Option<Duration>>> {
+    times
+        .split(',')
+        .map(str::trim)
+        .map(|field| match field {
+            "" => Ok(None),
+            field => parse_runtime(path, line_nr, field).map(Some),
+        })
+        .collect()
+}
+
+// the energies written by dump_csv
+fn parse_energies(path: &Path, line_nr: usize, energies: &str) -> GenericResult<Vec<Option<f64>>> {
+    energies
+        .split(',')
+        .map(str::trim)
+        .map(|field| match field {
+            "" => Ok(None),
+            field => field.parse().map(Some).map_err(|err| {
+                format!(
+                    "{}:{}: invalid energy '{}': {}",
+                    path.display(),
+                    line_nr + 1,
+                    field,
+                    err
+                )
+                .into()
+            }),
+        })
+        .collect()
+}
+
+fn parse_wide(csv_path: &Path, contents: &str) -> GenericResult<ResultFile> {
+    let mut metadata = RunMetadata::default();
+    let mut results = Vec::new();
+    // tags and phase times for the next proof
+    let mut tags = Vec::new();
+    let mut build_times = Vec::new();
+    let mut solver_times = Vec::new();
+    let mut goto_times = Vec::new();
+    let mut cbmc_times = Vec::new();
+    let mut energies = Vec::new();
+    let mut phase_times = Vec::new();
+    let mut swapped_runs = Vec::new();
+    let mut retries = Vec::new();
+    let mut artifact_bytes = None;
+    let mut rss_kib = None;
+    for (line_nr, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if let Some(note) = comment.strip_prefix("note:") {
+                metadata.note = Some(note.trim().to_string());
+            } else if let Some(key_value) = comment.strip_prefix("meta:") {
+                metadata.meta.push(parse_key_value(key_value.trim())?);
+            } else if let Some(proof_tags) = comment.strip_prefix("tags:") {
+                tags = proof_tags.split_whitespace().map(String::from).collect();
+            } else if let Some(times) = comment.strip_prefix("build:") {
+                build_times = parse_times(csv_path, line_nr, times)?;
+            } else if let Some(times) = comment.strip_prefix("solver:") {
+                solver_times = parse_times(csv_path, line_nr, times)?;
+            } else if let Some(times) = comment.strip_prefix("goto:") {
+                goto_times = parse_times(csv_path, line_nr, times)?;
+            } else if let Some(times) = comment.strip_prefix("cbmc:") {
+                cbmc_times = parse_times(csv_path, line_nr, times)?;
+            } else if let Some((phase, times)) = comment
+                .strip_prefix("phase ")
+                .and_then(|phase_times| phase_times.split_once(':'))
+            {
+                phase_times.push((
+                    phase.trim().to_string(),
+                    parse_times(csv_path, line_nr, times)?,
+                ));
+            } else if let Some(run_nrs) = comment.strip_prefix("swapped:") {
+                swapped_runs = run_nrs
+                    .split(',')
+                    .map(|run_nr| match run_nr.trim().parse::<usize>() {
+                        Ok(run_nr) if run_nr > 0 => Ok(run_nr - 1),
+                        _ => Err(format!(
+                            "{}:{}: invalid run number '{}'",
+                            csv_path.display(),
+                            line_nr + 1,
+                            run_nr.trim()
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+            } else if let Some(run_retries) = comment.strip_prefix("retries:") {
+                retries = run_retries
+                    .split(',')
+                    .map(|field| {
+                        field.trim().parse::<u32>().map_err(|err| {
+                            format!(
+                                "{}:{}: invalid retry count '{}': {}",
+                                csv_path.display(),
+                                line_nr + 1,
+                                field.trim(),
+                                err
+                            )
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            } else if let Some(joules) = comment.strip_prefix("energy:") {
+                energies = parse_energies(csv_path, line_nr, joules)?;
+            } else if let Some(bytes) = comment.strip_prefix("disk:") {
+                artifact_bytes = Some(bytes.trim().parse::<u64>().map_err(|err| {
+                    format!(
+                        "{}:{}: invalid disk usage '{}': {}",
+                        csv_path.display(),
+                        line_nr + 1,
+                        bytes.trim(),
+                        err
+                    )
+                })?);
+            } else if let Some(kib) = comment.strip_prefix("rss:") {
+                rss_kib = Some(kib.trim().parse::<u64>().map_err(|err| {
+                    format!(
+                        "{}:{}: invalid peak RSS '{}': {}",
+                        csv_path.display(),
+                        line_nr + 1,
+                        kib.trim(),
+                        err
+                    )
+                })?);
+            }
+            continue;
+        }
+        let mut fields = line.split(',');
+        let name = fields
+            .next()
+            .expect("split always yields at least one element")
+            .to_string();
+        let mut proof = ProofResults::new(name);
+        proof.tags = std::mem::take(&mut tags);
+        proof.build_times = std::mem::take(&mut build_times);
+        proof.solver_times = std::mem::take(&mut solver_times);
+        proof.goto_times = std::mem::take(&mut goto_times);
+        proof.cbmc_times = std::mem::take(&mut cbmc_times);
+        proof.energies = std::mem::take(&mut energies);
+        proof.phase_times = std::mem::take(&mut phase_times);
+        proof.swapped_runs = std::mem::take(&mut swapped_runs);
+        proof.retries = std::mem::take(&mut retries);
+        proof.max_artifact_bytes = artifact_bytes.take();
+        proof.max_rss_kib = rss_kib.take();
+        for field in fields {
+            let mut field = field.trim();
+            if let Some(unsuspected_field) = field.strip_suffix('?') {
+                proof.suspect_runs.push(proof.runs.len());
+                field = unsuspected_field;
+            }
+            if let Some(replaced_field) = field.strip_suffix('!') {
+                proof.outlier_runs.push(proof.runs.len());
+                field = replaced_field;
+            }
+            if field.is_empty() {
+                proof.runs.push(None);
+            } else if let Some(exit) = Exit::parse(field) {
+                proof.exits.push((proof.runs.len(), exit));
+                proof.runs.push(None);
+            } else {
+                let runtime = parse_runtime(csv_path, line_nr, field)?;
+                proof.runs.push(Some(runtime));
+            }
+        }
+        results.push(proof);
+    }
+    Ok(ResultFile {
+        metadata,
+        proofs: results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(seconds: f64) -> Option<Duration> {
+        Some(Duration::from_secs_f64(seconds))
+    }
+
+    fn test_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "benchmark-test-{}-{}.csv",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn reads_what_it_dumps() {
+        let path = test_path("round-trip");
+        let metadata = RunMetadata {
+            note: Some("baseline".to_string()),
+            meta: vec![("host".to_string(), "ci-1".to_string())],
+        };
+        let mut proof = ProofResults::new("aws_array_list".to_string());
+        proof.runs = vec![seconds(1.5), None, seconds(2.25), seconds(3.0)];
+        proof.exits = vec![(1, Exit::Code(2))];
+        proof.suspect_runs = vec![2];
+        proof.outlier_runs = vec![3];
+        proof.swapped_runs = vec![0];
+        proof.retries = vec![0, 1, 0, 0];
+        proof.tags = vec!["expensive".to_string()];
+        proof.build_times = vec![seconds(0.5), None, seconds(0.25), seconds(0.5)];
+        proof.phase_times = vec![("report".to_string(), vec![seconds(0.125), None, None, None])];
+        proof.max_rss_kib = Some(2048);
+        proof.max_artifact_bytes = Some(4096);
+        let mut other = ProofResults::new("timed_out".to_string());
+        other.runs = vec![None];
+        other.exits = vec![(0, Exit::Timeout)];
+        {
+            let mut csv_file = File::create(&path).unwrap();
+            dump_metadata(&metadata, &mut csv_file).unwrap();
+            dump_csv(&proof, &mut csv_file).unwrap();
+            dump_csv(&other, &mut csv_file).unwrap();
+        }
+        let read = read_results(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.metadata.note.as_deref(), Some("baseline"));
+        assert_eq!(read.metadata.get("host"), Some("ci-1"));
+        assert_eq!(read.proofs.len(), 2);
+        let read_proof = &read.proofs[0];
+        assert_eq!(read_proof.name, proof.name);
+        assert_eq!(read_proof.runs, proof.runs);
+        assert!(read_proof.exit(1) == Some(Exit::Code(2)));
+        assert_eq!(read_proof.suspect_runs, proof.suspect_runs);
+        assert_eq!(read_proof.outlier_runs, proof.outlier_runs);
+        assert_eq!(read_proof.swapped_runs, proof.swapped_runs);
+        assert_eq!(read_proof.retries, proof.retries);
+        assert_eq!(read_proof.tags, proof.tags);
+        assert_eq!(read_proof.build_times, proof.build_times);
+        assert_eq!(read_proof.phase_times, proof.phase_times);
+        assert_eq!(read_proof.max_rss_kib, proof.max_rss_kib);
+        assert_eq!(read_proof.max_artifact_bytes, proof.max_artifact_bytes);
+        let read_other = &read.proofs[1];
+        assert_eq!(read_other.name, "timed_out");
+        assert_eq!(read_other.runs, vec![None]);
+        assert!(read_other.exit(0) == Some(Exit::Timeout));
+        // the tags etc of one proof don't carry over to the next
+        assert!(read_other.tags.is_empty());
+        assert!(read_other.build_times.is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_runtimes() {
+        let path = test_path("invalid-runtimes");
+        for runtime in ["-1", "NaN", "inf", "1e300", "fast"] {
+            std::fs::write(&path, format!("# meta: host=ci-1\nproof,1.5,{}\n", runtime)).unwrap();
+            let err = read_results(&path).err().unwrap().to_string();
+            assert!(
+                err.contains(&format!(":2: invalid runtime '{}'", runtime)),
+                "unexpected error {}",
+                err
+            );
+            std::fs::write(
+                &path,
+                format!(
+                    "proof,run,runtime,success,build_time\nproof,1,{},true,1\n",
+                    runtime
+                ),
+            )
+            .unwrap();
+            let err = read_results(&path).err().unwrap().to_string();
+            assert!(
+                err.contains(&format!(":2: invalid runtime '{}'", runtime)),
+                "unexpected error {}",
+                err
+            );
+            // the other times are optional, so invalid ones are unknown
+            std::fs::write(
+                &path,
+                format!(
+                    "proof,run,runtime,success,build_time\nproof,1,1.5,true,{}\n",
+                    runtime
+                ),
+            )
+            .unwrap();
+            assert_eq!(
+                read_results(&path).unwrap().proofs[0].build_times,
+                vec![None]
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_per_run_details() {
+        let path = test_path("long");
+        std::fs::write(
+            &path,
+            "# meta: host=ci-1\n\
+             proof,run,runtime,success,exit_code,signal,timed_out,max_rss_kib,build_time\n\
+             a,1,1.5,true,0,,false,100,0.5\n\
+             b,1,0.25,false,2,,false,50,\n\
+             a,2,2.5,true,0,,false,300,0.25\n\
+             b,2,9,false,,,true,,\n",
+        )
+        .unwrap();
+        let read = read_results(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.metadata.get("host"), Some("ci-1"));
+        assert_eq!(read.proofs.len(), 2);
+        let a = &read.proofs[0];
+        assert_eq!(a.name, "a");
+        assert_eq!(a.runs, vec![seconds(1.5), seconds(2.5)]);
+        assert_eq!(a.build_times, vec![seconds(0.5), seconds(0.25)]);
+        assert_eq!(a.max_rss_kib, Some(300));
+        let b = &read.proofs[1];
+        assert_eq!(b.runs, vec![None, None]);
+        assert!(b.exit(0) == Some(Exit::Code(2)));
+        assert!(b.exit(1) == Some(Exit::Timeout));
+        assert_eq!(b.build_times, vec![None, None]);
+    }
+
+    #[test]
+    fn rejects_per_run_details_with_missing_fields() {
+        let path = test_path("long-missing-fields");
+        std::fs::write(&path, "proof,run,runtime,success\na,1,1.5\n").unwrap();
+        let err = read_results(&path).err().unwrap().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.ends_with(":2: expected 4 fields, got 3"), "{}", err);
+    }
+
+    #[test]
+    fn reads_phase_times_of_runs() {
+        let path = test_path("phase-times");
+        std::fs::write(
+            &path,
+            "proof,run,runtime,success,phase_times\n\
+             a,1,1.5,true,report=0.5 coverage=\n\
+             a,2,1.5,true,report=1e300 coverage=-1\n",
+        )
+        .unwrap();
+        let read = read_results(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            read.proofs[0].phase_times,
+            vec![
+                ("report".to_string(), vec![seconds(0.5), None]),
+                ("coverage".to_string(), vec![None, None])
+            ]
+        );
+    }
+}
@@ -0,0 +1,186 @@
+// Restrictions on what the make invocations of a proof can access.
+use std::ffi::CString;
+use std::io::{Error, Result as IOResult};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// to give every overlay its own scratch directory
+static NEXT_OVERLAY: AtomicUsize = AtomicUsize::new(0);
+
+// a writable overlay over a read-only source tree, all writes below lower end up in upper
+pub struct Overlay {
+    lower: PathBuf,
+    upper: PathBuf,
+    work: PathBuf,
+    // contains upper and work
+    scratch: PathBuf,
+}
+
+impl Overlay {
+    // a fresh, empty writable layer over lower (an absolute path) in a new directory below
+    // scratch_root
+    pub fn create(lower: &Path, scratch_root: &Path) -> IOResult<Overlay> {
+        let scratch = scratch_root.join(format!(
+            "overlay-{}",
+            NEXT_OVERLAY.fetch_add(1, Ordering::Relaxed)
+        ));
+        let overlay = Overlay {
+            lower: lower.to_path_buf(),
+            upper: scratch.join("upper"),
+            work: scratch.join("work"),
+            scratch,
+        };
+        std::fs::create_dir_all(&overlay.upper)?;
+        std::fs::create_dir_all(&overlay.work)?;
+        Ok(overlay)
+    }
+
+    // where the files written to path (below lower) end up outside of the sandbox
+    pub fn written_files(&self, path: &Path) -> PathBuf {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        match path.strip_prefix(&self.lower) {
+            Ok(relative) => self.upper.join(relative),
+            Err(_) => path,
+        }
+    }
+
+    pub fn remove(&self) -> IOResult<()> {
+        use std::os::unix::fs::PermissionsExt;
+        // the kernel leaves a directory without any permissions in the work directory, which we
+        // couldn't delete as an unprivileged user
+        let _ = std::fs::set_permissions(
+            self.work.join("work"),
+            std::fs::Permissions::from_mode(0o700),
+        );
+        std::fs::remove_dir_all(&self.scratch)
+    }
+}
+
+// the root of the git checkout containing proof_dir, the proof directory itself if it isn't in
+// one, as the source tree to protect with an Overlay
+pub fn source_root(proof_dir: &Path) -> IOResult<PathBuf> {
+    Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(proof_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+        .filter(|root| !root.as_os_str().is_empty())
+        .unwrap_or_else(|| proof_dir.to_path_buf())
+        .canonicalize()
+}
+
+fn c_string(path: &Path) -> CString {
+    CString::new(path.as_os_str().as_bytes()).expect("paths don't contain NUL bytes")
+}
+
+// write contents to path with raw syscalls, which is all we can safely do between fork and exec
+fn write_file(path: &[u8], contents: &[u8]) -> IOResult<()> {
+    debug_assert!(path.ends_with(b"\0"));
+    unsafe {
+        let fd = libc::open(path.as_ptr() as *const libc::c_char, libc::O_WRONLY);
+        if fd < 0 {
+            return Err(Error::last_os_error());
+        }
+        let written = libc::write(fd, contents.as_ptr() as *const libc::c_void, contents.len());
+        libc::close(fd);
+        if written != contents.len() as isize {
+            return Err(Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn check(result: libc::c_int) -> IOResult<()> {
+    if result != 0 {
+        Err(Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+// run command (with working directory directory) in new namespaces: without network access if
+// no_network is set, in which there is nothing but a loopback interface that is down, and with
+// overlay mounted over its source tree if given. As an unprivileged user this needs a user
+// namespace as well, in which we map our own user and group to themselves so file ownership
+// stays the same.
+pub fn restrict(
+    command: &mut Command,
+    directory: &Path,
+    no_network: bool,
+    overlay: Option<&Overlay>,
+) {
+    let mut flags = 0;
+    if no_network {
+        flags |= libc::CLONE_NEWNET;
+    }
+    if overlay.is_some() {
+        flags |= libc::CLONE_NEWNS;
+    }
+    if flags == 0 {
+        return;
+    }
+    let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+    let uid_map = format!("{} {} 1", uid, uid);
+    let gid_map = format!("{} {} 1", gid, gid);
+    // the working directory is entered before we get to run, so it must not be relative to it
+    let directory = c_string(
+        &directory
+            .canonicalize()
+            .unwrap_or_else(|_| directory.into()),
+    );
+    let mount = overlay.map(|overlay| {
+        (
+            c_string(&overlay.lower),
+            CString::new(format!(
+                "lowerdir={},upperdir={},workdir={}",
+                overlay.lower.display(),
+                overlay.upper.display(),
+                overlay.work.display()
+            ))
+            .expect("paths don't contain NUL bytes"),
+        )
+    });
+    let isolate = move || {
+        unsafe {
+            if uid == 0 {
+                check(libc::unshare(flags))?;
+            } else {
+                check(libc::unshare(flags | libc::CLONE_NEWUSER))?;
+                write_file(b"/proc/self/setgroups\0", b"deny")?;
+                write_file(b"/proc/self/gid_map\0", gid_map.as_bytes())?;
+                write_file(b"/proc/self/uid_map\0", uid_map.as_bytes())?;
+            }
+            if let Some((target, options)) = &mount {
+                // don't propagate our mounts back to the parent namespace
+                check(libc::mount(
+                    std::ptr::null(),
+                    b"/\0".as_ptr() as *const libc::c_char,
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                ))?;
+                check(libc::mount(
+                    b"overlay\0".as_ptr() as *const libc::c_char,
+                    target.as_ptr(),
+                    b"overlay\0".as_ptr() as *const libc::c_char,
+                    0,
+                    options.as_ptr() as *const libc::c_void,
+                ))?;
+                // the working directory was entered before the overlay was mounted on top of it
+                check(libc::chdir(directory.as_ptr()))?;
+            }
+        }
+        Ok(())
+    };
+    // safe because the closure doesn't allocate or take locks, it only makes syscalls
+    unsafe {
+        command.pre_exec(isolate);
+    }
+}
@@ -0,0 +1,394 @@
+// Hands out queued proofs to the worker threads, holding back proofs that can't run right now
+// because
+// - their concurrency groups are full
+// - proofs they depend on haven't finished yet
+// - their expected peak memory doesn't fit next to the running proofs (with --memory-aware)
+// - they have to run on their own (exclusive proofs)
+// - the machine is saturated (with --adaptive-jobs)
+use crate::environment;
+use crate::monitor;
+use crate::pattern;
+use crate::GenericResult;
+use crate::RunProofMessage;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+// how often the load and free memory are checked again while proofs are held back
+const ADMISSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// A group of proofs of which at most max_concurrent may run at the same time, e.g. because their
+// Makefiles share intermediate directories
+#[derive(Clone)]
+pub struct ProofGroup {
+    pub name: String,
+    pub max_concurrent: usize,
+    pub patterns: Vec<String>,
+}
+
+impl ProofGroup {
+    pub fn contains(&self, proof_name: &str) -> bool {
+        pattern::matches_any(&self.patterns, proof_name)
+    }
+}
+
+// parse a --group argument of the form NAME[:MAX]=PATTERN[,PATTERN...]
+pub fn parse_group(arg: &str) -> Result<ProofGroup, String> {
+    let split = arg
+        .find('=')
+        .ok_or_else(|| format!("expected NAME[:MAX]=PATTERN[,PATTERN...], got '{}'", arg))?;
+    let (name, max_concurrent) = match arg[..split].find(':') {
+        Some(colon) => (
+            &arg[..colon],
+            arg[colon + 1..split]
+                .parse::<usize>()
+                .map_err(|err| format!("invalid group limit in '{}': {}", arg, err))?,
+        ),
+        None => (&arg[..split], 1),
+    };
+    if name.is_empty() || max_concurrent == 0 {
+        return Err(format!("invalid group '{}'", arg));
+    }
+    Ok(ProofGroup {
+        name: name.to_string(),
+        max_concurrent,
+        patterns: arg[split + 1..]
+            .split(',')
+            .map(|pattern| pattern.to_string())
+            .collect(),
+    })
+}
+
+// When the machine counts as saturated with --adaptive-jobs, so no further proof starts while
+// another one is running
+#[derive(Clone, Copy)]
+pub struct Admission {
+    // the load average (or the number of runnable tasks right now) at which no more proofs start
+    pub max_load: f64,
+    pub min_free_memory_bytes: u64,
+}
+
+// the 1 minute load average and the tasks runnable right now from /proc/loadavg, like
+// 2.50 1.80 1.20 3/512 12345
+fn load() -> Option<(f64, f64)> {
+    let loadavg = std::fs::read_to_string("/proc/loadavg").ok()?;
+    let fields: Vec<&str> = loadavg.split_whitespace().collect();
+    let (runnable, _) = fields.get(3)?.split_once('/')?;
+    Some((fields.first()?.parse().ok()?, runnable.parse().ok()?))
+}
+
+fn available_memory_bytes() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    Some(environment::meminfo_field(&meminfo, "MemAvailable")? * 1024)
+}
+
+impl Admission {
+    // why no further proof should start now, None if one can
+    fn saturated(&self) -> Option<String> {
+        if let Some((load_average, runnable)) = load() {
+            // the thread reading /proc/loadavg is runnable itself
+            let load = load_average.max(runnable - 1.0);
+            if load >= self.max_load {
+                return Some(format!("load {:.1}, at most {}", load, self.max_load));
+            }
+        }
+        match available_memory_bytes() {
+            Some(available) if available < self.min_free_memory_bytes => {
+                Some(format!("{}MiB of memory available", available >> 20))
+            }
+            _ => None,
+        }
+    }
+}
+
+// proofs matching pattern only start once all proofs matching one of the after patterns finished,
+// e.g. because they rebuild a shared library the others use
+pub struct Dependency {
+    pub pattern: String,
+    pub after: Vec<String>,
+}
+
+// a --dependencies-file with a PATTERN: PATTERN... line per dependency, skipping empty lines and #
+// comments
+pub fn load_dependencies(path: &Path) -> GenericResult<Vec<Dependency>> {
+    let mut dependencies = Vec::new();
+    for (line_nr, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once(':') {
+            Some((pattern, after)) if !pattern.trim().is_empty() && !after.trim().is_empty() => {
+                dependencies.push(Dependency {
+                    pattern: pattern.trim().to_string(),
+                    after: after.split_whitespace().map(String::from).collect(),
+                })
+            }
+            _ => {
+                return Err(format!(
+                    "{}:{}: expected PATTERN: PATTERN..., got '{}'",
+                    path.display(),
+                    line_nr + 1,
+                    line
+                )
+                .into())
+            }
+        }
+    }
+    Ok(dependencies)
+}
+
+struct SchedulerState {
+    queue: VecDeque<RunProofMessage>,
+    running_per_group: HashMap<String, usize>,
+    // proofs handed out and not finished yet, and their paths (two jobs for the same proof, one
+    // per round with --interleave, can't run at once since they share the build directory)
+    running: usize,
+    running_proofs: HashSet<PathBuf>,
+    // the jobs (one per round with --interleave) of every proof that didn't finish yet
+    unfinished_jobs: HashMap<PathBuf, usize>,
+    // the expected peak memory of those proofs
+    reserved_memory_bytes: u64,
+    // whether one of them is exclusive, so nothing else may start
+    running_exclusive: bool,
+    // whether proofs are held back because the machine is saturated
+    holding: bool,
+}
+
+pub struct Scheduler {
+    state: Mutex<SchedulerState>,
+    changed: Condvar,
+    group_limits: HashMap<String, usize>,
+    admission: Option<Admission>,
+    // how much the expected peak memory of the running proofs may add up to
+    memory_budget_bytes: Option<u64>,
+}
+
+impl Scheduler {
+    pub fn new(
+        jobs: Vec<RunProofMessage>,
+        groups: &[ProofGroup],
+        admission: Option<Admission>,
+        memory_budget_bytes: Option<u64>,
+    ) -> Scheduler {
+        let mut unfinished_jobs = HashMap::new();
+        for job in jobs.iter() {
+            *unfinished_jobs.entry(job.job_path.clone()).or_insert(0) += 1;
+        }
+        Scheduler {
+            state: Mutex::new(SchedulerState {
+                unfinished_jobs,
+                queue: jobs.into_iter().collect(),
+                running_per_group: HashMap::new(),
+                running: 0,
+                running_proofs: HashSet::new(),
+                reserved_memory_bytes: 0,
+                running_exclusive: false,
+                holding: false,
+            }),
+            changed: Condvar::new(),
+            group_limits: groups
+                .iter()
+                .map(|group| (group.name.clone(), group.max_concurrent))
+                .collect(),
+            admission,
+            memory_budget_bytes,
+        }
+    }
+
+    // the reason to hold back all proofs, never while none are running so the session goes on,
+    // and not once the session stops so the remaining proofs get handed out (and skipped) right
+    // away
+    fn saturated(&self, state: &SchedulerState) -> Option<String> {
+        if state.running == 0 || monitor::stopping() {
+            return None;
+        }
+        self.admission.as_ref()?.saturated()
+    }
+
+    // whether job fits into its groups and next to the memory of the running proofs, and the proofs
+    // it runs after have finished; a proof whose peak memory isn't known reserves none, and one
+    // that doesn't fit at all runs on its own
+    fn ready(&self, state: &SchedulerState, job: &RunProofMessage) -> bool {
+        let fits_memory = match (self.memory_budget_bytes, job.memory_bytes) {
+            (Some(budget), Some(memory)) => {
+                state.running == 0 || state.reserved_memory_bytes + memory <= budget
+            }
+            _ => true,
+        };
+        fits_memory
+            && !state.running_proofs.contains(&job.job_path)
+            && job
+                .after
+                .iter()
+                .all(|proof| state.unfinished_jobs.get(proof).cloned().unwrap_or(0) == 0)
+            && job.groups.iter().all(|group| {
+                state.running_per_group.get(group).cloned().unwrap_or(0) < self.group_limits[group]
+            })
+    }
+
+    // whether job is ready and may run next to the running proofs, which an exclusive proof
+    // doesn't share the machine with
+    fn can_start(&self, state: &SchedulerState, job: &RunProofMessage) -> bool {
+        self.ready(state, job) && !state.running_exclusive && (!job.exclusive || state.running == 0)
+    }
+
+    // the next job that can be started, blocking until one becomes available. Returns None once
+    // all jobs have been handed out.
+    pub fn next_job(&self) -> Option<RunProofMessage> {
+        let mut state = self
+            .state
+            .lock()
+            .expect("scheduler lock shouldn't be poisoned");
+        loop {
+            if state.queue.is_empty() {
+                return None;
+            }
+            if let Some(reason) = self.saturated(&state) {
+                if !state.holding {
+                    println!("HOLDING BACK proofs, {}", reason);
+                    state.holding = true;
+                }
+                // until a proof finishes or the machine might have recovered
+                state = self
+                    .changed
+                    .wait_timeout(state, ADMISSION_CHECK_INTERVAL)
+                    .expect("scheduler lock shouldn't be poisoned")
+                    .0;
+                continue;
+            }
+            let mut startable = None;
+            for (index, job) in state.queue.iter().enumerate() {
+                if self.can_start(&state, job) {
+                    startable = Some(index);
+                    break;
+                }
+                // nothing after a ready exclusive proof starts, so the running proofs drain and it
+                // doesn't wait forever
+                if job.exclusive && self.ready(&state, job) {
+                    break;
+                }
+            }
+            if let Some(index) = startable {
+                if state.holding {
+                    println!("ADMITTING proofs again");
+                    state.holding = false;
+                }
+                let job = state.queue.remove(index).expect("index is in bounds");
+                for group in job.groups.iter() {
+                    *state.running_per_group.entry(group.clone()).or_insert(0) += 1;
+                }
+                state.running += 1;
+                state.running_proofs.insert(job.job_path.clone());
+                state.reserved_memory_bytes += job.memory_bytes.unwrap_or(0);
+                state.running_exclusive = job.exclusive;
+                return Some(job);
+            }
+            state = self
+                .changed
+                .wait(state)
+                .expect("scheduler lock shouldn't be poisoned");
+        }
+    }
+
+    // put a job that was handed out back at the front of the queue, when the job thread running it
+    // died before finishing it
+    pub fn requeue(&self, job: RunProofMessage) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("scheduler lock shouldn't be poisoned");
+        Scheduler::release(&mut state, &job);
+        state.queue.push_front(job);
+        self.changed.notify_all();
+    }
+
+    // drop the queued jobs of the proof in path, the later rounds of one whose job failed with
+    // --interleave
+    pub fn drop_jobs(&self, path: &Path) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("scheduler lock shouldn't be poisoned");
+        let queued = state.queue.len();
+        state.queue.retain(|job| job.job_path != path);
+        let dropped = queued - state.queue.len();
+        if let Some(unfinished) = state.unfinished_jobs.get_mut(path) {
+            *unfinished -= dropped;
+        }
+        self.changed.notify_all();
+    }
+
+    pub fn finish_job(&self, job: &RunProofMessage) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("scheduler lock shouldn't be poisoned");
+        Scheduler::release(&mut state, job);
+        if let Some(unfinished) = state.unfinished_jobs.get_mut(&job.job_path) {
+            *unfinished -= 1;
+        }
+        self.changed.notify_all();
+    }
+
+    // what a running job held, back for the others
+    fn release(state: &mut SchedulerState, job: &RunProofMessage) {
+        for group in job.groups.iter() {
+            if let Some(running) = state.running_per_group.get_mut(group) {
+                *running -= 1;
+            }
+        }
+        state.running -= 1;
+        state.running_proofs.remove(&job.job_path);
+        state.reserved_memory_bytes -= job.memory_bytes.unwrap_or(0);
+        if job.exclusive {
+            state.running_exclusive = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_groups() {
+        let group = parse_group("shared:2=aws_*,s2n_*").unwrap();
+        assert_eq!(group.name, "shared");
+        assert_eq!(group.max_concurrent, 2);
+        assert_eq!(group.patterns, vec!["aws_*", "s2n_*"]);
+        assert!(group.contains("aws_array_list"));
+        assert!(!group.contains("other"));
+        assert_eq!(parse_group("solo=one").unwrap().max_concurrent, 1);
+        assert!(parse_group("no-patterns").is_err());
+        assert!(parse_group("zero:0=a").is_err());
+        assert!(parse_group(":2=a").is_err());
+        assert!(parse_group("bad:x=a").is_err());
+    }
+
+    #[test]
+    fn loads_dependencies() {
+        let path = std::env::temp_dir().join(format!(
+            "benchmark-test-dependencies-{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "# comment\n\nlibrary_*: setup\nuser: library_* setup\n",
+        )
+        .unwrap();
+        let dependencies = load_dependencies(&path).unwrap();
+        assert_eq!(dependencies.len(), 2);
+        assert_eq!(dependencies[0].pattern, "library_*");
+        assert_eq!(dependencies[0].after, vec!["setup"]);
+        assert_eq!(dependencies[1].pattern, "user");
+        assert_eq!(dependencies[1].after, vec!["library_*", "setup"]);
+        std::fs::write(&path, "ok: fine\nmissing colon\n").unwrap();
+        let err = load_dependencies(&path).err().unwrap().to_string();
+        assert!(err.ends_with(":2: expected PATTERN: PATTERN..., got 'missing colon'"));
+        std::fs::write(&path, "nothing:\n").unwrap();
+        assert!(load_dependencies(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,39 @@
+// Checkpoints of a session, so one that got killed can be resumed without running the proofs it
+// already completed again. The state file has the same format as the csv file, with every proof
+// appended as soon as all its runs are done.
+use crate::results::{dump_csv, dump_metadata, read_results, ProofResults, RunMetadata};
+use crate::GenericResult;
+use std::fs::{File, OpenOptions};
+use std::io::Result as IOResult;
+use std::path::Path;
+
+pub struct StateFile {
+    file: File,
+}
+
+impl StateFile {
+    // start a new state file, or with resume continue the one at path and return the proofs it
+    // already has
+    pub fn open(
+        path: &Path,
+        resume: bool,
+        metadata: &RunMetadata,
+    ) -> GenericResult<(StateFile, Vec<ProofResults>)> {
+        if resume && path.exists() {
+            let completed = read_results(path)?.proofs;
+            let file = OpenOptions::new().append(true).open(path)?;
+            return Ok((StateFile { file }, completed));
+        }
+        let mut file = File::create(path)?;
+        dump_metadata(metadata, &mut file)?;
+        Ok((StateFile { file }, Vec::new()))
+    }
+
+    // all variants of a proof whose runs are all done
+    pub fn record(&mut self, variants: &[ProofResults]) -> IOResult<()> {
+        for variant in variants.iter() {
+            dump_csv(variant, &mut self.file)?;
+        }
+        self.file.sync_data()
+    }
+}
@@ -0,0 +1,541 @@
+// Statistical helpers used when comparing results.
+use std::fmt;
+use std::str::FromStr;
+
+// which value to summarize the runtimes of a proof with
+#[derive(Clone, Copy)]
+pub enum Statistic {
+    Mean,
+    Median,
+    Min,
+    // minimum of the first N runs
+    BestOf(usize),
+}
+
+impl Statistic {
+    pub fn of(&self, samples: &[f64]) -> Option<f64> {
+        if samples.is_empty() {
+            return None;
+        }
+        match *self {
+            Statistic::Mean => Some(mean(samples)),
+            Statistic::Median => Some(median(samples)),
+            Statistic::Min => Some(samples.iter().cloned().fold(f64::INFINITY, f64::min)),
+            Statistic::BestOf(n) => Some(
+                samples
+                    .iter()
+                    .take(n)
+                    .cloned()
+                    .fold(f64::INFINITY, f64::min),
+            ),
+        }
+    }
+}
+
+impl FromStr for Statistic {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Statistic, String> {
+        match arg {
+            "mean" => Ok(Statistic::Mean),
+            "median" => Ok(Statistic::Median),
+            "min" => Ok(Statistic::Min),
+            _ => match arg.strip_prefix("best-of-").map(str::parse::<usize>) {
+                Some(Ok(n)) if n > 0 => Ok(Statistic::BestOf(n)),
+                _ => Err(format!(
+                    "unknown statistic '{}', expected mean, median, min or best-of-N",
+                    arg
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Statistic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Statistic::Mean => write!(f, "mean"),
+            Statistic::Median => write!(f, "median"),
+            Statistic::Min => write!(f, "min"),
+            Statistic::BestOf(n) => write!(f, "best-of-{}", n),
+        }
+    }
+}
+
+// Abramowitz & Stegun 7.1.26 approximation of the error function, accurate to about 1e-7
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    sign * (1.0 - polynomial * (-x * x).exp())
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+// two-sided 95% quantiles of the student t distribution for 1 to 30 degrees of freedom
+const T_QUANTILES_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+fn t_quantile_95(degrees_of_freedom: usize) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::INFINITY,
+        1..=30 => T_QUANTILES_95[degrees_of_freedom - 1],
+        _ => 1.96,
+    }
+}
+
+pub fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+pub fn median(samples: &[f64]) -> f64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("runtimes are never NaN"));
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        sorted[middle]
+    } else {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    }
+}
+
+// geometric mean of positive values, None if there are none
+pub fn geometric_mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() || values.iter().any(|&value| value <= 0.0) {
+        return None;
+    }
+    let log_sum: f64 = values.iter().map(|value| value.ln()).sum();
+    Some((log_sum / values.len() as f64).exp())
+}
+
+pub fn sample_standard_deviation(samples: &[f64]) -> f64 {
+    let mean = mean(samples);
+    let sum_of_squares: f64 = samples.iter().map(|x| (x - mean) * (x - mean)).sum();
+    (sum_of_squares / (samples.len() - 1) as f64).sqrt()
+}
+
+// half width of the 95% confidence interval of the mean relative to the mean, None if there
+// are not at least two samples
+pub fn relative_confidence_half_width(samples: &[f64]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let half_width = t_quantile_95(samples.len() - 1) * sample_standard_deviation(samples)
+        / (samples.len() as f64).sqrt();
+    Some(half_width / mean(samples))
+}
+
+// how many other runtimes we need to judge whether one is an outlier
+const MIN_OUTLIER_REFERENCE_SAMPLES: usize = 3;
+
+// by how many standard deviations of the others value deviates from their mean, None if there are
+// too few others or they don't vary at all
+pub fn outlier_sigmas(value: f64, others: &[f64]) -> Option<f64> {
+    if others.len() < MIN_OUTLIER_REFERENCE_SAMPLES {
+        return None;
+    }
+    let standard_deviation = sample_standard_deviation(others);
+    if standard_deviation > 0.0 {
+        Some((value - mean(others)).abs() / standard_deviation)
+    } else {
+        None
+    }
+}
+
+// cheap deterministic pseudo random numbers, state must not be 0
+pub fn xorshift(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// an estimate together with its 95% confidence interval
+pub struct Estimate {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+// the resampling is seeded with a constant so reports are reproducible
+const BOOTSTRAP_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// percentile bootstrap: the statistic computed on resamples resamples (drawn with replacement)
+// of the samples, its mean as the estimate and its 2.5th and 97.5th percentiles as the
+// confidence interval, None if there are not at least two samples
+pub fn bootstrap(samples: &[f64], statistic: Statistic, resamples: usize) -> Option<Estimate> {
+    if samples.len() < 2 || resamples == 0 {
+        return None;
+    }
+    let mut state = BOOTSTRAP_SEED;
+    let mut resample = vec![0.0; samples.len()];
+    let mut estimates: Vec<f64> = (0..resamples)
+        .map(|_| {
+            for value in resample.iter_mut() {
+                *value = samples[(xorshift(&mut state) % samples.len() as u64) as usize];
+            }
+            statistic.of(&resample).expect("resamples are never empty")
+        })
+        .collect();
+    estimates.sort_by(|a, b| a.partial_cmp(b).expect("runtimes are never NaN"));
+    let percentile = |p: f64| estimates[((p * (resamples - 1) as f64).round()) as usize];
+    Some(Estimate {
+        value: mean(&estimates),
+        lower: percentile(0.025),
+        upper: percentile(0.975),
+    })
+}
+
+// the two modes of an apparently bimodal sample
+pub struct Modes {
+    pub low_mean: f64,
+    pub low_count: usize,
+    pub high_mean: f64,
+    pub high_count: usize,
+    // Ashman's D, how far apart the modes are relative to their spread
+    pub separation: f64,
+}
+
+// we need a few samples in each mode before calling anything bimodal
+const MIN_SAMPLES_PER_MODE: usize = 2;
+// Ashman's D above 2 is the usual criterion for a clean separation of two modes
+const MIN_MODE_SEPARATION: f64 = 2.0;
+// and the modes need to be far enough apart to matter, relative to the lower one
+const MIN_MODE_DISTANCE: f64 = 0.1;
+
+fn population_variance(samples: &[f64]) -> f64 {
+    let mean = mean(samples);
+    samples.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / samples.len() as f64
+}
+
+// split the samples into two clusters (the optimal 1-dimensional 2-means split) and return them
+// as modes if they are clearly separated
+pub fn bimodal_modes(samples: &[f64]) -> Option<Modes> {
+    if samples.len() < 2 * MIN_SAMPLES_PER_MODE + 1 {
+        return None;
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("runtimes are never NaN"));
+    let within_cluster_variance = |split: usize| {
+        let (low, high) = sorted.split_at(split);
+        population_variance(low) * low.len() as f64 + population_variance(high) * high.len() as f64
+    };
+    let split = (MIN_SAMPLES_PER_MODE..=sorted.len() - MIN_SAMPLES_PER_MODE).min_by(|&a, &b| {
+        within_cluster_variance(a)
+            .partial_cmp(&within_cluster_variance(b))
+            .expect("runtimes are never NaN")
+    })?;
+    let (low, high) = sorted.split_at(split);
+    let spread = (population_variance(low) + population_variance(high)).sqrt();
+    let separation = if spread > 0.0 {
+        std::f64::consts::SQRT_2 * (mean(high) - mean(low)) / spread
+    } else if mean(high) > mean(low) {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    if separation > MIN_MODE_SEPARATION && mean(high) - mean(low) > MIN_MODE_DISTANCE * mean(low) {
+        Some(Modes {
+            low_mean: mean(low),
+            low_count: low.len(),
+            high_mean: mean(high),
+            high_count: high.len(),
+            separation,
+        })
+    } else {
+        None
+    }
+}
+
+// ranks of all values in the concatenation of a and b, tied values get the average of their ranks
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&i, &j| {
+        values[i]
+            .partial_cmp(&values[j])
+            .expect("runtimes are never NaN")
+    });
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // ranks are 1-based, so the average of start+1..=end
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &index in order[start..end].iter() {
+            ranks[index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+// number of arrangements of n1 + n2 values (without ties) for which the U statistic of the
+// first sample equals u, for all u in 0..=n1*n2
+fn u_distribution(n1: usize, n2: usize) -> Vec<f64> {
+    // counts[m][n] is the distribution for samples of size m and n
+    let mut counts: Vec<Vec<Vec<f64>>> = vec![vec![Vec::new(); n2 + 1]; n1 + 1];
+    for m in 0..=n1 {
+        for n in 0..=n2 {
+            counts[m][n] = if m == 0 || n == 0 {
+                vec![1.0]
+            } else {
+                let mut distribution = vec![0.0; m * n + 1];
+                // the largest value is either from the first sample (beating all n values
+                // of the second sample) or from the second one
+                for (u, count) in counts[m - 1][n].iter().enumerate() {
+                    distribution[u + n] += count;
+                }
+                for (u, count) in counts[m][n - 1].iter().enumerate() {
+                    distribution[u] += count;
+                }
+                distribution
+            };
+        }
+    }
+    counts.swap_remove(n1).swap_remove(n2)
+}
+
+// number of subsets of 1..=n with rank sum w, for all w in 0..=n(n+1)/2
+fn signed_rank_distribution(n: usize) -> Vec<f64> {
+    let mut distribution = vec![0.0; n * (n + 1) / 2 + 1];
+    distribution[0] = 1.0;
+    for rank in 1..=n {
+        for w in (rank..distribution.len()).rev() {
+            distribution[w] += distribution[w - rank];
+        }
+    }
+    distribution
+}
+
+// two-sided p-value of the Wilcoxon signed-rank test for the hypothesis that the paired
+// differences are symmetric around 0, None if all differences are 0
+pub fn wilcoxon_signed_rank(differences: &[f64]) -> Option<f64> {
+    let nonzero: Vec<f64> = differences.iter().cloned().filter(|d| *d != 0.0).collect();
+    if nonzero.is_empty() {
+        return None;
+    }
+    let n = nonzero.len();
+    let absolute_ranks = ranks(&nonzero.iter().map(|d| d.abs()).collect::<Vec<_>>());
+    let positive_rank_sum: f64 = nonzero
+        .iter()
+        .zip(absolute_ranks.iter())
+        .filter(|(d, _)| **d > 0.0)
+        .map(|(_, rank)| rank)
+        .sum();
+    let total_rank_sum = (n * (n + 1)) as f64 / 2.0;
+    let w = positive_rank_sum.min(total_rank_sum - positive_rank_sum);
+    let p_value = if n <= EXACT_MANN_WHITNEY_LIMIT {
+        let distribution = signed_rank_distribution(n);
+        let total: f64 = distribution.iter().sum();
+        // with ties w can be fractional, in which case we round up to stay conservative
+        let at_most_w: f64 = distribution[..=w.ceil() as usize].iter().sum();
+        2.0 * at_most_w / total
+    } else {
+        let mean = total_rank_sum / 2.0;
+        let standard_deviation = ((n * (n + 1) * (2 * n + 1)) as f64 / 24.0).sqrt();
+        let z = (w - mean + 0.5) / standard_deviation;
+        2.0 * normal_cdf(z)
+    };
+    Some(p_value.min(1.0))
+}
+
+// above this many values in total we use the normal approximation instead of the exact
+// distribution of U
+const EXACT_MANN_WHITNEY_LIMIT: usize = 40;
+
+// two-sided p-value of the Mann-Whitney U test for the hypothesis that a and b come from the same
+// distribution, None if either sample is empty
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let (n1, n2) = (a.len(), b.len());
+    let all_values: Vec<f64> = a.iter().chain(b.iter()).cloned().collect();
+    let all_ranks = ranks(&all_values);
+    let rank_sum_a: f64 = all_ranks[..n1].iter().sum();
+    let u_a = rank_sum_a - (n1 * (n1 + 1)) as f64 / 2.0;
+    let u = u_a.min((n1 * n2) as f64 - u_a);
+
+    let p_value = if n1 + n2 <= EXACT_MANN_WHITNEY_LIMIT {
+        let distribution = u_distribution(n1, n2);
+        let total: f64 = distribution.iter().sum();
+        // with ties u can be fractional, in which case we round up to stay conservative
+        let at_most_u: f64 = distribution[..=u.ceil() as usize].iter().sum();
+        2.0 * at_most_u / total
+    } else {
+        let mean = (n1 * n2) as f64 / 2.0;
+        let standard_deviation = ((n1 * n2 * (n1 + n2 + 1)) as f64 / 12.0).sqrt();
+        // continuity correction
+        let z = (u - mean + 0.5) / standard_deviation;
+        2.0 * normal_cdf(z)
+    };
+    Some(p_value.min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn normal_cdf_matches_tables() {
+        assert_close(normal_cdf(0.0), 0.5);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.0) - 0.158_655).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ranks_ties_by_their_average() {
+        assert_eq!(ranks(&[10.0, 30.0, 20.0, 20.0]), vec![1.0, 4.0, 2.5, 2.5]);
+    }
+
+    #[test]
+    fn mann_whitney_u_matches_exact_reference_values() {
+        // U = 0 is 1 of the C(6, 3) = 20 arrangements
+        assert_close(
+            mann_whitney_u(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]).unwrap(),
+            0.1,
+        );
+        // U = 0 is 1 of the C(10, 5) = 252 arrangements
+        assert_close(
+            mann_whitney_u(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0]).unwrap(),
+            2.0 / 252.0,
+        );
+        // U = 3, at most 3 in 7 of 20 arrangements
+        assert_close(
+            mann_whitney_u(&[1.0, 3.0, 5.0], &[2.0, 4.0, 6.0]).unwrap(),
+            0.7,
+        );
+        assert!(mann_whitney_u(&[], &[1.0]).is_none());
+    }
+
+    #[test]
+    fn mann_whitney_u_approximates_large_samples() {
+        let low: Vec<f64> = (1..=21).map(f64::from).collect();
+        let high: Vec<f64> = (22..=42).map(f64::from).collect();
+        assert!(mann_whitney_u(&low, &high).unwrap() < 1e-6);
+        let odd: Vec<f64> = (0..21).map(|i| f64::from(2 * i + 1)).collect();
+        let even: Vec<f64> = (0..21).map(|i| f64::from(2 * i + 2)).collect();
+        assert!(mann_whitney_u(&odd, &even).unwrap() > 0.5);
+    }
+
+    #[test]
+    fn wilcoxon_signed_rank_matches_exact_reference_values() {
+        // W = 0 is 1 of the 2^5 = 32 sign combinations
+        assert_close(
+            wilcoxon_signed_rank(&[1.0, 2.0, 3.0, 4.0, 5.0]).unwrap(),
+            0.0625,
+        );
+        // W = 6, at most 6 in 13 of 32 sign combinations
+        assert_close(
+            wilcoxon_signed_rank(&[1.0, -2.0, 3.0, -4.0, 5.0]).unwrap(),
+            0.8125,
+        );
+        // zero differences are dropped
+        assert_close(
+            wilcoxon_signed_rank(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0]).unwrap(),
+            0.0625,
+        );
+        assert!(wilcoxon_signed_rank(&[0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn measures_confidence_intervals() {
+        assert_close(sample_standard_deviation(&[1.0, 2.0, 3.0]), 1.0);
+        // t(2) = 4.303, standard error 1 / sqrt(3), relative to the mean of 2
+        assert_close(
+            relative_confidence_half_width(&[1.0, 2.0, 3.0]).unwrap(),
+            4.303 / 3f64.sqrt() / 2.0,
+        );
+        // the normal quantile once there are more than 30 degrees of freedom
+        let samples: Vec<f64> = (0..40).map(|i| f64::from(i % 2) + 1.0).collect();
+        let standard_error = sample_standard_deviation(&samples) / 40f64.sqrt();
+        assert_close(
+            relative_confidence_half_width(&samples).unwrap(),
+            1.96 * standard_error / 1.5,
+        );
+        assert!(relative_confidence_half_width(&[1.0]).is_none());
+    }
+
+    #[test]
+    fn computes_geometric_means() {
+        assert_close(geometric_mean(&[1.0, 4.0]).unwrap(), 2.0);
+        assert_close(geometric_mean(&[2.0, 8.0, 4.0]).unwrap(), 4.0);
+        assert!(geometric_mean(&[1.0, 0.0]).is_none());
+        assert!(geometric_mean(&[]).is_none());
+    }
+
+    #[test]
+    fn summarizes_with_statistics() {
+        assert!(matches!("median".parse(), Ok(Statistic::Median)));
+        assert!(matches!("best-of-3".parse(), Ok(Statistic::BestOf(3))));
+        assert!("best-of-0".parse::<Statistic>().is_err());
+        assert!("mode".parse::<Statistic>().is_err());
+        assert_eq!(Statistic::BestOf(2).of(&[3.0, 2.0, 1.0]), Some(2.0));
+        assert_eq!(Statistic::Min.of(&[3.0, 2.0, 1.0]), Some(1.0));
+        assert_eq!(Statistic::Min.of(&[]), None);
+        assert_close(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_close(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+    }
+
+    #[test]
+    fn detects_bimodal_samples() {
+        let modes = bimodal_modes(&[1.0, 2.01, 0.99, 1.01, 2.0, 1.0, 1.99, 2.0]).unwrap();
+        assert_close(modes.low_mean, 1.0);
+        assert_eq!(modes.low_count, 4);
+        assert_close(modes.high_mean, 2.0);
+        assert_eq!(modes.high_count, 4);
+        assert!(modes.separation > 2.0);
+        assert!(bimodal_modes(&[1.0, 1.01, 0.99, 1.02, 0.98, 1.0]).is_none());
+        // too few samples for two modes
+        assert!(bimodal_modes(&[1.0, 2.0, 1.0, 2.0]).is_none());
+        // clearly separated, but too close together to matter
+        assert!(bimodal_modes(&[1.0, 1.0, 1.0, 1.01, 1.01, 1.01]).is_none());
+    }
+
+    #[test]
+    fn bootstraps_confidence_intervals() {
+        let constant = bootstrap(&[2.0, 2.0, 2.0], Statistic::Mean, 100).unwrap();
+        assert_close(constant.value, 2.0);
+        assert_close(constant.lower, 2.0);
+        assert_close(constant.upper, 2.0);
+        let samples = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let estimate = bootstrap(&samples, Statistic::Mean, 1000).unwrap();
+        assert!((estimate.value - 4.5).abs() < 0.2);
+        assert!(estimate.lower < 4.5 && 4.5 < estimate.upper);
+        assert!(estimate.lower >= 1.0 && estimate.upper <= 8.0);
+        // seeded, so the same samples always give the same interval
+        let again = bootstrap(&samples, Statistic::Mean, 1000).unwrap();
+        assert_eq!((again.lower, again.upper), (estimate.lower, estimate.upper));
+        assert!(bootstrap(&[1.0], Statistic::Mean, 100).is_none());
+        assert!(bootstrap(&samples, Statistic::Mean, 0).is_none());
+    }
+
+    #[test]
+    fn measures_outliers() {
+        assert_close(outlier_sigmas(5.0, &[1.0, 2.0, 3.0]).unwrap(), 3.0);
+        assert_close(outlier_sigmas(1.5, &[1.0, 2.0, 3.0]).unwrap(), 0.5);
+        assert!(outlier_sigmas(5.0, &[1.0, 2.0]).is_none());
+        assert!(outlier_sigmas(5.0, &[2.0, 2.0, 2.0]).is_none());
+    }
+}
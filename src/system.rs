@@ -0,0 +1,77 @@
+// The hardware and software a session ran on, recorded as metadata so results from different
+// machines or tool versions can be told apart.
+use std::process::{Command, Stdio};
+
+// the metadata keys describing the system, in the order they are recorded
+pub const SYSTEM_KEYS: [&str; 6] = [
+    "cpu_model",
+    "cores",
+    "memory_kib",
+    "kernel",
+    "make_version",
+    "cbmc_version",
+];
+
+fn cpu_model() -> Option<String> {
+    std::fs::read_to_string("/proc/cpuinfo")
+        .ok()?
+        .lines()
+        .find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some(value.trim().to_string()).filter(|_| key.trim() == "model name")
+        })
+}
+
+// online CPUs, unlike available_parallelism not limited by our CPU affinity
+fn cores() -> Option<String> {
+    // safe because sysconf has no preconditions
+    let cores = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    Some(cores.to_string()).filter(|_| cores > 0)
+}
+
+pub fn memory_kib() -> Option<String> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let total = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?;
+    Some(total.trim().strip_suffix("kB")?.trim().to_string())
+}
+
+fn kernel() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+// the first line program --version prints
+fn version(program: &str) -> Option<String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| output.status.success() && !line.is_empty())
+}
+
+// the system metadata we could find out, by key
+pub fn metadata() -> Vec<(String, String)> {
+    // the proof Makefiles run whatever CBMC names
+    let cbmc = std::env::var("CBMC").unwrap_or_else(|_| "cbmc".to_string());
+    SYSTEM_KEYS
+        .iter()
+        .zip([
+            cpu_model(),
+            cores(),
+            memory_kib(),
+            kernel(),
+            version("make"),
+            version(&cbmc),
+        ])
+        .filter_map(|(key, value)| Some((key.to_string(), value?)))
+        .collect()
+}
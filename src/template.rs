@@ -0,0 +1,179 @@
+// User-provided report templates, plain text (e.g. Markdown or HTML) with {{placeholders}} that
+// get replaced by the sections of the report, so teams can lay out reports their own way.
+use crate::GenericResult;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// the placeholders every report has, besides meta.<key> for the metadata of the session
+pub const PLACEHOLDERS: [&str; 8] = [
+    "note",
+    "statistic",
+    "proofs",
+    "failing_proofs",
+    "suite_score",
+    "slowest_proofs",
+    "most_variable_proofs",
+    "speedup",
+];
+
+const META_PREFIX: &str = "meta.";
+
+pub struct ReportTemplate {
+    template: String,
+    pub output: PathBuf,
+}
+
+// TEMPLATE=OUTPUT
+pub fn parse_template_arg(arg: &str) -> Result<(PathBuf, PathBuf), String> {
+    match arg.split_once('=') {
+        Some((template, output)) if !template.is_empty() && !output.is_empty() => {
+            Ok((PathBuf::from(template), PathBuf::from(output)))
+        }
+        _ => Err(format!(
+            "invalid report template '{}', expected TEMPLATE=OUTPUT",
+            arg
+        )),
+    }
+}
+
+// the text before the next placeholder, the name in it and the text after it, None if there is
+// no placeholder left
+fn next_placeholder(text: &str) -> Option<Result<(&str, &str, &str), String>> {
+    let start = text.find("{{")?;
+    Some(match text[start + 2..].find("}}") {
+        Some(length) => Ok((
+            &text[..start],
+            text[start + 2..start + 2 + length].trim(),
+            &text[start + 2 + length + 2..],
+        )),
+        None => Err("unterminated {{".to_string()),
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl ReportTemplate {
+    // read the template and check its placeholders, so a typo doesn't only show up at the end of
+    // a session
+    pub fn load(template_path: &Path, output: &Path) -> GenericResult<ReportTemplate> {
+        let template = std::fs::read_to_string(template_path)
+            .map_err(|err| format!("can't read {}: {}", template_path.display(), err))?;
+        let mut rest = template.as_str();
+        while let Some(placeholder) = next_placeholder(rest) {
+            let (_, name, after) =
+                placeholder.map_err(|err| format!("{}: {}", template_path.display(), err))?;
+            if !PLACEHOLDERS.contains(&name) && !name.starts_with(META_PREFIX) {
+                return Err(format!(
+                    "{}: unknown placeholder {{{{{}}}}}, expected one of {} or meta.<key>",
+                    template_path.display(),
+                    name,
+                    PLACEHOLDERS.join(", ")
+                )
+                .into());
+            }
+            rest = after;
+        }
+        Ok(ReportTemplate {
+            template,
+            output: output.to_path_buf(),
+        })
+    }
+
+    // the template with its placeholders replaced by values, escaped for HTML if the output is an
+    // HTML file; metadata that wasn't recorded is left empty
+    pub fn render(&self, values: &HashMap<String, String>) -> String {
+        let html = matches!(
+            self.output
+                .extension()
+                .and_then(|extension| extension.to_str()),
+            Some("html") | Some("htm")
+        );
+        let mut rendered = String::new();
+        let mut rest = self.template.as_str();
+        while let Some(Ok((before, name, after))) = next_placeholder(rest) {
+            rendered += before;
+            let value = values.get(name).map_or("", String::as_str);
+            if html {
+                rendered += &escape_html(value);
+            } else {
+                rendered += value;
+            }
+            rest = after;
+        }
+        rendered + rest
+    }
+}
+
+// the values of the meta.<key> placeholders
+pub fn meta_values(meta: &[(String, String)]) -> impl Iterator<Item = (String, String)> + '_ {
+    meta.iter()
+        .map(|(key, value)| (format!("{}{}", META_PREFIX, key), value.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("benchmark-test-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn loads_and_renders_templates() {
+        let template_path = test_path("template.md");
+        std::fs::write(
+            &template_path,
+            "# {{ note }} on {{meta.host}}\n{{proofs}}\n{{meta.missing}}end\n",
+        )
+        .unwrap();
+        let values: HashMap<String, String> =
+            meta_values(&[("host".to_string(), "ci".to_string())])
+                .chain(vec![
+                    ("note".to_string(), "Nightly".to_string()),
+                    ("proofs".to_string(), "<b>2</b> & more".to_string()),
+                ])
+                .collect();
+
+        let markdown = ReportTemplate::load(&template_path, Path::new("report.md")).unwrap();
+        assert_eq!(
+            markdown.render(&values),
+            "# Nightly on ci\n<b>2</b> & more\nend\n"
+        );
+        let html = ReportTemplate::load(&template_path, Path::new("report.html")).unwrap();
+        assert_eq!(
+            html.render(&values),
+            "# Nightly on ci\n&lt;b&gt;2&lt;/b&gt; &amp; more\nend\n"
+        );
+
+        std::fs::write(&template_path, "{{typo}}").unwrap();
+        let err = ReportTemplate::load(&template_path, Path::new("report.md"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("unknown placeholder {{typo}}"));
+        std::fs::write(&template_path, "{{note").unwrap();
+        let err = ReportTemplate::load(&template_path, Path::new("report.md"))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.ends_with(": unterminated {{"));
+        std::fs::remove_file(&template_path).unwrap();
+        assert!(ReportTemplate::load(&template_path, Path::new("report.md")).is_err());
+    }
+
+    #[test]
+    fn parses_template_args() {
+        assert_eq!(
+            parse_template_arg("in.md=out.md"),
+            Ok((PathBuf::from("in.md"), PathBuf::from("out.md")))
+        );
+        assert!(parse_template_arg("in.md").is_err());
+        assert!(parse_template_arg("=out.md").is_err());
+        assert!(parse_template_arg("in.md=").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+// How long the CBMC tools themselves take in a run, split into building the goto binaries and the
+// analysis, by overriding the make variables of the proof Makefiles that name the tools with this
+// program (as the hidden time-tool subcommand), which times every invocation.
+use crate::GenericResult;
+use std::fs::OpenOptions;
+use std::io::{Result as IOResult, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+// the file the time-tool subcommand appends the time of every invocation to
+pub const TOOL_TIMES_VARIABLE: &str = "BENCH_TOOL_TIMES";
+
+const GOTO: &str = "goto";
+const CBMC: &str = "cbmc";
+
+// the make variable naming a tool, the tool by default, and what its time counts as
+const TOOLS: [(&str, &str, &str); 3] = [
+    ("GOTO_CC", "goto-cc", GOTO),
+    ("GOTO_INSTRUMENT", "goto-instrument", GOTO),
+    ("CBMC", "cbmc", CBMC),
+];
+
+// summed over all invocations in a run, which overlap with make -j
+#[derive(Clone, Default)]
+pub struct ToolTimes {
+    // goto-cc and goto-instrument
+    pub goto: Option<Duration>,
+    pub cbmc: Option<Duration>,
+}
+
+#[derive(StructOpt)]
+#[structopt(setting = structopt::clap::AppSettings::TrailingVarArg)]
+pub struct TimeToolArguments {
+    /// what the time of the command counts as
+    kind: String,
+    #[structopt(required = true, allow_hyphen_values = true)]
+    command: Vec<String>,
+}
+
+// word as a single word for a shell, in single quotes
+pub fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+// the make arguments that have the tools run through time-tool, keeping tools that are overridden
+// in the environment
+pub fn make_arguments() -> IOResult<Vec<String>> {
+    let program = std::env::current_exe()?;
+    let program = shell_quote(&program.to_string_lossy());
+    Ok(TOOLS
+        .iter()
+        .map(|(variable, tool, kind)| {
+            let tool = std::env::var(variable).unwrap_or_else(|_| tool.to_string());
+            format!("{}={} time-tool {} {}", variable, program, kind, tool)
+        })
+        .collect())
+}
+
+// run the command and append how long it took to the file in BENCH_TOOL_TIMES, exiting like the
+// command did
+pub fn time_tool(args: &TimeToolArguments) -> GenericResult<()> {
+    let start_time = Instant::now();
+    let status = Command::new(&args.command[0])
+        .args(&args.command[1..])
+        .status()
+        .map_err(|err| format!("can't run {}: {}", args.command[0], err))?;
+    let elapsed = start_time.elapsed();
+    if let Some(times_path) = std::env::var_os(TOOL_TIMES_VARIABLE) {
+        // a single short append, so parallel invocations don't mix up their lines
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(times_path)?
+            .write_all(format!("{} {}\n", args.kind, elapsed.as_secs_f64()).as_bytes())?;
+    }
+    use std::os::unix::process::ExitStatusExt;
+    std::process::exit(
+        status
+            .code()
+            .or_else(|| status.signal().map(|signal| 128 + signal))
+            .unwrap_or(1),
+    )
+}
+
+// the times in a file written by time-tool, with lines like goto 1.25
+pub fn parse_file(path: &Path) -> IOResult<ToolTimes> {
+    let mut times = ToolTimes::default();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let (kind, seconds) = match line.split_once(' ') {
+            Some(kind_seconds) => kind_seconds,
+            None => continue,
+        };
+        let time = match seconds.parse::<f64>().map(Duration::try_from_secs_f64) {
+            Ok(Ok(time)) => time,
+            _ => continue,
+        };
+        let total = match kind {
+            GOTO => &mut times.goto,
+            CBMC => &mut times.cbmc,
+            _ => continue,
+        };
+        *total = Some(total.unwrap_or_default().saturating_add(time));
+    }
+    Ok(times)
+}
+
+// where time-tool writes the times of the run with run_id
+pub fn times_path(run_id: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("benchmark-tools-{}.txt", run_id))
+}
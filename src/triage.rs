@@ -0,0 +1,89 @@
+// Every failed run of a session in a single JSON file, for tools that file tickets for failures
+// without having to parse the csv files or the logs.
+use crate::progress::json_string;
+use crate::results::Exit;
+use std::io::Result as IOResult;
+use std::path::{Path, PathBuf};
+
+// how many lines from the end of the log of a failed run go into the failures file
+pub const LOG_TAIL_LINES: usize = 20;
+
+pub struct Failure {
+    pub proof: String,
+    // the number of the run among the measured runs of the proof, starting at 1
+    pub run: usize,
+    pub run_id: String,
+    pub exit: Option<Exit>,
+    // only with --log-dir
+    pub log: Option<PathBuf>,
+    pub last_lines: Vec<String>,
+}
+
+// the kind of failure, for deciding who to file it with
+fn classification(exit: Option<Exit>) -> &'static str {
+    match exit {
+        Some(Exit::Code(_)) => "error",
+        Some(Exit::Signal(_)) => "crash",
+        Some(Exit::MemoryLimit) => "memory-limit",
+        Some(Exit::Timeout) => "timeout",
+        None => "not-started",
+    }
+}
+
+// the last count lines of the (not yet compressed) log file of a run
+pub fn last_lines(log_path: &Path, count: usize) -> IOResult<Vec<String>> {
+    let output = std::fs::read(log_path)?;
+    let output = String::from_utf8_lossy(&output);
+    let lines: Vec<&str> = output.lines().collect();
+    Ok(lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+fn optional_json<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+impl Failure {
+    fn to_json(&self) -> String {
+        let (exit_code, signal) = match self.exit {
+            Some(Exit::Code(code)) => (Some(code), None),
+            Some(Exit::Signal(signal)) => (None, Some(signal)),
+            _ => (None, None),
+        };
+        let last_lines: Vec<String> = self
+            .last_lines
+            .iter()
+            .map(|line| json_string(line))
+            .collect();
+        format!(
+            "{{\"proof\":{},\"run\":{},\"run_id\":{},\"classification\":\"{}\",\"exit_code\":{},\"signal\":{},\"log\":{},\"last_lines\":[{}]}}",
+            json_string(&self.proof),
+            self.run,
+            json_string(&self.run_id),
+            classification(self.exit),
+            optional_json(exit_code),
+            optional_json(signal),
+            optional_json(
+                self.log
+                    .as_ref()
+                    .map(|log| json_string(&log.display().to_string()))
+            ),
+            last_lines.join(",")
+        )
+    }
+}
+
+// a JSON array with one object per failure, empty if nothing failed
+pub fn write(path: &Path, failures: &[Failure]) -> IOResult<()> {
+    let failures: Vec<String> = failures
+        .iter()
+        .map(|failure| format!("  {}", failure.to_json()))
+        .collect();
+    if failures.is_empty() {
+        std::fs::write(path, "[]\n")
+    } else {
+        std::fs::write(path, format!("[\n{}\n]\n", failures.join(",\n")))
+    }
+}